@@ -0,0 +1,247 @@
+use crate::auth::AuthManager;
+use crate::monitor::{ProcessInfo, TerminationStrategy};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path};
+use std::time::Duration;
+
+/// Session and system binaries that are always allowed, by file name, when
+/// installed in a system directory. A trailing `*` matches any suffix.
+const SAFE_PROCESSES: &[&str] = &[
+    // Init, bus and login
+    "systemd", "(sd-pam)", "dbus-daemon", "dbus-broker", "dbus-broker-launch", "dbus-launch",
+    "login", "sshd", "gdm*", "sddm*", "lightdm*", "polkit*", "gnome-keyring-daemon",
+    "ssh-agent", "gpg-agent", "xdg-*", "at-spi*", "ibus*", "fcitx*",
+    // Display servers, shells and compositors
+    "Xorg", "Xwayland", "gnome-shell", "gnome-session*", "gsd-*", "mutter*", "plasmashell",
+    "kwin*", "ksmserver", "kded*", "kglobalaccel*", "xfce4-*", "xfwm4", "xfsettingsd",
+    "cinnamon*", "mate-*", "sway", "swaybg", "Hyprland", "waybar", "budgie-*",
+    // Audio and desktop services
+    "pipewire*", "wireplumber", "pulseaudio", "gvfs*", "evolution-*", "tracker-miner*",
+    "localsearch*", "goa-*", "dconf-service", "nm-applet", "blueman-*", "notify-osd",
+    // Shells, so a terminal that is allowed stays usable
+    "sh", "bash", "zsh", "fish", "dash",
+    // Ficha itself and its webview
+    "ficha*", "WebKit*",
+];
+
+/// Which processes the shield acts on while locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyMode {
+    /// Only the protected apps are enforced
+    Blocklist,
+    /// Everything the user runs is enforced unless it is allowed
+    Allowlist,
+}
+
+impl PolicyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyMode::Blocklist => "blocklist",
+            PolicyMode::Allowlist => "allowlist",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "blocklist" => Some(PolicyMode::Blocklist),
+            "allowlist" => Some(PolicyMode::Allowlist),
+            _ => None,
+        }
+    }
+}
+
+/// An executable allowed to run while locked in allowlist mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedExecutable {
+    pub id: i64,
+    pub name: String,
+    /// Empty when any binary with this name is allowed, which is only ever
+    /// set up by hand
+    pub exe_path: String,
+    /// "manual" or "learned"
+    pub source: String,
+    pub created_at: String,
+}
+
+/// What the settings screen shows about allowlist mode
+#[derive(Debug, Clone, Serialize)]
+pub struct AllowlistConfig {
+    pub mode: PolicyMode,
+    /// Set while processes are still being learned; not enforced until then
+    pub learning_until: Option<String>,
+    pub strategy: TerminationStrategy,
+    pub entries: Vec<AllowedExecutable>,
+    pub safe_processes: Vec<String>,
+}
+
+/// Allowlist mode as the monitor enforces it
+#[derive(Debug, Clone)]
+pub struct Allowlist {
+    uid: u32,
+    names: HashSet<String>,
+    paths: HashSet<String>,
+    pub strategy: TerminationStrategy,
+    pub grace_period: Duration,
+}
+
+impl Allowlist {
+    pub fn new(entries: &[AllowedExecutable], strategy: TerminationStrategy, grace_period: Duration) -> Self {
+        let mut names = HashSet::new();
+        let mut paths = HashSet::new();
+        for entry in entries {
+            if entry.exe_path.is_empty() {
+                names.insert(entry.name.clone());
+            } else {
+                paths.insert(entry.exe_path.clone());
+            }
+        }
+
+        Allowlist {
+            uid: AuthManager::desktop_uid(),
+            names,
+            paths,
+            strategy,
+            grace_period,
+        }
+    }
+
+    /// Whether the process belongs to the desktop user, the only one
+    /// allowlist mode applies to
+    pub fn applies_to(&self, process: &ProcessInfo) -> bool {
+        process_uid(process.pid) == Some(self.uid)
+    }
+
+    pub fn allows(&self, process: &ProcessInfo) -> bool {
+        if is_safe_process(process) || self.names.contains(&process.name) {
+            return true;
+        }
+        match &process.exe_path {
+            Some(path) => self.paths.contains(path),
+            None => false,
+        }
+    }
+}
+
+/// Safe when the process runs a binary of the safe set from a system
+/// directory. Names prove nothing on their own: comm can be changed with
+/// `PR_SET_NAME` and any binary can be copied to a safe name.
+pub fn is_safe_process(process: &ProcessInfo) -> bool {
+    let exe = match process.exe_path.as_deref() {
+        Some(exe) => Path::new(exe),
+        None => return false,
+    };
+    // The link text of a deleted binary no longer names it, and a process in
+    // another mount namespace may see a different file at the same path
+    let same_file = match (fs::metadata(format!("/proc/{}/exe", process.pid)), fs::metadata(exe)) {
+        (Ok(running), Ok(installed)) => running.dev() == installed.dev() && running.ino() == installed.ino(),
+        _ => false,
+    };
+    same_file && is_safe_exe(exe)
+}
+
+/// A root-owned binary under `/usr/bin`, `/usr/sbin`, `/usr/lib*` or
+/// `/usr/libexec` whose file name is in the safe set
+pub fn is_safe_exe(exe: &Path) -> bool {
+    let mut components = exe.components();
+    let in_system_dir = components.next() == Some(Component::RootDir)
+        && components.next() == Some(Component::Normal("usr".as_ref()))
+        && match components.next() {
+            Some(Component::Normal(dir)) => {
+                let dir = dir.to_string_lossy();
+                dir == "bin" || dir == "sbin" || dir.starts_with("lib")
+            }
+            _ => false,
+        }
+        && components.all(|component| matches!(component, Component::Normal(_)));
+    if !in_system_dir {
+        return false;
+    }
+
+    let name = exe.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    is_safe_name(name)
+        && fs::metadata(exe).is_ok_and(|meta| meta.is_file() && meta.uid() == 0 && meta.mode() & 0o022 == 0)
+}
+
+fn is_safe_name(name: &str) -> bool {
+    SAFE_PROCESSES.iter().any(|safe| match safe.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == *safe,
+    })
+}
+
+pub fn safe_processes() -> Vec<String> {
+    SAFE_PROCESSES.iter().map(|name| name.to_string()).collect()
+}
+
+/// Real UID owning /proc/[pid]
+pub fn process_uid(pid: i32) -> Option<u32> {
+    fs::metadata(format!("/proc/{}", pid)).ok().map(|meta| meta.uid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, exe_path: Option<&str>) -> ProcessInfo {
+        ProcessInfo {
+            pid: std::process::id() as i32,
+            name: name.to_string(),
+            exe_path: exe_path.map(String::from),
+            ppid: 1,
+            session: 0,
+            cmdline: vec![name.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_allowlist_matches_names_paths_and_safe_set() {
+        let entry = |name: &str, exe_path: &str| AllowedExecutable {
+            id: 0,
+            name: name.to_string(),
+            exe_path: exe_path.to_string(),
+            source: "manual".to_string(),
+            created_at: String::new(),
+        };
+        let allowlist = Allowlist::new(
+            &[entry("code", ""), entry("firefox", "/usr/lib/firefox/firefox")],
+            TerminationStrategy::Kill,
+            Duration::from_secs(5),
+        );
+
+        assert!(allowlist.allows(&process("code", Some("/opt/code/code"))));
+        assert!(allowlist.allows(&process("firefox", Some("/usr/lib/firefox/firefox"))));
+        // Same name, different binary
+        assert!(!allowlist.allows(&process("firefox", Some("/tmp/firefox"))));
+        assert!(!allowlist.allows(&process("steam", None)));
+        assert!(!allowlist.allows(&process("gsd-power", None)));
+        assert!(!allowlist.allows(&process("bash", None)));
+
+
+        // Only the desktop user's processes, whoever that is where the test runs
+        let own_uid = process_uid(std::process::id() as i32).unwrap();
+        let mine = Allowlist { uid: own_uid, ..allowlist.clone() };
+        let someone_elses = Allowlist { uid: own_uid + 1, ..allowlist };
+        assert!(mine.applies_to(&process("code", None)));
+        assert!(!someone_elses.applies_to(&process("code", None)));
+    }
+
+    #[test]
+    fn test_safe_set_needs_a_system_binary() {
+        assert!(is_safe_exe(Path::new("/usr/bin/bash")));
+        assert!(!is_safe_exe(Path::new("/usr/bin/steam")));
+        // Copied or renamed binaries outside the system directories
+        assert!(!is_safe_exe(Path::new("/tmp/bash")));
+        assert!(!is_safe_exe(Path::new("/home/user/gsd-steam")));
+        assert!(!is_safe_exe(Path::new("/usr/bin/../../tmp/bash")));
+        assert!(!is_safe_exe(Path::new("/usr/local/bin/bash")));
+
+        // A name set with prctl, or a path the process doesn't actually run
+        assert!(!is_safe_process(&process("sh", None)));
+        assert!(!is_safe_process(&process("gsd-power", Some("/tmp/gsd-power"))));
+        assert!(!is_safe_process(&process("bash", Some("/usr/bin/bash"))));
+    }
+}
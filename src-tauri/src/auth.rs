@@ -1,19 +1,124 @@
+use crate::conversation::PromptRelay;
+use crate::database::Database;
+use crate::totp::{SecondFactor, TotpManager};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
 use std::env;
+use std::fs;
+use std::path::Path;
+
+const AUTH_METHOD_SETTING: &str = "auth_method";
+const PASSPHRASE_HASH_SETTING: &str = "vault_passphrase_hash";
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+const PAM_SERVICE_SETTING: &str = "pam_service";
+/// Installed as /etc/pam.d/ficha by the packages
+const DEFAULT_PAM_SERVICE: &str = "ficha";
+/// Used when the ficha service file isn't installed
+const FALLBACK_PAM_SERVICE: &str = "login";
+const PAM_DIRS: &[&str] = &["/etc/pam.d", "/usr/lib/pam.d", "/usr/etc/pam.d"];
+/// logind's state for the primary seat, including who is using it
+const SEAT0_STATE: &str = "/run/systemd/seats/seat0";
+
+/// What the lock screen checks the unlock secret against
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// The user's login password, through PAM
+    System,
+    /// A dedicated passphrase, verified against an Argon2id hash
+    Passphrase,
+}
+
+impl AuthMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthMethod::System => "system",
+            AuthMethod::Passphrase => "passphrase",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "system" => Some(AuthMethod::System),
+            "passphrase" => Some(AuthMethod::Passphrase),
+            _ => None,
+        }
+    }
+}
+
+/// Auth settings for the lock screen, safe to expose before unlocking
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthConfig {
+    pub method: AuthMethod,
+    pub passphrase_set: bool,
+    pub system_available: bool,
+    /// Unlocking also needs a TOTP or recovery code
+    pub totp_enabled: bool,
+    pub pam_service: String,
+}
+
+/// PAM conversation: the first hidden prompt gets the password from the
+/// lock screen, anything after that goes to the UI
+#[cfg(feature = "pam-auth")]
+struct Conversation<'a> {
+    username: String,
+    password: Option<String>,
+    relay: &'a dyn PromptRelay,
+}
+
+#[cfg(feature = "pam-auth")]
+impl pam::Converse for Conversation<'_> {
+    fn prompt_echo(&mut self, msg: &std::ffi::CStr) -> Result<std::ffi::CString, ()> {
+        let answer = self.relay.prompt(&msg.to_string_lossy(), true).ok_or(())?;
+        std::ffi::CString::new(answer).map_err(|_| ())
+    }
+
+    fn prompt_blind(&mut self, msg: &std::ffi::CStr) -> Result<std::ffi::CString, ()> {
+        let answer = match self.password.take() {
+            Some(password) => password,
+            None => self.relay.prompt(&msg.to_string_lossy(), false).ok_or(())?,
+        };
+        std::ffi::CString::new(answer).map_err(|_| ())
+    }
+
+    fn info(&mut self, msg: &std::ffi::CStr) {
+        self.relay.message(&msg.to_string_lossy(), false);
+    }
+
+    fn error(&mut self, msg: &std::ffi::CStr) {
+        self.relay.message(&msg.to_string_lossy(), true);
+    }
+
+    fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+/// Outcome of checking every unlock factor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnlockCheck {
+    Denied,
+    Granted { second_factor: Option<SecondFactor> },
+}
 
 pub struct AuthManager;
 
 impl AuthManager {
-    /// Authenticate a user against the system using PAM (if available)
+    /// Authenticate a user against the system using PAM (if available).
+    /// Prompts beyond the password are relayed through `relay`.
     /// Falls back to simple password check for development/testing
     #[cfg(feature = "pam-auth")]
-    pub fn authenticate(username: &str, password: &str) -> Result<bool, String> {
+    pub fn authenticate(service: &str, username: &str, password: &str, relay: &dyn PromptRelay) -> Result<bool, String> {
         use pam::Authenticator;
 
-        let mut auth = Authenticator::with_password("system-auth")
-            .map_err(|e| format!("Failed to initialize PAM: {}", e))?;
-
-        // Set the username and password
-        auth.get_handler().set_credentials(username, password);
+        let conversation = Conversation {
+            username: username.to_string(),
+            password: Some(password.to_string()),
+            relay,
+        };
+        let mut auth = Authenticator::with_handler(service, conversation)
+            .map_err(|e| format!("Failed to initialize PAM service '{}': {}", service, e))?;
 
         // Attempt authentication
         match auth.authenticate() {
@@ -28,7 +133,7 @@ impl AuthManager {
     /// Fallback authentication for development (when PAM is not available)
     /// WARNING: This is NOT secure and should only be used for development/testing
     #[cfg(not(feature = "pam-auth"))]
-    pub fn authenticate(_username: &str, password: &str) -> Result<bool, String> {
+    pub fn authenticate(_service: &str, _username: &str, password: &str, _relay: &dyn PromptRelay) -> Result<bool, String> {
         // For development, accept any password longer than 3 characters
         // In production with PAM enabled, this will not be used
         Ok(password.len() > 3)
@@ -40,11 +145,190 @@ impl AuthManager {
             .map_err(|_| "Could not determine current user".to_string())
     }
 
+    /// UID of the desktop user Ficha protects. Started through sudo or
+    /// pkexec (e.g. for the pre-exec backend) that is the user who elevated,
+    /// not root; otherwise a root instance takes the active user of seat0.
+    pub fn desktop_uid() -> u32 {
+        let uid = nix::unistd::getuid().as_raw();
+        if uid != 0 {
+            return uid;
+        }
+        ["SUDO_UID", "PKEXEC_UID"]
+            .iter()
+            .find_map(|var| env::var(var).ok()?.parse().ok())
+            .or_else(|| Self::parse_active_uid(&fs::read_to_string(SEAT0_STATE).ok()?))
+            .unwrap_or(uid)
+    }
+
+    fn parse_active_uid(seat_state: &str) -> Option<u32> {
+        seat_state
+            .lines()
+            .find_map(|line| line.strip_prefix("ACTIVE_UID="))?
+            .trim()
+            .parse()
+            .ok()
+    }
+
     /// Authenticate using the current logged-in user
-    pub fn authenticate_current_user(password: &str) -> Result<bool, String> {
+    pub fn authenticate_current_user(service: &str, password: &str, relay: &dyn PromptRelay) -> Result<bool, String> {
         let username = Self::get_current_user()?;
-        Self::authenticate(&username, password)
+        Self::authenticate(service, &username, password, relay)
+    }
+
+    fn pam_service_exists(service: &str) -> bool {
+        PAM_DIRS.iter().any(|dir| Path::new(dir).join(service).is_file())
+    }
+
+    /// The configured PAM service, else ficha's own when installed, else login
+    pub fn pam_service(database: &Database) -> Result<String, String> {
+        if let Some(service) = database.get_setting(PAM_SERVICE_SETTING).map_err(|e| e.to_string())? {
+            return Ok(service);
+        }
+        Ok(if Self::pam_service_exists(DEFAULT_PAM_SERVICE) {
+            DEFAULT_PAM_SERVICE
+        } else {
+            FALLBACK_PAM_SERVICE
+        }
+        .to_string())
     }
+
+    pub fn set_pam_service(database: &Database, service: &str) -> Result<(), String> {
+        let valid = !service.is_empty()
+            && service
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !service.starts_with('.');
+        if !valid {
+            return Err(format!("Invalid PAM service name '{}'", service));
+        }
+        if !Self::pam_service_exists(service) {
+            return Err(format!("PAM service '{}' is not configured in /etc/pam.d", service));
+        }
+        database
+            .set_setting(PAM_SERVICE_SETTING, service)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Whether system (PAM) authentication was compiled in
+    pub fn system_available() -> bool {
+        cfg!(feature = "pam-auth")
+    }
+
+    /// The configured method. Without PAM, system auth would be the
+    /// development fallback, so the passphrase is used instead.
+    pub fn method(database: &Database) -> Result<AuthMethod, String> {
+        let stored = database
+            .get_setting(AUTH_METHOD_SETTING)
+            .map_err(|e| e.to_string())?
+            .and_then(|value| AuthMethod::parse(&value));
+
+        Ok(match stored {
+            Some(AuthMethod::System) | None if Self::system_available() => AuthMethod::System,
+            _ => AuthMethod::Passphrase,
+        })
+    }
+
+    pub fn config(database: &Database) -> Result<AuthConfig, String> {
+        Ok(AuthConfig {
+            method: Self::method(database)?,
+            passphrase_set: Self::passphrase_hash(database)?.is_some(),
+            system_available: Self::system_available(),
+            totp_enabled: TotpManager::is_enabled(database)?,
+            pam_service: Self::pam_service(database)?,
+        })
+    }
+
+    pub fn set_method(database: &Database, method: AuthMethod) -> Result<(), String> {
+        if method == AuthMethod::System && !Self::system_available() {
+            return Err("System authentication is not available in this build".to_string());
+        }
+        if method == AuthMethod::Passphrase && Self::passphrase_hash(database)?.is_none() {
+            return Err("Set a vault passphrase before switching to it".to_string());
+        }
+        database
+            .set_setting(AUTH_METHOD_SETTING, method.as_str())
+            .map_err(|e| e.to_string())
+    }
+
+    fn passphrase_hash(database: &Database) -> Result<Option<String>, String> {
+        database.get_setting(PASSPHRASE_HASH_SETTING).map_err(|e| e.to_string())
+    }
+
+    /// Check the unlock secret with the configured method, then the TOTP
+    /// code when a second factor is enrolled
+    pub fn verify_unlock(
+        database: &Database,
+        secret: &str,
+        totp_code: Option<&str>,
+        relay: &dyn PromptRelay,
+    ) -> Result<UnlockCheck, String> {
+        if !Self::verify_secret(database, secret, relay)? {
+            return Ok(UnlockCheck::Denied);
+        }
+        if !TotpManager::is_enabled(database)? {
+            return Ok(UnlockCheck::Granted { second_factor: None });
+        }
+
+        let code = match totp_code.filter(|code| !code.trim().is_empty()) {
+            Some(code) => code,
+            None => return Ok(UnlockCheck::Denied),
+        };
+        Ok(match TotpManager::verify(database, &Self::get_current_user()?, code)? {
+            Some(factor) => UnlockCheck::Granted { second_factor: Some(factor) },
+            None => UnlockCheck::Denied,
+        })
+    }
+
+    /// Check the first factor with the configured method. Errors when the
+    /// passphrase method is active but no passphrase has been set up.
+    fn verify_secret(database: &Database, secret: &str, relay: &dyn PromptRelay) -> Result<bool, String> {
+        match Self::method(database)? {
+            AuthMethod::System => Self::authenticate_current_user(&Self::pam_service(database)?, secret, relay),
+            AuthMethod::Passphrase => match Self::passphrase_hash(database)? {
+                Some(hash) => verify_passphrase(&hash, secret),
+                None => Err("No vault passphrase has been set up".to_string()),
+            },
+        }
+    }
+
+    /// Check a secret against the stored passphrase, whatever the method
+    pub fn verify_passphrase(database: &Database, passphrase: &str) -> Result<bool, String> {
+        match Self::passphrase_hash(database)? {
+            Some(hash) => verify_passphrase(&hash, passphrase),
+            None => Ok(false),
+        }
+    }
+
+    /// Store a new passphrase hash, replacing any existing one
+    pub fn set_passphrase(database: &Database, passphrase: &str) -> Result<(), String> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(format!(
+                "Passphrase must be at least {} characters",
+                MIN_PASSPHRASE_LEN
+            ));
+        }
+        let hash = hash_passphrase(passphrase)?;
+        database
+            .set_setting(PASSPHRASE_HASH_SETTING, &hash)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Argon2id hash in PHC string format, with a random salt
+pub fn hash_passphrase(passphrase: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut rand_core::OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash passphrase: {}", e))
+}
+
+pub fn verify_passphrase(hash: &str, passphrase: &str) -> Result<bool, String> {
+    let parsed = PasswordHash::new(hash)
+        .map_err(|e| format!("Stored passphrase hash is invalid: {}", e))?;
+    Ok(Argon2::default()
+        .verify_password(passphrase.as_bytes(), &parsed)
+        .is_ok())
 }
 
 #[cfg(test)]
@@ -57,4 +341,22 @@ mod tests {
         assert!(user.is_ok(), "Should be able to get current user");
         assert!(!user.unwrap().is_empty(), "Username should not be empty");
     }
+
+    #[test]
+    fn test_parse_active_uid() {
+        let seat = "# This is private data. Do not parse.\nIS_SEAT0=1\nACTIVE=c2\nACTIVE_UID=1000\nSESSIONS=c2 3\n";
+        assert_eq!(AuthManager::parse_active_uid(seat), Some(1000));
+        assert_eq!(AuthManager::parse_active_uid("IS_SEAT0=1\n"), None);
+    }
+
+    #[test]
+    fn test_passphrase_hash_roundtrip() {
+        let hash = hash_passphrase("correct horse battery").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert_ne!(hash, hash_passphrase("correct horse battery").unwrap());
+
+        assert_eq!(verify_passphrase(&hash, "correct horse battery"), Ok(true));
+        assert_eq!(verify_passphrase(&hash, "wrong horse battery"), Ok(false));
+        assert!(verify_passphrase("not a hash", "anything").is_err());
+    }
 }
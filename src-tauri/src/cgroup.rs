@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Freezes protected apps with the cgroup v2 freezer so they can be resumed
+/// with their state intact after the next unlock.
+///
+/// Frozen processes are moved into a Ficha-managed subtree, one leaf per app.
+pub struct CgroupFreezer {
+    /// Leaf cgroup -> (pid, cgroup it was moved out of)
+    frozen: HashMap<PathBuf, Vec<(i32, String)>>,
+}
+
+impl CgroupFreezer {
+    pub fn new() -> Self {
+        CgroupFreezer {
+            frozen: HashMap::new(),
+        }
+    }
+
+    /// The unified-hierarchy cgroup path of `pid`, e.g. `/user.slice/...`
+    pub fn cgroup_of(pid: i32) -> Option<String> {
+        let content = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .map(|path| path.to_string())
+    }
+
+    /// Ficha's subtree: at the root when running as root, otherwise under the
+    /// `user@UID.service` cgroup systemd delegates to the user
+    fn base_dir() -> Result<PathBuf, String> {
+        if !Path::new(CGROUP_ROOT).join("cgroup.controllers").exists() {
+            return Err("cgroup v2 is not mounted".to_string());
+        }
+
+        if nix::unistd::geteuid().is_root() {
+            return Ok(Path::new(CGROUP_ROOT).join("ficha.frozen"));
+        }
+
+        let own = Self::cgroup_of(std::process::id() as i32)
+            .ok_or("could not read own cgroup")?;
+        Self::delegated_base(&own).ok_or_else(|| "no delegated user@.service cgroup".to_string())
+    }
+
+    fn delegated_base(own_cgroup: &str) -> Option<PathBuf> {
+        let mut path = PathBuf::from(CGROUP_ROOT);
+        for component in own_cgroup.trim_start_matches('/').split('/') {
+            path.push(component);
+            if component.starts_with("user@") && component.ends_with(".service") {
+                return Some(path.join("ficha-frozen.slice"));
+            }
+        }
+        None
+    }
+
+    /// Move `pids` into the app's leaf cgroup and freeze it.
+    /// Returns the PIDs that were actually frozen.
+    pub fn freeze(&mut self, process_name: &str, pids: &[i32]) -> Result<Vec<i32>, String> {
+        let leaf = Self::base_dir()?.join(Self::leaf_name(process_name));
+
+        fs::create_dir_all(&leaf)
+            .map_err(|e| format!("Failed to create {:?}: {}", leaf, e))?;
+
+        let mut moved = Vec::new();
+        for &pid in pids {
+            let original = match Self::cgroup_of(pid) {
+                Some(original) => original,
+                None => continue,
+            };
+            match fs::write(leaf.join("cgroup.procs"), pid.to_string()) {
+                Ok(()) => moved.push((pid, original)),
+                Err(e) => eprintln!("Failed to move PID {} into {:?}: {}", pid, leaf, e),
+            }
+        }
+
+        if moved.is_empty() {
+            let _ = fs::remove_dir(&leaf);
+            return Err("could not move any process into the freezer cgroup".to_string());
+        }
+
+        fs::write(leaf.join("cgroup.freeze"), "1")
+            .map_err(|e| format!("Failed to freeze {:?}: {}", leaf, e))?;
+
+        let frozen: Vec<i32> = moved.iter().map(|(pid, _)| *pid).collect();
+        self.frozen.entry(leaf).or_default().extend(moved);
+        Ok(frozen)
+    }
+
+    fn leaf_name(process_name: &str) -> String {
+        let slug: String = process_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        format!("{}.scope", slug)
+    }
+
+    /// Thaw every frozen app and move its processes back where they came from.
+    /// Returns the number of PIDs thawed.
+    pub fn thaw_all(&mut self) -> usize {
+        self.frozen
+            .drain()
+            .map(|(leaf, members)| Self::thaw_leaf(&leaf, &members))
+            .sum()
+    }
+
+    /// Thaw one app, e.g. once its blocked window is over.
+    /// Returns the number of PIDs thawed.
+    pub fn thaw(&mut self, process_name: &str) -> usize {
+        let name = Self::leaf_name(process_name);
+        let leaves: Vec<PathBuf> = self
+            .frozen
+            .keys()
+            .filter(|leaf| leaf.file_name().is_some_and(|file| file == name.as_str()))
+            .cloned()
+            .collect();
+        leaves
+            .into_iter()
+            .filter_map(|leaf| self.frozen.remove_entry(&leaf))
+            .map(|(leaf, members)| Self::thaw_leaf(&leaf, &members))
+            .sum()
+    }
+
+    fn thaw_leaf(leaf: &Path, members: &[(i32, String)]) -> usize {
+        if let Err(e) = fs::write(leaf.join("cgroup.freeze"), "0") {
+            eprintln!("Failed to thaw {:?}: {}", leaf, e);
+            return 0;
+        }
+
+        for (pid, original) in members {
+            let procs = Path::new(CGROUP_ROOT)
+                .join(original.trim_start_matches('/'))
+                .join("cgroup.procs");
+            // The original scope may be gone; the process then stays in our leaf
+            let _ = fs::write(procs, pid.to_string());
+        }
+
+        let _ = fs::remove_dir(leaf);
+        members.len()
+    }
+
+    pub fn frozen_pids(&self) -> HashSet<i32> {
+        self.frozen
+            .values()
+            .flatten()
+            .map(|(pid, _)| *pid)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delegated_base() {
+        let own = "/user.slice/user-1000.slice/user@1000.service/app.slice/app-ficha.scope";
+        assert_eq!(
+            CgroupFreezer::delegated_base(own),
+            Some(PathBuf::from(
+                "/sys/fs/cgroup/user.slice/user-1000.slice/user@1000.service/ficha-frozen.slice"
+            ))
+        );
+        assert_eq!(CgroupFreezer::delegated_base("/system.slice/ficha.service"), None);
+    }
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How long a relayed prompt waits for the user before PAM is told the
+/// conversation failed
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Payload of the `pam-prompt` event; answered with `respond_pam_prompt`
+#[derive(Debug, Clone, Serialize)]
+pub struct PamPrompt {
+    pub id: String,
+    pub message: String,
+    /// Whether the answer may be shown while typed (e.g. an OTP)
+    pub echo: bool,
+}
+
+/// Payload of the `pam-message` event
+#[derive(Debug, Clone, Serialize)]
+pub struct PamMessage {
+    pub message: String,
+    pub error: bool,
+}
+
+/// Where PAM's conversation goes once the unlock password has been used
+#[cfg_attr(not(feature = "pam-auth"), allow(dead_code))]
+pub trait PromptRelay {
+    /// Ask the user something. `None` aborts the conversation.
+    fn prompt(&self, message: &str, echo: bool) -> Option<String>;
+    fn message(&self, message: &str, error: bool);
+}
+
+/// Prompts waiting for an answer from the UI
+pub struct PromptBroker {
+    pending: Mutex<HashMap<String, Sender<Option<String>>>>,
+}
+
+impl PromptBroker {
+    pub fn new() -> Self {
+        PromptBroker {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn open(&self, message: &str, echo: bool) -> (PamPrompt, Receiver<Option<String>>) {
+        let (tx, rx) = mpsc::channel();
+        let prompt = PamPrompt {
+            id: uuid::Uuid::new_v4().to_string(),
+            message: message.to_string(),
+            echo,
+        };
+        self.pending.lock().unwrap().insert(prompt.id.clone(), tx);
+        (prompt, rx)
+    }
+
+    fn close(&self, id: &str) {
+        self.pending.lock().unwrap().remove(id);
+    }
+
+    /// Deliver the user's answer, or `None` to cancel
+    pub fn respond(&self, id: &str, response: Option<String>) -> Result<(), String> {
+        let tx = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| "No such PAM prompt is waiting".to_string())?;
+        tx.send(response)
+            .map_err(|_| "The authentication attempt has already ended".to_string())
+    }
+}
+
+/// Relays prompts to the frontend as events and blocks for the answer
+pub struct UiRelay<'a> {
+    pub app_handle: &'a AppHandle,
+    pub broker: &'a PromptBroker,
+}
+
+impl PromptRelay for UiRelay<'_> {
+    fn prompt(&self, message: &str, echo: bool) -> Option<String> {
+        let (prompt, rx) = self.broker.open(message, echo);
+        if let Err(e) = self.app_handle.emit("pam-prompt", &prompt) {
+            eprintln!("Failed to relay PAM prompt: {}", e);
+            self.broker.close(&prompt.id);
+            return None;
+        }
+
+        let answer = rx.recv_timeout(PROMPT_TIMEOUT).ok().flatten();
+        self.broker.close(&prompt.id);
+        answer
+    }
+
+    fn message(&self, message: &str, error: bool) {
+        let payload = PamMessage {
+            message: message.to_string(),
+            error,
+        };
+        if let Err(e) = self.app_handle.emit("pam-message", &payload) {
+            eprintln!("Failed to relay PAM message: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broker_delivers_answer_once() {
+        let broker = PromptBroker::new();
+        let (prompt, rx) = broker.open("One-time code:", true);
+
+        assert!(broker.respond("unknown", None).is_err());
+        broker.respond(&prompt.id, Some("123456".to_string())).unwrap();
+        assert_eq!(rx.recv().unwrap(), Some("123456".to_string()));
+        assert!(broker.respond(&prompt.id, None).is_err());
+    }
+}
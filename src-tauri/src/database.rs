@@ -1,8 +1,35 @@
+use crate::allowlist::AllowedExecutable;
+use crate::elevation::ElevationExemption;
+use crate::matching::MatchRule;
+use crate::monitor::TerminationStrategy;
+use crate::policy_file::ManagedApp;
+use crate::schedule::Schedule;
 use rusqlite::{Connection, Result, params};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use chrono::Utc;
 
+/// Profile every install starts with, and that older data is moved into
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Columns of protected_apps other than the id and profile, in the order
+/// they are copied when the table is rebuilt or a profile is duplicated
+const APP_COLUMNS: &str = "name, process_name, icon, category, last_attempt, created_at,
+    termination_strategy, grace_period_secs, match_rule, exe_sha256, icon_name, schedule,
+    daily_quota_mins, audit_only, source, adopted";
+
+/// A named set of protected apps (with their rules, schedules and quotas),
+/// policy states and idle timeout, e.g. Work, Home or Travel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub idle_timeout_mins: i64,
+    pub active: bool,
+    pub created_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtectedApp {
     pub id: String,
@@ -12,6 +39,33 @@ pub struct ProtectedApp {
     pub category: String,
     pub last_attempt: Option<String>,
     pub created_at: String,
+    pub termination_strategy: TerminationStrategy,
+    pub grace_period_secs: i64,
+    pub match_rule: MatchRule,
+    pub exe_sha256: Option<String>,
+    /// Icon theme name or path from the app's desktop entry
+    pub icon_name: Option<String>,
+    /// When set, the app is enforced by this schedule instead of the shield
+    pub schedule: Option<Schedule>,
+    /// Minutes the app may run per day while it is allowed
+    pub daily_quota_mins: Option<i64>,
+    /// Matches are logged as "would have terminated" instead of enforced
+    pub audit_only: bool,
+    /// Policy file the app is declared in; None when added in the app
+    pub source: Option<String>,
+}
+
+/// Time-boxed permission to run one protected app while the shield is locked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlockGrant {
+    pub app_id: String,
+    pub app_name: String,
+    pub process_name: String,
+    /// RFC 3339, UTC
+    pub expires_at: String,
+    /// Stop the app's running processes when the grant runs out
+    pub kill_on_expiry: bool,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,9 +148,199 @@ impl Database {
             [],
         )?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS auth_attempts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                success INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS unlock_grants (
+                app_id TEXT PRIMARY KEY,
+                expires_at TEXT NOT NULL,
+                kill_on_expiry INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_usage (
+                app_id TEXT NOT NULL,
+                day TEXT NOT NULL,
+                seconds INTEGER NOT NULL,
+                PRIMARY KEY (app_id, day)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS allowed_executables (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                exe_path TEXT NOT NULL,
+                source TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE(name, exe_path)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS elevation_exemptions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                idle_timeout_mins INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Policy states per profile; security_policies.enabled is the
+        // default for profiles that haven't toggled a policy
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS profile_policies (
+                profile_id TEXT NOT NULL,
+                policy_id TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                PRIMARY KEY (profile_id, policy_id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS recovery_codes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                code_hash TEXT NOT NULL,
+                used INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Columns added after the first release
+        Self::add_column_if_missing(&conn, "protected_apps", "termination_strategy", "TEXT NOT NULL DEFAULT 'kill'")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "grace_period_secs", "INTEGER NOT NULL DEFAULT 5")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "match_rule", "TEXT")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "exe_sha256", "TEXT")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "icon_name", "TEXT")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "schedule", "TEXT")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "daily_quota_mins", "INTEGER")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "audit_only", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::add_column_if_missing(&conn, "protected_apps", "source", "TEXT")?;
+        // Set when a policy file takes over an app that was added in the app,
+        // which then stays behind when the file drops it
+        Self::add_column_if_missing(&conn, "protected_apps", "adopted", "INTEGER NOT NULL DEFAULT 0")?;
+
+        if !Self::has_column(&conn, "protected_apps", "profile_id")? {
+            Self::migrate_to_profiles(&conn)?;
+        }
+
+        // Rows from before match rules get the default rule for their name
+        let legacy: Vec<(String, String)> = conn
+            .prepare("SELECT id, process_name FROM protected_apps WHERE match_rule IS NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        for (id, process_name) in legacy {
+            conn.execute(
+                "UPDATE protected_apps SET match_rule = ?1 WHERE id = ?2",
+                params![Self::rule_to_json(&MatchRule::default_for(&process_name)), id],
+            )?;
+        }
+
         Ok(())
     }
 
+    fn rule_to_json(rule: &MatchRule) -> String {
+        serde_json::to_string(rule).unwrap_or_default()
+    }
+
+    fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .any(|name| name == column);
+        Ok(exists)
+    }
+
+    fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        if !Self::has_column(conn, table, column)? {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        }
+        Ok(())
+    }
+
+    /// Move everything into the default profile. protected_apps is rebuilt
+    /// because SQLite can't relax UNIQUE(process_name) to one per profile
+    /// in place; policy states and the idle timeout are carried over.
+    fn migrate_to_profiles(conn: &Connection) -> Result<()> {
+        let idle_timeout: i64 = conn
+            .query_row("SELECT value FROM settings WHERE key = 'idle_timeout'", [], |row| row.get::<_, String>(0))
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(10);
+
+        // Rolled back on drop if any statement fails
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(&format!(
+            "CREATE TABLE protected_apps_new (
+                id TEXT PRIMARY KEY,
+                profile_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                process_name TEXT NOT NULL,
+                icon TEXT NOT NULL,
+                category TEXT NOT NULL,
+                last_attempt TEXT,
+                created_at TEXT NOT NULL,
+                termination_strategy TEXT NOT NULL DEFAULT 'kill',
+                grace_period_secs INTEGER NOT NULL DEFAULT 5,
+                match_rule TEXT,
+                exe_sha256 TEXT,
+                icon_name TEXT,
+                schedule TEXT,
+                daily_quota_mins INTEGER,
+                audit_only INTEGER NOT NULL DEFAULT 0,
+                source TEXT,
+                adopted INTEGER NOT NULL DEFAULT 0,
+                UNIQUE(profile_id, process_name)
+             );
+             INSERT INTO protected_apps_new (id, profile_id, {columns})
+                SELECT id, '{profile}', {columns} FROM protected_apps;
+             DROP TABLE protected_apps;
+             ALTER TABLE protected_apps_new RENAME TO protected_apps;
+             INSERT OR IGNORE INTO profiles (id, name, idle_timeout_mins, created_at)
+                VALUES ('{profile}', 'Default', {idle_timeout}, '{now}');
+             INSERT OR IGNORE INTO profile_policies (profile_id, policy_id, enabled)
+                SELECT '{profile}', id, enabled FROM security_policies;",
+            columns = APP_COLUMNS,
+            profile = DEFAULT_PROFILE,
+            idle_timeout = idle_timeout,
+            now = Utc::now().to_rfc3339(),
+        ))?;
+        tx.commit()
+    }
+
+    fn active_profile_id(conn: &Connection) -> Result<String> {
+        match conn.query_row("SELECT value FROM settings WHERE key = 'active_profile'", [], |row| row.get(0)) {
+            Ok(id) => Ok(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(DEFAULT_PROFILE.to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
     fn seed_initial_data(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
@@ -110,21 +354,21 @@ impl Database {
         if count == 0 {
             // Seed initial protected apps
             let initial_apps = vec![
-                ("brave", "Brave Browser", "brave", "🌐", "Browser"),
-                ("chrome", "Google Chrome", "chrome", "🌐", "Browser"),
-                ("firefox", "Firefox", "firefox", "🦊", "Browser"),
-                ("discord", "Discord", "discord", "💬", "Communication"),
-                ("slack", "Slack", "slack", "💼", "Productivity"),
-                ("steam", "Steam", "steam", "🎮", "Gaming"),
+                ("brave", "Brave Browser", "brave", "🌐", "Browser", "brave-browser"),
+                ("chrome", "Google Chrome", "chrome", "🌐", "Browser", "google-chrome"),
+                ("firefox", "Firefox", "firefox", "🦊", "Browser", "firefox"),
+                ("discord", "Discord", "discord", "💬", "Communication", "discord"),
+                ("slack", "Slack", "slack", "💼", "Productivity", "slack"),
+                ("steam", "Steam", "steam", "🎮", "Gaming", "steam"),
             ];
 
             for app in initial_apps {
                 let id = uuid::Uuid::new_v4().to_string();
                 let now = Utc::now().to_rfc3339();
                 conn.execute(
-                    "INSERT INTO protected_apps (id, name, process_name, icon, category, created_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                    params![id, app.1, app.2, app.3, app.4, now],
+                    "INSERT INTO protected_apps (id, profile_id, name, process_name, icon, category, created_at, match_rule, icon_name)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![id, DEFAULT_PROFILE, app.1, app.2, app.3, app.4, now, Self::rule_to_json(&MatchRule::default_for(app.2)), app.5],
                 )?;
             }
 
@@ -162,19 +406,37 @@ impl Database {
     pub fn get_protected_apps(&self) -> Result<Vec<ProtectedApp>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, process_name, icon, category, last_attempt, created_at
-             FROM protected_apps ORDER BY created_at DESC"
+            "SELECT id, name, process_name, icon, category, last_attempt, created_at,
+                    termination_strategy, grace_period_secs, match_rule, exe_sha256, icon_name, schedule,
+                    daily_quota_mins, audit_only, source
+             FROM protected_apps WHERE profile_id = ?1 ORDER BY created_at DESC"
         )?;
 
-        let apps = stmt.query_map([], |row| {
+        let apps = stmt.query_map(params![Self::active_profile_id(&conn)?], |row| {
+            let process_name: String = row.get(2)?;
+            let match_rule = row.get::<_, Option<String>>(9)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_else(|| MatchRule::default_for(&process_name));
+
             Ok(ProtectedApp {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                process_name: row.get(2)?,
+                process_name,
                 icon: row.get(3)?,
                 category: row.get(4)?,
                 last_attempt: row.get(5)?,
                 created_at: row.get(6)?,
+                termination_strategy: TerminationStrategy::parse(&row.get::<_, String>(7)?)
+                    .unwrap_or(TerminationStrategy::Kill),
+                grace_period_secs: row.get(8)?,
+                match_rule,
+                exe_sha256: row.get(10)?,
+                icon_name: row.get(11)?,
+                schedule: row.get::<_, Option<String>>(12)?
+                    .and_then(|json| serde_json::from_str(&json).ok()),
+                daily_quota_mins: row.get(13)?,
+                audit_only: row.get::<_, i32>(14)? != 0,
+                source: row.get(15)?,
             })
         })?
         .collect::<Result<Vec<_>>>()?;
@@ -182,15 +444,15 @@ impl Database {
         Ok(apps)
     }
 
-    pub fn add_protected_app(&self, name: String, process_name: String, icon: String, category: String) -> Result<ProtectedApp> {
+    pub fn add_protected_app(&self, name: String, process_name: String, icon: String, category: String, match_rule: MatchRule, icon_name: Option<String>) -> Result<ProtectedApp> {
         let conn = self.conn.lock().unwrap();
         let id = uuid::Uuid::new_v4().to_string();
         let now = Utc::now().to_rfc3339();
 
         conn.execute(
-            "INSERT INTO protected_apps (id, name, process_name, icon, category, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![id, name, process_name, icon, category, now],
+            "INSERT INTO protected_apps (id, profile_id, name, process_name, icon, category, created_at, match_rule, icon_name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![id, Self::active_profile_id(&conn)?, name, process_name, icon, category, now, Self::rule_to_json(&match_rule), icon_name],
         )?;
 
         Ok(ProtectedApp {
@@ -201,20 +463,288 @@ impl Database {
             category,
             last_attempt: None,
             created_at: now,
+            termination_strategy: TerminationStrategy::Kill,
+            grace_period_secs: 5,
+            match_rule,
+            exe_sha256: None,
+            icon_name,
+            schedule: None,
+            daily_quota_mins: None,
+            audit_only: false,
+            source: None,
         })
     }
 
+    pub fn set_match_rule(&self, id: &str, match_rule: &MatchRule) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE protected_apps SET match_rule = ?1 WHERE id = ?2",
+            params![Self::rule_to_json(match_rule), id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_exe_sha256(&self, id: &str, exe_sha256: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE protected_apps SET exe_sha256 = ?1 WHERE id = ?2",
+            params![exe_sha256, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_schedule(&self, id: &str, schedule: Option<&Schedule>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let json = schedule.map(|schedule| serde_json::to_string(schedule).unwrap_or_default());
+        conn.execute(
+            "UPDATE protected_apps SET schedule = ?1 WHERE id = ?2",
+            params![json, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_daily_quota(&self, id: &str, minutes: Option<i64>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE protected_apps SET daily_quota_mins = ?1 WHERE id = ?2",
+            params![minutes, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_audit_only(&self, id: &str, audit_only: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE protected_apps SET audit_only = ?1 WHERE id = ?2",
+            params![audit_only as i32, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_termination_strategy(&self, id: &str, strategy: TerminationStrategy, grace_period_secs: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE protected_apps SET termination_strategy = ?1, grace_period_secs = ?2 WHERE id = ?3",
+            params![strategy.as_str(), grace_period_secs, id],
+        )?;
+        Ok(())
+    }
+
+    /// Make the active profile's managed apps match `apps`: declared ones are
+    /// inserted or take over the app with the same process name, managed ones
+    /// no longer declared are removed
+    pub fn sync_managed_apps(&self, apps: &[ManagedApp]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let profile_id = Self::active_profile_id(&conn)?;
+        let tx = conn.transaction()?;
+
+        for app in apps {
+            let schedule = app.schedule.as_ref().and_then(|schedule| serde_json::to_string(schedule).ok());
+            let updated = tx.execute(
+                "UPDATE protected_apps SET name = ?1, icon = ?2, category = ?3, icon_name = ?4, match_rule = ?5,
+                    termination_strategy = ?6, grace_period_secs = ?7, schedule = ?8, daily_quota_mins = ?9,
+                    audit_only = ?10, adopted = CASE WHEN source IS NULL THEN 1 ELSE adopted END, source = ?11
+                 WHERE profile_id = ?12 AND process_name = ?13",
+                params![
+                    app.name, app.icon, app.category, app.icon_name, Self::rule_to_json(&app.match_rule()),
+                    app.strategy.as_str(), app.grace_period_secs, schedule, app.daily_quota_mins,
+                    app.audit_only as i32, app.source, profile_id, app.process_name
+                ],
+            )?;
+            if updated == 0 {
+                tx.execute(
+                    "INSERT INTO protected_apps (id, profile_id, name, process_name, icon, category, created_at,
+                        icon_name, match_rule, termination_strategy, grace_period_secs, schedule, daily_quota_mins,
+                        audit_only, source)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                    params![
+                        uuid::Uuid::new_v4().to_string(), profile_id, app.name, app.process_name, app.icon,
+                        app.category, Utc::now().to_rfc3339(), app.icon_name, Self::rule_to_json(&app.match_rule()),
+                        app.strategy.as_str(), app.grace_period_secs, schedule, app.daily_quota_mins,
+                        app.audit_only as i32, app.source
+                    ],
+                )?;
+            }
+        }
+
+        let stale: Vec<(String, String, bool)> = tx
+            .prepare("SELECT id, process_name, adopted FROM protected_apps WHERE profile_id = ?1 AND source IS NOT NULL")?
+            .query_map(params![profile_id], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i32>(2)? != 0)))?
+            .collect::<Result<Vec<_>>>()?;
+        for (id, process_name, adopted) in stale {
+            if apps.iter().any(|app| app.process_name == process_name) {
+                continue;
+            }
+            if adopted {
+                // Added in the app before the file declared it, hand it back
+                tx.execute("UPDATE protected_apps SET source = NULL, adopted = 0 WHERE id = ?1", params![id])?;
+            } else {
+                tx.execute("DELETE FROM protected_apps WHERE id = ?1", params![id])?;
+                tx.execute("DELETE FROM unlock_grants WHERE app_id = ?1", params![id])?;
+                tx.execute("DELETE FROM app_usage WHERE app_id = ?1", params![id])?;
+            }
+        }
+
+        tx.commit()
+    }
+
     pub fn remove_protected_app(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM protected_apps WHERE id = ?1", params![id])?;
+        conn.execute("DELETE FROM unlock_grants WHERE app_id = ?1", params![id])?;
+        conn.execute("DELETE FROM app_usage WHERE app_id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // Daily usage, keyed by local date (YYYY-MM-DD)
+    /// Returns the new total for the day
+    pub fn add_app_usage(&self, app_id: &str, day: &str, seconds: i64) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO app_usage (app_id, day, seconds) VALUES (?1, ?2, ?3)
+             ON CONFLICT(app_id, day) DO UPDATE SET seconds = seconds + excluded.seconds",
+            params![app_id, day, seconds],
+        )?;
+        conn.query_row(
+            "SELECT seconds FROM app_usage WHERE app_id = ?1 AND day = ?2",
+            params![app_id, day],
+            |row| row.get(0),
+        )
+    }
+
+    /// Seconds used per app id on `day`
+    pub fn get_app_usage(&self, day: &str) -> Result<HashMap<String, i64>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT app_id, seconds FROM app_usage WHERE day = ?1")?;
+        let usage = stmt.query_map(params![day], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, i64>>>()?;
+        Ok(usage)
+    }
+
+    // Temporary per-app unlocks
+    pub fn add_unlock_grant(&self, app_id: &str, expires_at: &str, kill_on_expiry: bool) -> Result<UnlockGrant> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT OR REPLACE INTO unlock_grants (app_id, expires_at, kill_on_expiry, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![app_id, expires_at, kill_on_expiry as i32, Utc::now().to_rfc3339()],
+            )?;
+        }
+
+        self.get_unlock_grants()?
+            .into_iter()
+            .find(|grant| grant.app_id == app_id)
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    pub fn get_unlock_grants(&self) -> Result<Vec<UnlockGrant>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT g.app_id, a.name, a.process_name, g.expires_at, g.kill_on_expiry, g.created_at
+             FROM unlock_grants g JOIN protected_apps a ON a.id = g.app_id
+             ORDER BY g.expires_at"
+        )?;
+
+        let grants = stmt.query_map([], |row| {
+            Ok(UnlockGrant {
+                app_id: row.get(0)?,
+                app_name: row.get(1)?,
+                process_name: row.get(2)?,
+                expires_at: row.get(3)?,
+                kill_on_expiry: row.get::<_, i32>(4)? != 0,
+                created_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(grants)
+    }
+
+    /// Returns whether a grant was removed
+    pub fn remove_unlock_grant(&self, app_id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn.execute("DELETE FROM unlock_grants WHERE app_id = ?1", params![app_id])?;
+        Ok(removed > 0)
+    }
+
+    // Allowlist mode
+    pub fn get_allowed_executables(&self) -> Result<Vec<AllowedExecutable>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, exe_path, source, created_at FROM allowed_executables ORDER BY name"
+        )?;
+
+        let entries = stmt.query_map([], |row| {
+            Ok(AllowedExecutable {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                exe_path: row.get(2)?,
+                source: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Returns whether the entry was new
+    pub fn add_allowed_executable(&self, name: &str, exe_path: &str, source: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let added = conn.execute(
+            "INSERT OR IGNORE INTO allowed_executables (name, exe_path, source, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![name, exe_path, source, Utc::now().to_rfc3339()],
+        )?;
+        Ok(added > 0)
+    }
+
+    pub fn remove_allowed_executable(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM allowed_executables WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // Root Access Prevention exemptions
+    pub fn get_elevation_exemptions(&self) -> Result<Vec<ElevationExemption>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, command, created_at FROM elevation_exemptions ORDER BY command"
+        )?;
+
+        let exemptions = stmt.query_map([], |row| {
+            Ok(ElevationExemption {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(exemptions)
+    }
+
+    pub fn add_elevation_exemption(&self, command: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO elevation_exemptions (command, created_at) VALUES (?1, ?2)",
+            params![command, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_elevation_exemption(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM elevation_exemptions WHERE id = ?1", params![id])?;
         Ok(())
     }
 
     pub fn update_last_attempt(&self, process_name: &str, timestamp: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "UPDATE protected_apps SET last_attempt = ?1 WHERE process_name = ?2",
-            params![timestamp, process_name],
+            "UPDATE protected_apps SET last_attempt = ?1 WHERE process_name = ?2 AND profile_id = ?3",
+            params![timestamp, process_name, Self::active_profile_id(&conn)?],
         )?;
         Ok(())
     }
@@ -261,14 +791,17 @@ impl Database {
         })
     }
 
-    // Security Policies CRUD
+    // Security Policies CRUD, with states of the active profile
     pub fn get_security_policies(&self) -> Result<Vec<SecurityPolicy>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, title, description, enabled, severity FROM security_policies"
+            "SELECT p.id, p.title, p.description, COALESCE(pp.enabled, p.enabled), p.severity
+             FROM security_policies p
+             LEFT JOIN profile_policies pp ON pp.policy_id = p.id AND pp.profile_id = ?1
+             ORDER BY p.id"
         )?;
 
-        let policies = stmt.query_map([], |row| {
+        let policies = stmt.query_map(params![Self::active_profile_id(&conn)?], |row| {
             Ok(SecurityPolicy {
                 id: row.get(0)?,
                 title: row.get(1)?,
@@ -284,24 +817,213 @@ impl Database {
 
     pub fn toggle_policy(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        let profile_id = Self::active_profile_id(&conn)?;
+        let enabled = Self::policy_enabled(&conn, &profile_id, id)?;
         conn.execute(
-            "UPDATE security_policies SET enabled = NOT enabled WHERE id = ?1",
-            params![id],
+            "INSERT OR REPLACE INTO profile_policies (profile_id, policy_id, enabled) VALUES (?1, ?2, ?3)",
+            params![profile_id, id, !enabled as i32],
+        )?;
+        Ok(())
+    }
+
+    /// Set a policy's state in the active profile
+    pub fn set_policy_enabled(&self, id: &str, enabled: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO profile_policies (profile_id, policy_id, enabled) VALUES (?1, ?2, ?3)",
+            params![Self::active_profile_id(&conn)?, id, enabled as i32],
         )?;
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn is_policy_enabled(&self, id: &str) -> Result<bool> {
         let conn = self.conn.lock().unwrap();
+        Self::policy_enabled(&conn, &Self::active_profile_id(&conn)?, id)
+    }
+
+    fn policy_enabled(conn: &Connection, profile_id: &str, id: &str) -> Result<bool> {
         let enabled: i32 = conn.query_row(
-            "SELECT enabled FROM security_policies WHERE id = ?1",
-            params![id],
+            "SELECT COALESCE(pp.enabled, p.enabled) FROM security_policies p
+             LEFT JOIN profile_policies pp ON pp.policy_id = p.id AND pp.profile_id = ?1
+             WHERE p.id = ?2",
+            params![profile_id, id],
             |row| row.get(0)
         )?;
         Ok(enabled != 0)
     }
 
+    // Profiles
+    pub fn get_profiles(&self) -> Result<Vec<Profile>> {
+        let conn = self.conn.lock().unwrap();
+        let active = Self::active_profile_id(&conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, idle_timeout_mins, created_at FROM profiles ORDER BY created_at"
+        )?;
+
+        let profiles = stmt.query_map([], |row| {
+            let id: String = row.get(0)?;
+            Ok(Profile {
+                active: id == active,
+                id,
+                name: row.get(1)?,
+                idle_timeout_mins: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(profiles)
+    }
+
+    pub fn get_active_profile(&self) -> Result<Profile> {
+        self.get_profiles()?
+            .into_iter()
+            .find(|profile| profile.active)
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    /// Create a profile, starting as a copy of the active one (apps, rules,
+    /// schedules, quotas and policy states) when `copy_active` is set
+    pub fn create_profile(&self, name: &str, copy_active: bool) -> Result<Profile> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let source = Self::active_profile_id(&conn)?;
+            let id = uuid::Uuid::new_v4().to_string();
+            let tx = conn.transaction()?;
+
+            tx.execute(
+                "INSERT INTO profiles (id, name, idle_timeout_mins, created_at)
+                 SELECT ?1, ?2, idle_timeout_mins, ?3 FROM profiles WHERE id = ?4",
+                params![id, name, Utc::now().to_rfc3339(), source],
+            )?;
+
+            if copy_active {
+                let app_ids: Vec<String> = tx
+                    .prepare("SELECT id FROM protected_apps WHERE profile_id = ?1")?
+                    .query_map(params![source], |row| row.get(0))?
+                    .collect::<Result<Vec<_>>>()?;
+                for app_id in app_ids {
+                    tx.execute(
+                        &format!(
+                            "INSERT INTO protected_apps (id, profile_id, {columns})
+                             SELECT ?1, ?2, {columns} FROM protected_apps WHERE id = ?3",
+                            columns = APP_COLUMNS
+                        ),
+                        params![uuid::Uuid::new_v4().to_string(), id, app_id],
+                    )?;
+                }
+                tx.execute(
+                    "INSERT INTO profile_policies (profile_id, policy_id, enabled)
+                     SELECT ?1, policy_id, enabled FROM profile_policies WHERE profile_id = ?2",
+                    params![id, source],
+                )?;
+            }
+            tx.commit()?;
+        }
+
+        self.get_profiles()?
+            .into_iter()
+            .find(|profile| profile.name == name)
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    pub fn set_active_profile(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT id FROM profiles WHERE id = ?1", params![id], |row| row.get::<_, String>(0))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('active_profile', ?1)",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a profile with its apps and their grants and usage
+    pub fn delete_profile(&self, id: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM unlock_grants WHERE app_id IN (SELECT id FROM protected_apps WHERE profile_id = ?1)",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM app_usage WHERE app_id IN (SELECT id FROM protected_apps WHERE profile_id = ?1)",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM protected_apps WHERE profile_id = ?1", params![id])?;
+        tx.execute("DELETE FROM profile_policies WHERE profile_id = ?1", params![id])?;
+        tx.execute("DELETE FROM profiles WHERE id = ?1", params![id])?;
+        tx.commit()
+    }
+
+    /// Idle timeout of the active profile
+    pub fn set_idle_timeout(&self, minutes: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE profiles SET idle_timeout_mins = ?1 WHERE id = ?2",
+            params![minutes, Self::active_profile_id(&conn)?],
+        )?;
+        Ok(())
+    }
+
+    // Unlock attempts
+    pub fn record_auth_attempt(&self, success: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO auth_attempts (timestamp, success) VALUES (?1, ?2)",
+            params![Utc::now().to_rfc3339(), success as i32],
+        )?;
+
+        // A success clears the history the backoff is computed from
+        if success {
+            conn.execute(
+                "DELETE FROM auth_attempts WHERE id < ?1",
+                params![conn.last_insert_rowid()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Failed attempts since the last success, and when the latest was
+    pub fn get_auth_failures(&self) -> Result<(i64, Option<String>)> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*), MAX(timestamp) FROM auth_attempts
+             WHERE success = 0
+               AND id > COALESCE((SELECT MAX(id) FROM auth_attempts WHERE success = 1), 0)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    // Recovery codes for the TOTP second factor, stored as SHA-256 hashes
+    pub fn replace_recovery_codes(&self, code_hashes: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM recovery_codes", [])?;
+        for hash in code_hashes {
+            tx.execute(
+                "INSERT INTO recovery_codes (code_hash, used) VALUES (?1, 0)",
+                params![hash],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Mark an unused code as used. Returns whether one matched.
+    pub fn consume_recovery_code(&self, code_hash: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE recovery_codes SET used = 1 WHERE code_hash = ?1 AND used = 0",
+            params![code_hash],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn count_recovery_codes(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM recovery_codes WHERE used = 0", [], |row| row.get(0))
+    }
+
     // Settings CRUD
     pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
         let conn = self.conn.lock().unwrap();
@@ -316,6 +1038,12 @@ impl Database {
         }
     }
 
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
     pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -339,3 +1067,104 @@ impl Database {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_migrate_and_scope_apps_and_policies() {
+        let path = std::env::temp_dir().join(format!("ficha-test-{}.db", uuid::Uuid::new_v4()));
+        {
+            // Schema from before profiles, with one app per process name
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE protected_apps (
+                    id TEXT PRIMARY KEY, name TEXT NOT NULL, process_name TEXT NOT NULL UNIQUE,
+                    icon TEXT NOT NULL, category TEXT NOT NULL, last_attempt TEXT, created_at TEXT NOT NULL
+                 );
+                 CREATE TABLE security_policies (
+                    id TEXT PRIMARY KEY, title TEXT NOT NULL, description TEXT NOT NULL,
+                    enabled INTEGER NOT NULL, severity TEXT NOT NULL
+                 );
+                 CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+                 INSERT INTO protected_apps VALUES ('a1', 'Steam', 'steam', '🎮', 'Gaming', NULL, '2024-01-01');
+                 INSERT INTO security_policies VALUES ('policy_4', 'Idle', '', 1, 'low');
+                 INSERT INTO settings VALUES ('idle_timeout', '7');",
+            ).unwrap();
+        }
+
+        let db = Database::new(path.to_str().unwrap()).unwrap();
+        let default = db.get_active_profile().unwrap();
+        assert_eq!(default.id, DEFAULT_PROFILE);
+        assert_eq!(default.idle_timeout_mins, 7);
+        assert_eq!(db.get_protected_apps().unwrap()[0].id, "a1");
+        assert!(db.is_policy_enabled("policy_4").unwrap());
+
+        let work = db.create_profile("Work", false).unwrap();
+        db.set_active_profile(&work.id).unwrap();
+        assert!(db.get_protected_apps().unwrap().is_empty());
+        // The same process name may be protected once per profile
+        db.add_protected_app("Steam".into(), "steam".into(), "🎮".into(), "Gaming".into(), MatchRule::default_for("steam"), None).unwrap();
+        db.toggle_policy("policy_4").unwrap();
+        assert!(!db.is_policy_enabled("policy_4").unwrap());
+
+        db.set_active_profile(DEFAULT_PROFILE).unwrap();
+        assert_eq!(db.get_protected_apps().unwrap().len(), 1);
+        assert!(db.is_policy_enabled("policy_4").unwrap());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_fresh_install_seeds_default_profile() {
+        let db = Database::new(":memory:").unwrap();
+        assert_eq!(db.get_active_profile().unwrap().id, DEFAULT_PROFILE);
+        assert_eq!(db.get_protected_apps().unwrap().len(), 6);
+        assert!(db.is_policy_enabled("policy_1").unwrap());
+    }
+
+    fn managed(process_name: &str) -> ManagedApp {
+        ManagedApp {
+            name: process_name.to_string(),
+            process_name: process_name.to_string(),
+            icon: "📦".to_string(),
+            category: "Managed".to_string(),
+            icon_name: None,
+            match_rule: None,
+            strategy: TerminationStrategy::Kill,
+            grace_period_secs: 5,
+            schedule: None,
+            daily_quota_mins: None,
+            audit_only: false,
+            source: "/etc/ficha/policy.toml".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sync_managed_apps_only_removes_what_it_created() {
+        let db = Database::new(":memory:").unwrap();
+        let app_id = |process_name: &str| {
+            db.get_protected_apps().unwrap().into_iter().find(|app| app.process_name == process_name).map(|app| app.id)
+        };
+
+        // steam was added in the app, zoom only comes from the file
+        db.sync_managed_apps(&[managed("steam"), managed("zoom")]).unwrap();
+        let steam = app_id("steam").unwrap();
+        let zoom = app_id("zoom").unwrap();
+        db.add_unlock_grant(&zoom, "2099-01-01T00:00:00Z", false).unwrap();
+        db.add_app_usage(&zoom, "2024-01-01", 60).unwrap();
+
+        db.sync_managed_apps(&[]).unwrap();
+
+        let apps = db.get_protected_apps().unwrap();
+        let steam_app = apps.iter().find(|app| app.id == steam).unwrap();
+        assert_eq!(steam_app.source, None);
+        assert_eq!(app_id("zoom"), None);
+        let grants: i64 = db.conn.lock().unwrap()
+            .query_row("SELECT COUNT(*) FROM unlock_grants WHERE app_id = ?1", params![zoom], |row| row.get(0))
+            .unwrap();
+        assert_eq!(grants, 0);
+        assert!(!db.get_app_usage("2024-01-01").unwrap().contains_key(&zoom));
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The parts of a freedesktop `.desktop` file the app picker cares about
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesktopEntry {
+    /// Desktop file ID, e.g. `org.gnome.Nautilus.desktop`
+    pub id: String,
+    pub name: String,
+    /// Exec line split into arguments, field codes removed
+    pub exec: Vec<String>,
+    pub icon: Option<String>,
+    pub categories: Vec<String>,
+    pub startup_wm_class: Option<String>,
+}
+
+/// What an Exec line actually starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecTarget {
+    /// Resolved program and the argv it is started with
+    Binary(PathBuf, Vec<String>),
+    Flatpak(String),
+    Snap(String),
+}
+
+impl DesktopEntry {
+    /// Parse the `[Desktop Entry]` group. Returns `None` for anything the
+    /// picker shouldn't list: non-applications, hidden entries and entries
+    /// without an Exec line.
+    pub fn parse(id: &str, content: &str) -> Option<Self> {
+        let mut in_entry = false;
+        let mut name = None;
+        let mut exec = None;
+        let mut icon = None;
+        let mut categories = Vec::new();
+        let mut startup_wm_class = None;
+        let mut is_application = false;
+
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_entry || line.starts_with('#') {
+                continue;
+            }
+
+            // Localised keys like Name[fr] have the bracket before '='
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+
+            match key {
+                "Type" => is_application = value == "Application",
+                "Name" => name = Some(value.to_string()),
+                "Exec" => exec = Some(Self::split_exec(value)),
+                "Icon" if !value.is_empty() => icon = Some(value.to_string()),
+                "Categories" => {
+                    categories = value
+                        .split(';')
+                        .filter(|c| !c.is_empty())
+                        .map(String::from)
+                        .collect()
+                }
+                "StartupWMClass" if !value.is_empty() => startup_wm_class = Some(value.to_string()),
+                "NoDisplay" | "Hidden" if value == "true" => return None,
+                _ => {}
+            }
+        }
+
+        let exec = exec.filter(|args| !args.is_empty())?;
+        if !is_application {
+            return None;
+        }
+
+        Some(DesktopEntry {
+            id: id.to_string(),
+            name: name?,
+            exec,
+            icon,
+            categories,
+            startup_wm_class,
+        })
+    }
+
+    /// Split an Exec value into arguments, honouring double quotes and
+    /// dropping `%f`-style field codes
+    fn split_exec(value: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut current = String::new();
+        let mut in_quotes = false;
+        let mut has_arg = false;
+        let mut chars = value.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    in_quotes = !in_quotes;
+                    has_arg = true;
+                }
+                '\\' if in_quotes => {
+                    if let Some(escaped) = chars.next() {
+                        current.push(escaped);
+                    }
+                }
+                ' ' | '\t' if !in_quotes => {
+                    if has_arg {
+                        args.push(std::mem::take(&mut current));
+                        has_arg = false;
+                    }
+                }
+                _ => {
+                    current.push(c);
+                    has_arg = true;
+                }
+            }
+        }
+        if has_arg {
+            args.push(current);
+        }
+
+        args.into_iter()
+            .filter(|arg| !(arg.len() == 2 && arg.starts_with('%')))
+            .map(|arg| arg.replace("%%", "%"))
+            .collect()
+    }
+
+    /// Resolve the Exec line to the program it runs, looking through `env`
+    /// wrappers and `flatpak run` / `snap run` launchers
+    pub fn exec_target(&self) -> Option<ExecTarget> {
+        let mut args = self.exec.iter().map(String::as_str).peekable();
+
+        // env [-u NAME] [NAME=value]... program
+        if args.peek().is_some_and(|arg| file_name(arg) == "env") {
+            args.next();
+            while let Some(arg) = args.peek() {
+                if *arg == "-u" {
+                    args.next();
+                } else if !(arg.starts_with('-') || arg.contains('=')) {
+                    break;
+                }
+                args.next();
+            }
+        }
+
+        let program = args.next()?;
+        let rest: Vec<&str> = args.collect();
+        let argv = || std::iter::once(program).chain(rest.iter().copied()).map(String::from).collect();
+
+        match file_name(program) {
+            "flatpak" if rest.first() == Some(&"run") => rest[1..]
+                .iter()
+                .find(|arg| !arg.starts_with('-'))
+                .map(|app_id| ExecTarget::Flatpak(app_id.to_string())),
+            "snap" if rest.first() == Some(&"run") => rest[1..]
+                .iter()
+                .find(|arg| !arg.starts_with('-'))
+                .map(|name| ExecTarget::Snap(name.split('.').next().unwrap_or(name).to_string())),
+            _ if program.starts_with("/snap/bin/") => {
+                let name = file_name(program);
+                Some(ExecTarget::Snap(name.split('.').next().unwrap_or(name).to_string()))
+            }
+            _ => Self::find_program(program).map(|path| ExecTarget::Binary(path, argv())),
+        }
+    }
+
+    fn find_program(program: &str) -> Option<PathBuf> {
+        if program.contains('/') {
+            let path = PathBuf::from(program);
+            return path.is_file().then_some(path);
+        }
+
+        std::env::var("PATH")
+            .unwrap_or_else(|_| "/usr/local/bin:/usr/bin:/bin".to_string())
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join(program))
+            .find(|path| path.is_file())
+    }
+
+    /// Map freedesktop categories onto the picker's categories
+    pub fn picker_category(&self) -> &'static str {
+        let has = |category: &str| self.categories.iter().any(|c| c == category);
+
+        if has("WebBrowser") {
+            "Browser"
+        } else if has("InstantMessaging") || has("Chat") || has("Email") || has("VideoConference") {
+            "Communication"
+        } else if has("Game") {
+            "Gaming"
+        } else if has("Development") {
+            "Development"
+        } else if has("AudioVideo") || has("Audio") || has("Video") {
+            "Media"
+        } else if has("Graphics") {
+            "Graphics"
+        } else if has("Office") {
+            "Office"
+        } else if has("Network") {
+            "Internet"
+        } else if has("Education") || has("Science") {
+            "Education"
+        } else if has("System") || has("Settings") {
+            "System"
+        } else if has("Utility") {
+            "Utilities"
+        } else {
+            "Other"
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// `applications` directories in XDG precedence order: user data first,
+/// then `XDG_DATA_DIRS`, then flatpak and snap exports
+pub fn application_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut dirs: Vec<PathBuf> = data_home.into_iter().collect();
+    dirs.extend(data_dirs.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from));
+    dirs.extend(home.map(|home| home.join(".local/share/flatpak/exports/share")));
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+    dirs.push(PathBuf::from("/var/lib/snapd/desktop"));
+
+    let mut unique = Vec::new();
+    for dir in dirs {
+        let dir = dir.join("applications");
+        if !unique.contains(&dir) {
+            unique.push(dir);
+        }
+    }
+    unique
+}
+
+/// Every listable application, with earlier directories shadowing later
+/// ones that share a desktop file ID
+pub fn discover() -> Vec<DesktopEntry> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+
+    for dir in application_dirs() {
+        let mut files = Vec::new();
+        collect_desktop_files(&dir, &dir, &mut files);
+
+        for (id, path) in files {
+            // A hidden entry still shadows the ones behind it
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if let Some(entry) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| DesktopEntry::parse(&id, &content))
+            {
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
+
+/// Desktop file IDs join subdirectories with '-': `kde/okular.desktop`
+/// becomes `kde-okular.desktop`
+fn collect_desktop_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_desktop_files(root, &path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let id = relative.to_string_lossy().replace('/', "-");
+                files.push((id, path));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_desktop_entry() {
+        let content = "[Desktop Entry]\nType=Application\nName=Firefox\nName[fr]=Firefox (fr)\nExec=firefox %u\nIcon=firefox\nCategories=Network;WebBrowser;\nStartupWMClass=firefox\n\n[Desktop Action new-window]\nName=New Window\nExec=firefox --new-window %u\n";
+        let entry = DesktopEntry::parse("firefox.desktop", content).unwrap();
+        assert_eq!(entry.name, "Firefox");
+        assert_eq!(entry.exec, vec!["firefox"]);
+        assert_eq!(entry.icon.as_deref(), Some("firefox"));
+        assert_eq!(entry.startup_wm_class.as_deref(), Some("firefox"));
+        assert_eq!(entry.picker_category(), "Browser");
+
+        let hidden = "[Desktop Entry]\nType=Application\nName=Helper\nExec=helper\nNoDisplay=true\n";
+        assert!(DesktopEntry::parse("helper.desktop", hidden).is_none());
+        let link = "[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com\n";
+        assert!(DesktopEntry::parse("docs.desktop", link).is_none());
+    }
+
+    #[test]
+    fn test_exec_targets() {
+        let entry = |exec: &str| DesktopEntry {
+            id: "test.desktop".to_string(),
+            name: "Test".to_string(),
+            exec: DesktopEntry::split_exec(exec),
+            icon: None,
+            categories: Vec::new(),
+            startup_wm_class: None,
+        };
+
+        assert_eq!(
+            entry(r#""/opt/My App/app" --flag "%F""#).exec,
+            vec!["/opt/My App/app", "--flag"]
+        );
+        assert_eq!(
+            entry("/usr/bin/flatpak run --branch=stable --arch=x86_64 --command=telegram-desktop org.telegram.desktop -- %u").exec_target(),
+            Some(ExecTarget::Flatpak("org.telegram.desktop".to_string()))
+        );
+        assert_eq!(
+            entry("env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/spotify_spotify.desktop /snap/bin/spotify %U").exec_target(),
+            Some(ExecTarget::Snap("spotify".to_string()))
+        );
+        assert_eq!(
+            entry("env -u LD_PRELOAD sh -c 'true'").exec_target(),
+            DesktopEntry::find_program("sh").map(|sh| ExecTarget::Binary(sh, vec!["sh".to_string(), "-c".to_string(), "'true'".to_string()]))
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::monitor::{ProcessInfo, ProcessMonitor};
+use nix::libc;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+/// Source of processes for the monitor loop to check.
+///
+/// Backends decide *which* processes are worth looking at; the matching and
+/// killing logic in `ProcessMonitor::check_and_kill_protected` is shared.
+pub trait DetectionBackend: Send {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Block for at most `timeout` and return the processes that should be checked
+    fn next_candidates(&mut self, timeout: Duration) -> Vec<ProcessInfo>;
+}
+
+/// Open the best available backend, falling back to polling `/proc`
+pub fn open_backend() -> Box<dyn DetectionBackend> {
+    match ProcConnectorBackend::open() {
+        Ok(backend) => Box::new(backend),
+        Err(e) => {
+            eprintln!("Proc connector unavailable ({}), falling back to /proc scan", e);
+            Box::new(ProcScanBackend)
+        }
+    }
+}
+
+/// Polls the whole of `/proc` once per interval
+pub struct ProcScanBackend;
+
+impl DetectionBackend for ProcScanBackend {
+    fn name(&self) -> &'static str {
+        "proc-scan"
+    }
+
+    fn next_candidates(&mut self, timeout: Duration) -> Vec<ProcessInfo> {
+        std::thread::sleep(timeout);
+        ProcessMonitor::get_all_processes()
+    }
+}
+
+// Values from <linux/connector.h> and <linux/cn_proc.h>
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+
+const NLMSG_HDRLEN: usize = 16;
+const CN_MSG_LEN: usize = 20;
+// what + cpu + timestamp_ns, followed by the event_data union
+const PROC_EVENT_HDRLEN: usize = 16;
+
+/// Receives `PROC_EVENT_EXEC` notifications from the kernel through the
+/// netlink process connector, so each exec is checked as it happens.
+pub struct ProcConnectorBackend {
+    fd: OwnedFd,
+}
+
+impl ProcConnectorBackend {
+    pub fn open() -> Result<Self, String> {
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_CONNECTOR,
+            )
+        };
+        if raw < 0 {
+            return Err(format!("socket: {}", io::Error::last_os_error()));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = CN_IDX_PROC;
+
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(format!("bind: {}", io::Error::last_os_error()));
+        }
+
+        let message = Self::subscribe_message();
+        let sent = unsafe {
+            libc::send(
+                fd.as_raw_fd(),
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(format!("subscribe: {}", io::Error::last_os_error()));
+        }
+
+        Ok(ProcConnectorBackend { fd })
+    }
+
+    /// nlmsghdr + cn_msg + PROC_CN_MCAST_LISTEN
+    fn subscribe_message() -> Vec<u8> {
+        let total = NLMSG_HDRLEN + CN_MSG_LEN + 4;
+        let mut buf = Vec::with_capacity(total);
+
+        // struct nlmsghdr
+        buf.extend_from_slice(&(total as u32).to_ne_bytes());
+        buf.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        buf.extend_from_slice(&0u16.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(&std::process::id().to_ne_bytes());
+
+        // struct cn_msg
+        buf.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        buf.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(&4u16.to_ne_bytes());
+        buf.extend_from_slice(&0u16.to_ne_bytes());
+
+        buf.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+        buf
+    }
+
+    /// Extract the TGIDs of all exec events in a datagram
+    fn parse_exec_events(buf: &[u8]) -> Vec<i32> {
+        let mut pids = Vec::new();
+        let mut offset = 0;
+
+        while offset + NLMSG_HDRLEN <= buf.len() {
+            let len = read_u32(buf, offset) as usize;
+            if len < NLMSG_HDRLEN || offset + len > buf.len() {
+                break;
+            }
+
+            let event = offset + NLMSG_HDRLEN + CN_MSG_LEN;
+            if event + PROC_EVENT_HDRLEN + 8 <= offset + len
+                && read_u32(buf, event) == PROC_EVENT_EXEC
+            {
+                // exec_proc_event { process_pid, process_tgid }
+                pids.push(read_u32(buf, event + PROC_EVENT_HDRLEN + 4) as i32);
+            }
+
+            // NLMSG_ALIGN
+            offset += (len + 3) & !3;
+        }
+
+        pids
+    }
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes([buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3]])
+}
+
+impl DetectionBackend for ProcConnectorBackend {
+    fn name(&self) -> &'static str {
+        "proc-connector"
+    }
+
+    fn next_candidates(&mut self, timeout: Duration) -> Vec<ProcessInfo> {
+        let mut pfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        let ready = unsafe { libc::poll(&mut pfd, 1, timeout.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return Vec::new();
+        }
+
+        let mut buf = [0u8; 8192];
+        let mut candidates = Vec::new();
+
+        loop {
+            let received = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+
+            if received < 0 {
+                let err = io::Error::last_os_error();
+                if err.raw_os_error() == Some(libc::ENOBUFS) {
+                    // The kernel dropped events; rescan so nothing slips through
+                    eprintln!("Proc connector overrun, rescanning /proc");
+                    return ProcessMonitor::get_all_processes();
+                }
+                break;
+            }
+
+            for pid in Self::parse_exec_events(&buf[..received as usize]) {
+                if let Some(info) = ProcessMonitor::get_process_info(pid) {
+                    candidates.push(info);
+                }
+            }
+        }
+
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec_event(pid: i32) -> Vec<u8> {
+        let mut buf = vec![0u8; NLMSG_HDRLEN + CN_MSG_LEN + PROC_EVENT_HDRLEN + 8];
+        let len = buf.len() as u32;
+        buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        let event = NLMSG_HDRLEN + CN_MSG_LEN;
+        buf[event..event + 4].copy_from_slice(&PROC_EVENT_EXEC.to_ne_bytes());
+        buf[event + 16..event + 20].copy_from_slice(&pid.to_ne_bytes());
+        buf[event + 20..event + 24].copy_from_slice(&pid.to_ne_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_parse_exec_events() {
+        let mut buf = exec_event(1234);
+        buf.extend(exec_event(5678));
+        assert_eq!(ProcConnectorBackend::parse_exec_events(&buf), vec![1234, 5678]);
+    }
+
+    #[test]
+    fn test_parse_ignores_other_events() {
+        let mut buf = exec_event(42);
+        let event = NLMSG_HDRLEN + CN_MSG_LEN;
+        // PROC_EVENT_FORK
+        buf[event..event + 4].copy_from_slice(&1u32.to_ne_bytes());
+        assert!(ProcConnectorBackend::parse_exec_events(&buf).is_empty());
+        assert!(ProcConnectorBackend::parse_exec_events(&buf[..10]).is_empty());
+    }
+}
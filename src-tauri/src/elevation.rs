@@ -0,0 +1,374 @@
+use crate::auth::AuthManager;
+use crate::monitor::ProcessInfo;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// How a tool that runs a command as another user (usually root) is
+/// invoked, so its options aren't mistaken for the command
+struct ElevationTool {
+    name: &'static str,
+    /// Options that take a value
+    with_value: &'static [&'static str],
+    /// Options whose value is a command string run by a shell
+    command_string: &'static [&'static str],
+    /// Options that run the command words through a shell instead
+    shell: &'static [&'static str],
+    /// The first word after the options is the target user
+    user_first: bool,
+}
+
+const ELEVATION_TOOLS: &[ElevationTool] = &[
+    ElevationTool {
+        name: "sudo",
+        with_value: &[
+            "-u", "--user", "-g", "--group", "-C", "--close-from", "-D", "--chdir", "-h", "--host",
+            "-p", "--prompt", "-r", "--role", "-t", "--type", "-U", "--other-user", "-T",
+            "--command-timeout",
+        ],
+        command_string: &[],
+        shell: &["-s", "--shell", "-i", "--login"],
+        user_first: false,
+    },
+    ElevationTool {
+        name: "su",
+        with_value: &["-s", "--shell", "-g", "--group", "-G", "--supp-group", "-w", "--whitelist-environment"],
+        command_string: &["-c", "--command", "--session-command"],
+        shell: &[],
+        user_first: true,
+    },
+    ElevationTool {
+        name: "pkexec",
+        with_value: &["-u", "--user"],
+        command_string: &[],
+        shell: &[],
+        user_first: false,
+    },
+    ElevationTool {
+        name: "doas",
+        with_value: &["-u", "-C"],
+        command_string: &[],
+        shell: &["-s"],
+        user_first: false,
+    },
+    ElevationTool {
+        name: "run0",
+        with_value: &[
+            "-u", "--user", "-g", "--group", "-D", "--chdir", "--setenv", "--unit", "--property",
+            "--description", "--slice", "--nice", "--machine", "--background",
+        ],
+        command_string: &["-c", "--command"],
+        shell: &[],
+        user_first: false,
+    },
+];
+
+/// Where an exempt program may live when it is given by path
+const SYSTEM_BIN_DIRS: &[&str] = &["/usr/bin", "/usr/sbin", "/bin", "/sbin"];
+/// Characters that make a `-c` command string more than one plain command
+/// (lists, pipes, redirections, substitutions, globs, quoting, assignments)
+const SHELL_METACHARACTERS: &[char] = &[
+    ';', '&', '|', '<', '>', '(', ')', '$', '`', '\\', '"', '\'', '*', '?', '[', ']', '{', '}',
+    '~', '#', '!', '=', '\n',
+];
+
+/// A command that may be elevated while locked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElevationExemption {
+    pub id: i64,
+    /// Elevated command, e.g. "systemctl suspend". A final `*` word also
+    /// lets further arguments through, e.g. "systemctl suspend *".
+    pub command: String,
+    pub created_at: String,
+}
+
+/// Root Access Prevention (`policy_3`) as the monitor enforces it
+#[derive(Debug, Clone)]
+pub struct ElevationGuard {
+    uid: u32,
+    exemptions: Vec<ExemptCommand>,
+}
+
+#[derive(Debug, Clone)]
+struct ExemptCommand {
+    words: Vec<String>,
+    /// Extra arguments after `words` are allowed
+    prefix: bool,
+}
+
+impl ElevationGuard {
+    pub fn new(exemptions: &[ElevationExemption]) -> Self {
+        ElevationGuard {
+            uid: AuthManager::desktop_uid(),
+            exemptions: exemptions
+                .iter()
+                .filter_map(|exemption| {
+                    let mut words: Vec<String> = exemption.command.split_whitespace().map(String::from).collect();
+                    let prefix = words.last().is_some_and(|word| word == "*");
+                    if prefix {
+                        words.pop();
+                    }
+                    (!words.is_empty()).then_some(ExemptCommand { words, prefix })
+                })
+                .collect(),
+        }
+    }
+
+    /// A guard for `uid` rather than the desktop user
+    #[cfg(test)]
+    pub fn for_uid(uid: u32, exemptions: &[ElevationExemption]) -> Self {
+        ElevationGuard { uid, ..Self::new(exemptions) }
+    }
+
+    /// Whether `process` is an elevation attempt by the desktop user that
+    /// isn't exempt
+    pub fn blocks(&self, process: &ProcessInfo) -> bool {
+        let Some(tool) = elevation_tool(process) else {
+            return false;
+        };
+        real_uid(process.pid) == Some(self.uid)
+            && !elevated_command(tool, &process.cmdline).is_some_and(|command| self.is_exempt(&command))
+    }
+
+    /// The whole command must match, unless the exemption ends in `*` and
+    /// the command isn't run by a shell
+    fn is_exempt(&self, command: &ElevatedCommand) -> bool {
+        let Some((program, args)) = command.words.split_first() else {
+            return false;
+        };
+        self.exemptions.iter().any(|exemption| {
+            let length_matches = if exemption.prefix && !command.shell {
+                exemption.words.len() - 1 <= args.len()
+            } else {
+                exemption.words.len() - 1 == args.len()
+            };
+            length_matches
+                && system_program_name(&exemption.words[0]).is_some()
+                && system_program_name(&exemption.words[0]) == system_program_name(program)
+                && exemption.words[1..].iter().zip(args).all(|(want, got)| want == got)
+        })
+    }
+}
+
+/// The tool `process` is, by its executable rather than its argv[0]
+fn elevation_tool(process: &ProcessInfo) -> Option<&'static ElevationTool> {
+    let exe_name = process.exe_path.as_deref().map(basename);
+    ELEVATION_TOOLS
+        .iter()
+        .find(|tool| exe_name == Some(tool.name))
+        .or_else(|| ELEVATION_TOOLS.iter().find(|tool| process.name == tool.name))
+}
+
+/// The command being elevated: argv without the tool and its options
+#[derive(Debug, PartialEq)]
+struct ElevatedCommand {
+    words: Vec<String>,
+    /// Run by a shell from a command string, so only an exact exemption applies
+    shell: bool,
+}
+
+/// Parse the command out of an elevation tool's argv. `None` when it is run
+/// by a shell from a string that is more than a plain command, since that
+/// can't be told apart from an exempt one by its words, or by a shell the
+/// user picks.
+fn elevated_command(tool: &ElevationTool, cmdline: &[String]) -> Option<ElevatedCommand> {
+    let shell_string = |value: &str| {
+        (!value.contains(SHELL_METACHARACTERS)).then(|| ElevatedCommand {
+            words: value.split_whitespace().map(String::from).collect(),
+            shell: true,
+        })
+    };
+
+    let mut args = cmdline.iter().skip(1);
+    let mut words: Vec<String> = Vec::new();
+    // Some(true) for a shell the user picks through $SHELL, Some(false) for
+    // the target user's login shell
+    let mut shell_flag: Option<bool> = None;
+    let mut user_seen = !tool.user_first;
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            words.extend(args.by_ref().cloned());
+        } else if let Some(long) = arg.strip_prefix("--") {
+            let (name, value) = match long.split_once('=') {
+                Some((name, value)) => (format!("--{}", name), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            if tool.command_string.contains(&name.as_str()) {
+                return shell_string(&value.or_else(|| args.next().cloned()).unwrap_or_default());
+            } else if tool.shell.contains(&name.as_str()) {
+                shell_flag = Some(name == "--shell");
+            } else if value.is_none() && tool.with_value.contains(&name.as_str()) {
+                args.next();
+            }
+        } else if arg.len() > 1 && arg.starts_with('-') {
+            // A cluster of short options, e.g. -Es or -lc "command"
+            for (i, flag) in arg.char_indices().skip(1) {
+                let option = format!("-{}", flag);
+                if tool.shell.contains(&option.as_str()) {
+                    shell_flag = Some(flag == 's');
+                    continue;
+                }
+                let takes_value = tool.with_value.contains(&option.as_str());
+                let command_string = tool.command_string.contains(&option.as_str());
+                if !takes_value && !command_string {
+                    continue;
+                }
+
+                // The value is the rest of the cluster or the next word
+                let attached = &arg[i + flag.len_utf8()..];
+                let value = if attached.is_empty() { args.next().cloned() } else { Some(attached.to_string()) };
+                if command_string {
+                    return shell_string(&value.unwrap_or_default());
+                }
+                break;
+            }
+        } else if arg == "-" {
+            // su's short form of --login
+        } else if !user_seen {
+            user_seen = true;
+        } else {
+            words.push(arg.clone());
+            words.extend(args.by_ref().cloned());
+        }
+    }
+
+    if tool.user_first && !words.is_empty() {
+        // Further words are passed to the target user's shell as a script
+        return None;
+    }
+
+    match shell_flag {
+        // sudo -s runs the words with $SHELL, which the user sets
+        Some(true) if !words.is_empty() => None,
+        // A login shell, e.g. sudo -i, runs the words as a command string
+        Some(_) => shell_string(&words.join(" ")),
+        None => Some(ElevatedCommand { words, shell: false }),
+    }
+}
+
+/// The name of a program given by bare name (looked up in the elevated
+/// user's PATH) or by its path in one of the system binary directories.
+/// `None` for relative paths and anything elsewhere, which the user could
+/// have put there.
+pub fn system_program_name(program: &str) -> Option<&str> {
+    if !program.contains('/') {
+        return (!program.is_empty()).then_some(program);
+    }
+    let path = Path::new(program);
+    let dir = path.parent()?.to_str()?;
+    SYSTEM_BIN_DIRS.contains(&dir).then(|| basename(program))
+}
+
+fn basename(path: &str) -> &str {
+    Path::new(path).file_name().and_then(|name| name.to_str()).unwrap_or(path)
+}
+
+/// Real UID from /proc/[pid]/status; the owner of /proc/[pid] is the
+/// effective UID, which is root for setuid tools like sudo
+pub fn real_uid(pid: i32) -> Option<u32> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn tool(name: &str) -> &'static ElevationTool {
+        ELEVATION_TOOLS.iter().find(|tool| tool.name == name).unwrap()
+    }
+
+    /// Words of the elevated command, with whether a shell runs them
+    fn parse(cmdline: &[String]) -> Option<(Vec<String>, bool)> {
+        elevated_command(tool(&cmdline[0]), cmdline).map(|command| (command.words, command.shell))
+    }
+
+    #[test]
+    fn test_elevated_command() {
+        let su_c = |command: &str| vec!["su".to_string(), "-c".to_string(), command.to_string()];
+        assert_eq!(parse(&args("sudo -u root -E apt update")), Some((args("apt update"), false)));
+        assert_eq!(parse(&args("sudo -- ls -la /root")), Some((args("ls -la /root"), false)));
+        assert_eq!(parse(&su_c("systemctl suspend")), Some((args("systemctl suspend"), true)));
+        assert_eq!(parse(&args("pkexec /usr/bin/gparted")), Some((args("/usr/bin/gparted"), false)));
+        assert_eq!(parse(&args("sudo -i")), Some((Vec::new(), true)));
+
+        // Shell strings that do more than run one command
+        assert_eq!(parse(&su_c("systemctl suspend && bash")), None);
+        assert_eq!(parse(&su_c("systemctl suspend; bash")), None);
+        assert_eq!(parse(&su_c("systemctl $(echo suspend)")), None);
+        assert_eq!(parse(&su_c("PATH=/tmp systemctl suspend")), None);
+        assert_eq!(parse(&["run0".into(), "--command=systemctl suspend|sh".into()]), None);
+    }
+
+    #[test]
+    fn test_options_are_parsed_per_tool() {
+        // su takes the user first and a shell as the value of -s
+        let su = vec!["su".into(), "-".into(), "root".into(), "-s".into(), "/bin/sh".into(), "-c".into(), "systemctl suspend".into()];
+        assert_eq!(parse(&su), Some((args("systemctl suspend"), true)));
+        assert_eq!(parse(&args("su root -lc reboot")), Some((args("reboot"), true)));
+        // Words after su's user are handed to the shell as a script
+        assert_eq!(parse(&args("su root systemctl suspend")), None);
+
+        // For sudo, -s takes no value: the words all run through $SHELL
+        assert_eq!(parse(&args("sudo -s evil systemctl suspend")), None);
+        assert_eq!(parse(&args("sudo -Es systemctl suspend")), None);
+        assert_eq!(parse(&args("sudo --shell systemctl suspend")), None);
+        // -i runs them as a command string in root's login shell
+        assert_eq!(parse(&args("sudo -i systemctl suspend")), Some((args("systemctl suspend"), true)));
+        assert_eq!(parse(&args("sudo -i systemctl suspend ;bash")), None);
+        assert_eq!(parse(&args("sudo -uroot apt update")), Some((args("apt update"), false)));
+        assert_eq!(parse(&args("doas -u root apt update")), Some((args("apt update"), false)));
+    }
+
+    #[test]
+    fn test_exemptions_match_whole_command() {
+        let exemption = |command: &str| ElevationExemption {
+            id: 0,
+            command: command.to_string(),
+            created_at: String::new(),
+        };
+        let plain = |line: &str| ElevatedCommand { words: args(line), shell: false };
+        let shell = |line: &str| ElevatedCommand { words: args(line), shell: true };
+        let guard = ElevationGuard::new(&[exemption("systemctl suspend"), exemption("/usr/bin/apt update")]);
+
+        assert!(guard.is_exempt(&plain("systemctl suspend")));
+        assert!(guard.is_exempt(&plain("/usr/bin/systemctl suspend")));
+        assert!(guard.is_exempt(&plain("apt update")));
+        assert!(!guard.is_exempt(&plain("apt install steam")));
+        assert!(!guard.is_exempt(&plain("systemctl")));
+        assert!(!guard.is_exempt(&plain("")));
+        // Extra arguments can run arbitrary commands through the exempt one
+        assert!(!guard.is_exempt(&plain("apt update -o APT::Update::Pre-Invoke::=/bin/sh")));
+        assert!(!guard.is_exempt(&plain("systemctl suspend -i")));
+        // Programs the user could have written are never exempt
+        assert!(!guard.is_exempt(&plain("./systemctl suspend")));
+        assert!(!guard.is_exempt(&plain("/tmp/systemctl suspend")));
+        assert!(!guard.is_exempt(&plain("/tmp/x/systemctl suspend")));
+        assert!(!guard.is_exempt(&plain("/tmp/apt update")));
+        assert!(!guard.is_exempt(&plain("/usr/bin/../../tmp/apt update")));
+        assert!(guard.is_exempt(&plain("/sbin/systemctl suspend")));
+
+        let guard = ElevationGuard::new(&[exemption("/opt/tools/backup run")]);
+        assert!(!guard.is_exempt(&plain("/opt/tools/backup run")));
+
+        let guard = ElevationGuard::new(&[exemption("systemctl suspend *")]);
+        assert!(guard.is_exempt(&plain("systemctl suspend")));
+        assert!(guard.is_exempt(&plain("systemctl suspend -i")));
+        assert!(!guard.is_exempt(&plain("systemctl poweroff")));
+        // Commands run by a shell need an exact exemption
+        assert!(!guard.is_exempt(&shell("systemctl suspend -i")));
+        let guard = ElevationGuard::new(&[exemption("systemctl suspend")]);
+        assert!(guard.is_exempt(&shell("systemctl suspend")));
+    }
+}
@@ -0,0 +1,412 @@
+use crate::monitor::{EnforcementBackend, ProcessMonitor, ProtectedTarget};
+use nix::libc;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Bit index of CAP_SYS_ADMIN in the capability sets
+const CAP_SYS_ADMIN: u32 = 21;
+const FANOTIFY_METADATA_VERSION: u8 = 3;
+
+/// Denies the exec of protected binaries before any of their code runs,
+/// using fanotify `FAN_OPEN_EXEC_PERM` permission events.
+pub struct ExecGuard {
+    fd: OwnedFd,
+    marked: Vec<MarkedBinary>,
+}
+
+/// A binary with an inode mark, identified by its inode so renames and
+/// hardlinks are still recognised
+struct MarkedBinary {
+    /// Canonical path when it was marked, for the log
+    path: PathBuf,
+    /// Protected process name
+    name: String,
+    dev: u64,
+    ino: u64,
+}
+
+impl MarkedBinary {
+    fn new(path: PathBuf, name: String) -> io::Result<Self> {
+        let meta = fs::metadata(&path)?;
+        Ok(MarkedBinary { path, name, dev: meta.dev(), ino: meta.ino() })
+    }
+
+    /// The marked binary `meta` describes, however it was opened
+    fn find<'a>(marked: &'a [MarkedBinary], meta: &fs::Metadata) -> Option<&'a MarkedBinary> {
+        marked.iter().find(|binary| binary.dev == meta.dev() && binary.ino == meta.ino())
+    }
+}
+
+impl ExecGuard {
+    pub fn open() -> Result<Self, String> {
+        if !Self::has_cap_sys_admin() {
+            return Err("missing CAP_SYS_ADMIN (run as root or grant the capability)".to_string());
+        }
+
+        let raw = unsafe {
+            libc::fanotify_init(
+                libc::FAN_CLASS_CONTENT | libc::FAN_CLOEXEC,
+                (libc::O_RDONLY | libc::O_LARGEFILE | libc::O_CLOEXEC) as libc::c_uint,
+            )
+        };
+        if raw < 0 {
+            return Err(format!("fanotify_init: {}", io::Error::last_os_error()));
+        }
+
+        Ok(ExecGuard {
+            fd: unsafe { OwnedFd::from_raw_fd(raw) },
+            marked: Vec::new(),
+        })
+    }
+
+    fn has_cap_sys_admin() -> bool {
+        let status = match fs::read_to_string("/proc/self/status") {
+            Ok(status) => status,
+            Err(_) => return false,
+        };
+
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("CapEff:"))
+            .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+            .map(|caps| caps & (1 << CAP_SYS_ADMIN) != 0)
+            .unwrap_or(false)
+    }
+
+    /// Resolve protected apps to the concrete binaries to mark
+    fn resolve_executables(targets: &[ProtectedTarget]) -> Vec<(PathBuf, String)> {
+        let mut resolved: Vec<(PathBuf, String)> = Vec::new();
+        for target in targets {
+            for path in ProcessMonitor::resolve_binaries(&target.process_name, &target.rule) {
+                if !resolved.iter().any(|(p, _)| p == &path) {
+                    resolved.push((path, target.process_name.clone()));
+                }
+            }
+        }
+        resolved
+    }
+
+    fn mark(&self, flags: libc::c_uint, path: Option<&Path>) -> io::Result<()> {
+        let c_path = match path {
+            Some(path) => Some(
+                CString::new(path.as_os_str().as_encoded_bytes())
+                    .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?,
+            ),
+            None => None,
+        };
+
+        let result = unsafe {
+            libc::fanotify_mark(
+                self.fd.as_raw_fd(),
+                flags,
+                libc::FAN_OPEN_EXEC_PERM,
+                libc::AT_FDCWD,
+                c_path.as_ref().map_or(std::ptr::null(), |p| p.as_ptr()),
+            )
+        };
+
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Replace the current inode marks with `wanted`
+    fn sync_marks(&mut self, wanted: Vec<(PathBuf, String)>) {
+        if !self.marked.is_empty() {
+            if let Err(e) = self.mark(libc::FAN_MARK_FLUSH, None) {
+                eprintln!("Failed to flush fanotify marks: {}", e);
+            }
+        }
+
+        self.marked = wanted
+            .into_iter()
+            .filter_map(|(path, name)| {
+                let marked = MarkedBinary::new(path.clone(), name)
+                    .and_then(|binary| self.mark(libc::FAN_MARK_ADD, Some(&path)).map(|()| binary));
+                if let Err(e) = &marked {
+                    eprintln!("Failed to mark {:?}: {}", path, e);
+                }
+                marked.ok()
+            })
+            .collect();
+    }
+
+    fn respond(&self, fd: libc::c_int, response: u32) {
+        let reply = libc::fanotify_response { fd, response };
+        let written = unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                &reply as *const libc::fanotify_response as *const libc::c_void,
+                mem::size_of::<libc::fanotify_response>(),
+            )
+        };
+        if written < 0 {
+            eprintln!("Failed to answer fanotify event: {}", io::Error::last_os_error());
+        }
+    }
+
+    /// Split a read() buffer into event records, stopping at the first one
+    /// that is truncated or from a metadata version we don't know
+    fn parse_events(buf: &[u8]) -> Vec<libc::fanotify_event_metadata> {
+        let meta_len = mem::size_of::<libc::fanotify_event_metadata>();
+        let mut events = Vec::new();
+        let mut offset = 0usize;
+
+        while offset + meta_len <= buf.len() {
+            let meta = unsafe {
+                std::ptr::read_unaligned(buf.as_ptr().add(offset) as *const libc::fanotify_event_metadata)
+            };
+            if meta.vers != FANOTIFY_METADATA_VERSION || (meta.event_len as usize) < meta_len {
+                break;
+            }
+            offset += meta.event_len as usize;
+            events.push(meta);
+        }
+        events
+    }
+
+    /// Wait up to `timeout` for permission events and answer them
+    fn handle_events<F>(&self, timeout: Duration, deny: bool, on_deny: &F)
+    where
+        F: Fn(i32, String, String),
+    {
+        let mut pfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        let ready = unsafe { libc::poll(&mut pfd, 1, timeout.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return;
+        }
+
+        // fanotify_event_metadata contains a u64, keep the buffer aligned for it
+        let mut buf = [0u64; 512];
+        let len = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                mem::size_of_val(&buf),
+            )
+        };
+        if len <= 0 {
+            return;
+        }
+
+        // Safety: `len` bytes of `buf` were just filled in by read()
+        let bytes = unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const u8, len as usize) };
+        let own_pid = std::process::id() as i32;
+
+        for meta in Self::parse_events(bytes) {
+            if meta.fd < 0 {
+                continue;
+            }
+            let event_file = fs::File::from(unsafe { OwnedFd::from_raw_fd(meta.fd) });
+
+            if meta.mask & libc::FAN_OPEN_EXEC_PERM == 0 {
+                continue;
+            }
+
+            // By inode, since the path it was opened by may be a hardlink or
+            // a rename of the marked one
+            let protected = event_file
+                .metadata()
+                .ok()
+                .and_then(|file_meta| MarkedBinary::find(&self.marked, &file_meta));
+
+            match protected {
+                Some(binary) if deny && meta.pid != own_pid => {
+                    self.respond(event_file.as_raw_fd(), libc::FAN_DENY);
+                    on_deny(meta.pid, binary.name.clone(), binary.path.to_string_lossy().to_string());
+                }
+                _ => self.respond(event_file.as_raw_fd(), libc::FAN_ALLOW),
+            }
+        }
+    }
+
+    /// Guard loop, run on its own thread until the monitor switches away
+    /// from the pre-exec backend. `running` is set by whoever started the
+    /// guard and cleared here on exit, so only one guard runs at a time.
+    pub fn run<F>(mut self, monitor: Arc<ProcessMonitor>, running: Arc<AtomicBool>, on_deny: F)
+    where
+        F: Fn(i32, String, String),
+    {
+        let mut last_binaries: Option<Vec<(String, Option<String>)>> = None;
+        println!("Pre-exec blocking enabled (fanotify)");
+
+        loop {
+            if monitor.enforcement_backend() != EnforcementBackend::PreExec {
+                self.sync_marks(Vec::new());
+                last_binaries = None;
+                running.store(false, Ordering::SeqCst);
+
+                // A switch back to pre-exec made before the store saw this
+                // guard still running and left enforcement to it
+                let resumed = monitor.enforcement_backend() == EnforcementBackend::PreExec
+                    && running
+                        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok();
+                if !resumed {
+                    println!("Pre-exec blocking disabled");
+                    return;
+                }
+                continue;
+            }
+
+            // Only mark what is enforced right now, so unlocked execs never
+            // wait on us; scheduled apps follow their schedule instead. Audited
+            // apps, and everything while auto-kill is off, are left to the
+            // kill-on-sight path, which only reports them.
+            let locked = monitor.is_monitoring();
+            let report_only = monitor.is_audit_mode() || !monitor.is_auto_kill();
+            let now = chrono::Utc::now();
+            let targets: Vec<ProtectedTarget> = monitor
+                .get_protected_processes()
+                .into_iter()
+                .filter(|target| !report_only && !target.audit_only && target.is_enforced(locked, now))
+                .collect();
+
+            // Re-resolve only when the watch list changes
+            let binaries: Vec<(String, Option<String>)> = targets
+                .iter()
+                .map(|target| (target.process_name.clone(), target.rule.exe_path().map(String::from)))
+                .collect();
+            if last_binaries.as_ref() != Some(&binaries) {
+                self.sync_marks(Self::resolve_executables(&targets));
+                last_binaries = Some(binaries);
+            }
+
+            self.handle_events(Duration::from_millis(500), !targets.is_empty(), &on_deny);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::{CompiledRule, MatchKind, MatchRule};
+    use crate::monitor::TerminationStrategy;
+
+    fn exe_target(process_name: &str, path: &Path) -> ProtectedTarget {
+        let rule = MatchRule {
+            kind: MatchKind::ExePath,
+            pattern: path.to_string_lossy().to_string(),
+            case_sensitive: true,
+            exclusions: Vec::new(),
+        };
+        ProtectedTarget {
+            process_name: process_name.to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule,
+            strategy: TerminationStrategy::Kill,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }
+    }
+
+    fn event(event_len: u32, vers: u8, fd: i32, pid: i32) -> libc::fanotify_event_metadata {
+        libc::fanotify_event_metadata {
+            event_len,
+            vers,
+            reserved: 0,
+            metadata_len: mem::size_of::<libc::fanotify_event_metadata>() as u16,
+            mask: libc::FAN_OPEN_EXEC_PERM,
+            fd,
+            pid,
+        }
+    }
+
+    fn bytes_of(meta: &libc::fanotify_event_metadata) -> Vec<u8> {
+        let size = mem::size_of::<libc::fanotify_event_metadata>();
+        unsafe { std::slice::from_raw_parts(meta as *const _ as *const u8, size) }.to_vec()
+    }
+
+    #[test]
+    fn test_resolve_executables() {
+        let dir = std::env::temp_dir().join(format!("ficha-fanotify-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("game");
+        fs::write(&binary, b"#!/bin/sh\n").unwrap();
+        let link = dir.join("game-launcher");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&binary, &link).unwrap();
+
+        let targets = vec![
+            exe_target("game", &binary),
+            // Same binary through a symlink, marked once under the first name
+            exe_target("launcher", &link),
+            exe_target("missing", &dir.join("missing")),
+        ];
+        let expected = fs::canonicalize(&binary).unwrap();
+        let resolved = ExecGuard::resolve_executables(&targets);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(resolved, vec![(expected, "game".to_string())]);
+    }
+
+    #[test]
+    fn test_marked_binaries_are_found_by_inode() {
+        let dir = std::env::temp_dir().join(format!("ficha-inode-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("game");
+        fs::write(&binary, b"#!/bin/sh\n").unwrap();
+        let other = dir.join("other");
+        fs::write(&other, b"#!/bin/sh\n").unwrap();
+        let marked = vec![MarkedBinary::new(binary.clone(), "game".to_string()).unwrap()];
+
+        // A hardlink, then the original renamed away, are still the marked binary
+        let hardlink = dir.join("not-a-game");
+        fs::hard_link(&binary, &hardlink).unwrap();
+        let renamed = dir.join("renamed");
+        fs::rename(&binary, &renamed).unwrap();
+        let found = |path: &Path| MarkedBinary::find(&marked, &fs::metadata(path).unwrap()).map(|b| b.name.clone());
+        let (by_hardlink, by_rename, by_other) = (found(&hardlink), found(&renamed), found(&other));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(by_hardlink.as_deref(), Some("game"));
+        assert_eq!(by_rename.as_deref(), Some("game"));
+        assert_eq!(by_other, None);
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let meta_len = mem::size_of::<libc::fanotify_event_metadata>() as u32;
+
+        // Records can carry extra info after the metadata, skipped by event_len
+        let mut buf = bytes_of(&event(meta_len + 8, FANOTIFY_METADATA_VERSION, 5, 100));
+        buf.extend_from_slice(&[0u8; 8]);
+        buf.extend(bytes_of(&event(meta_len, FANOTIFY_METADATA_VERSION, 6, 200)));
+        // A truncated trailing record is ignored
+        buf.extend_from_slice(&[0u8; 4]);
+
+        let events = ExecGuard::parse_events(&buf);
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].fd, events[0].pid), (5, 100));
+        assert_eq!((events[1].fd, events[1].pid), (6, 200));
+        assert_eq!(events[1].mask, libc::FAN_OPEN_EXEC_PERM);
+
+        // Parsing stops at an unknown version or a record shorter than the metadata
+        let mut buf = bytes_of(&event(meta_len, FANOTIFY_METADATA_VERSION, 5, 100));
+        buf.extend(bytes_of(&event(meta_len, FANOTIFY_METADATA_VERSION + 1, 6, 200)));
+        assert_eq!(ExecGuard::parse_events(&buf).len(), 1);
+
+        let buf = bytes_of(&event(4, FANOTIFY_METADATA_VERSION, 5, 100));
+        assert!(ExecGuard::parse_events(&buf).is_empty());
+        assert!(ExecGuard::parse_events(&[]).is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use base64::Engine;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Only formats the webview can render
+const EXTENSIONS: &[&str] = &["png", "svg"];
+
+/// One `[directory]` group from a theme's `index.theme`
+#[derive(Debug, Clone, PartialEq)]
+struct ThemeDir {
+    path: String,
+    size: u32,
+    kind: DirKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirKind {
+    Fixed,
+    Scalable { min: u32, max: u32 },
+    Threshold(u32),
+}
+
+impl ThemeDir {
+    fn matches(&self, size: u32) -> bool {
+        match self.kind {
+            DirKind::Fixed => self.size == size,
+            DirKind::Scalable { min, max } => (min..=max).contains(&size),
+            DirKind::Threshold(t) => size + t >= self.size && size <= self.size + t,
+        }
+    }
+
+    fn distance(&self, size: u32) -> u32 {
+        match self.kind {
+            DirKind::Fixed => self.size.abs_diff(size),
+            DirKind::Scalable { min, max } => {
+                if size < min {
+                    min - size
+                } else {
+                    size.saturating_sub(max)
+                }
+            }
+            DirKind::Threshold(t) => {
+                if size + t < self.size {
+                    self.size - t - size
+                } else {
+                    size.saturating_sub(self.size + t)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ThemeIndex {
+    dirs: Vec<ThemeDir>,
+    inherits: Vec<String>,
+}
+
+impl ThemeIndex {
+    fn parse(content: &str) -> Self {
+        let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current = String::new();
+
+        for line in content.lines().map(str::trim) {
+            if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = group.to_string();
+            } else if let Some((key, value)) = line.split_once('=') {
+                groups
+                    .entry(current.clone())
+                    .or_default()
+                    .insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+
+        let theme = groups.get("Icon Theme");
+        let list = |key: &str| -> Vec<String> {
+            theme
+                .and_then(|theme| theme.get(key))
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let dirs = list("Directories")
+            .into_iter()
+            .filter_map(|path| {
+                let group = groups.get(&path)?;
+                let number = |key: &str| group.get(key).and_then(|v| v.parse::<u32>().ok());
+
+                // HiDPI copies are only used for scaled lookups
+                if number("Scale").unwrap_or(1) != 1 {
+                    return None;
+                }
+
+                let size = number("Size")?;
+                let kind = match group.get("Type").map(String::as_str) {
+                    Some("Fixed") => DirKind::Fixed,
+                    Some("Scalable") => DirKind::Scalable {
+                        min: number("MinSize").unwrap_or(size),
+                        max: number("MaxSize").unwrap_or(size),
+                    },
+                    _ => DirKind::Threshold(number("Threshold").unwrap_or(2)),
+                };
+                Some(ThemeDir { path, size, kind })
+            })
+            .collect();
+
+        ThemeIndex {
+            dirs,
+            inherits: list("Inherits"),
+        }
+    }
+}
+
+/// Freedesktop icon theme lookup with a cache of resolved icons in the
+/// app data dir, so the UI can show the real icon of each protected app
+pub struct IconResolver {
+    base_dirs: Vec<PathBuf>,
+    cache_dir: PathBuf,
+}
+
+impl IconResolver {
+    pub fn new(cache_dir: PathBuf) -> Self {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+        // $HOME/.icons, then the XDG data dirs, then flatpak exports
+        let mut base_dirs: Vec<PathBuf> = home.iter().map(|home| home.join(".icons")).collect();
+        base_dirs.extend(data_home.map(|dir| dir.join("icons")));
+        base_dirs.extend(data_dirs.split(':').filter(|d| !d.is_empty()).map(|d| Path::new(d).join("icons")));
+        base_dirs.extend(home.map(|home| home.join(".local/share/flatpak/exports/share/icons")));
+        base_dirs.push(PathBuf::from("/var/lib/flatpak/exports/share/icons"));
+
+        IconResolver { base_dirs, cache_dir }
+    }
+
+    /// The desktop's icon theme from the GTK settings, if set
+    fn user_theme() -> Option<String> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        ["gtk-4.0", "gtk-3.0"].iter().find_map(|gtk| {
+            fs::read_to_string(config.join(gtk).join("settings.ini"))
+                .ok()?
+                .lines()
+                .find_map(|line| {
+                    let (key, value) = line.split_once('=')?;
+                    (key.trim() == "gtk-icon-theme-name").then(|| value.trim().to_string())
+                })
+        })
+    }
+
+    fn theme_index(&self, theme: &str) -> Option<ThemeIndex> {
+        self.base_dirs
+            .iter()
+            .find_map(|base| fs::read_to_string(base.join(theme).join("index.theme")).ok())
+            .map(|content| ThemeIndex::parse(&content))
+    }
+
+    fn find_in_dir(&self, theme: &str, dir: &ThemeDir, name: &str) -> Option<PathBuf> {
+        self.base_dirs.iter().find_map(|base| {
+            EXTENSIONS
+                .iter()
+                .map(|ext| base.join(theme).join(&dir.path).join(format!("{}.{}", name, ext)))
+                .find(|path| path.is_file())
+        })
+    }
+
+    fn find_in_theme(&self, theme: &str, index: &ThemeIndex, name: &str, size: u32) -> Option<PathBuf> {
+        // An exact size match wins, otherwise the closest directory
+        if let Some(path) = index
+            .dirs
+            .iter()
+            .filter(|dir| dir.matches(size))
+            .find_map(|dir| self.find_in_dir(theme, dir, name))
+        {
+            return Some(path);
+        }
+
+        let mut by_distance: Vec<&ThemeDir> = index.dirs.iter().collect();
+        by_distance.sort_by_key(|dir| dir.distance(size));
+        by_distance.into_iter().find_map(|dir| self.find_in_dir(theme, dir, name))
+    }
+
+    /// Resolve an `Icon=` value to a file: absolute paths as-is, otherwise
+    /// the user's theme and everything it inherits, then hicolor, then pixmaps
+    pub fn lookup(&self, icon: &str, size: u32) -> Option<PathBuf> {
+        let path = Path::new(icon);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+
+        // Some entries name the icon with its extension
+        let name = EXTENSIONS
+            .iter()
+            .find_map(|ext| icon.strip_suffix(&format!(".{}", ext)))
+            .unwrap_or(icon);
+
+        let mut queue: Vec<String> = Self::user_theme().into_iter().collect();
+        let mut visited: Vec<String> = Vec::new();
+        while let Some(theme) = queue.pop() {
+            if visited.contains(&theme) {
+                continue;
+            }
+            if let Some(index) = self.theme_index(&theme) {
+                if let Some(found) = self.find_in_theme(&theme, &index, name, size) {
+                    return Some(found);
+                }
+                queue.extend(index.inherits.into_iter().rev());
+            }
+            visited.push(theme);
+        }
+
+        if !visited.iter().any(|theme| theme == "hicolor") {
+            if let Some(index) = self.theme_index("hicolor") {
+                if let Some(found) = self.find_in_theme("hicolor", &index, name, size) {
+                    return Some(found);
+                }
+            }
+        }
+
+        EXTENSIONS
+            .iter()
+            .map(|ext| PathBuf::from(format!("/usr/share/pixmaps/{}.{}", name, ext)))
+            .find(|path| path.is_file())
+    }
+
+    fn cache_file(&self, icon: &str, size: u32, ext: &str) -> PathBuf {
+        let slug: String = icon
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+            .collect();
+        self.cache_dir.join(format!("{}-{}.{}", slug, size, ext))
+    }
+
+    /// The icon as a data URI, resolving and caching it on first use
+    pub fn data_uri(&self, icon: &str, size: u32) -> Result<Option<String>, String> {
+        let cached = EXTENSIONS
+            .iter()
+            .map(|ext| self.cache_file(icon, size, ext))
+            .find(|path| path.is_file());
+
+        let path = match cached {
+            Some(path) => path,
+            None => {
+                let source = match self.lookup(icon, size) {
+                    Some(source) => source,
+                    None => return Ok(None),
+                };
+                let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("png").to_lowercase();
+                if !EXTENSIONS.contains(&ext.as_str()) {
+                    return Ok(None);
+                }
+
+                let target = self.cache_file(icon, size, &ext);
+                fs::create_dir_all(&self.cache_dir)
+                    .map_err(|e| format!("Failed to create icon cache: {}", e))?;
+                fs::copy(&source, &target)
+                    .map_err(|e| format!("Failed to cache icon {:?}: {}", source, e))?;
+                target
+            }
+        };
+
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read icon {:?}: {}", path, e))?;
+        let mime = if path.extension().is_some_and(|ext| ext == "svg") {
+            "image/svg+xml"
+        } else {
+            "image/png"
+        };
+        Ok(Some(format!(
+            "data:{};base64,{}",
+            mime,
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_index_lookup_order() {
+        let index = ThemeIndex::parse(
+            "[Icon Theme]\nName=Test\nInherits=Adwaita,hicolor\nDirectories=16x16/apps,48x48/apps,scalable/apps,48x48@2/apps\n\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\n\
+             [48x48/apps]\nSize=48\n\n\
+             [scalable/apps]\nSize=128\nType=Scalable\nMinSize=8\nMaxSize=512\n\n\
+             [48x48@2/apps]\nSize=48\nScale=2\n",
+        );
+
+        assert_eq!(index.inherits, vec!["Adwaita", "hicolor"]);
+        assert_eq!(index.dirs.len(), 3);
+        assert!(index.dirs[0].matches(16) && !index.dirs[0].matches(17));
+        assert!(index.dirs[1].matches(50) && !index.dirs[1].matches(51));
+        assert!(!index.dirs[2].matches(600) && index.dirs[2].matches(256));
+        assert_eq!(index.dirs[0].distance(24), 8);
+        assert_eq!(index.dirs[1].distance(24), 22);
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::fs;
+
+// Where the AppImage runtime mounts the image it runs from
+const APPIMAGE_MOUNT_PREFIX: &str = "/tmp/.mount_";
+
+/// Where a sandboxed or bundled app came from, independent of the comm
+/// name and paths it shows inside its own mount namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum AppIdentity {
+    /// Flatpak application ID, e.g. `org.telegram.desktop`
+    Flatpak(String),
+    /// Snap name, e.g. `spotify`
+    Snap(String),
+    /// Path of the `.AppImage` file the process was mounted from
+    AppImage(String),
+}
+
+impl AppIdentity {
+    /// Resolve `pid` to its Flatpak, Snap or AppImage identity, if any
+    pub fn of(pid: i32) -> Option<Self> {
+        let cgroup = fs::read_to_string(format!("/proc/{}/cgroup", pid)).unwrap_or_default();
+
+        Self::flatpak_of(pid, &cgroup)
+            .or_else(|| Self::snap_from_cgroup(&cgroup).map(AppIdentity::Snap))
+            .or_else(|| Self::appimage_of(pid).map(AppIdentity::AppImage))
+    }
+
+    fn flatpak_of(pid: i32, cgroup: &str) -> Option<Self> {
+        // Only readable for our own user's sandboxes (or as root)
+        fs::read_to_string(format!("/proc/{}/root/.flatpak-info", pid))
+            .ok()
+            .and_then(|info| Self::parse_flatpak_info(&info))
+            .or_else(|| Self::flatpak_from_cgroup(cgroup))
+            .map(AppIdentity::Flatpak)
+    }
+
+    /// `name=` from the `[Application]` group of `.flatpak-info`
+    fn parse_flatpak_info(info: &str) -> Option<String> {
+        let mut in_application = false;
+        for line in info.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_application = line == "[Application]";
+            } else if in_application {
+                if let Some(name) = line.strip_prefix("name=") {
+                    return Some(name.trim().to_string());
+                }
+            }
+        }
+        None
+    }
+
+    /// systemd scopes named `app-flatpak-<app id>-<instance>.scope`
+    fn flatpak_from_cgroup(cgroup: &str) -> Option<String> {
+        Self::cgroup_components(cgroup).find_map(|component| {
+            let rest = component.strip_prefix("app-flatpak-")?.strip_suffix(".scope")?;
+            let (app_id, instance) = rest.rsplit_once('-')?;
+            instance
+                .chars()
+                .all(|c| c.is_ascii_digit())
+                .then(|| app_id.to_string())
+        })
+    }
+
+    /// Scopes and services named `snap.<name>.<app>...`
+    fn snap_from_cgroup(cgroup: &str) -> Option<String> {
+        Self::cgroup_components(cgroup).find_map(|component| {
+            let rest = component.strip_prefix("snap.")?;
+            let (name, _) = rest.split_once('.')?;
+            (!name.is_empty()).then(|| name.to_string())
+        })
+    }
+
+    fn cgroup_components(cgroup: &str) -> impl Iterator<Item = &str> {
+        cgroup
+            .lines()
+            .filter_map(|line| line.splitn(3, ':').nth(2))
+            .flat_map(|path| path.split('/'))
+    }
+
+    /// The AppImage runtime exports the image's path as `APPIMAGE`. Child
+    /// processes inherit it, so only binaries inside the image's mount count.
+    fn appimage_of(pid: i32) -> Option<String> {
+        let exe = fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+        if !Self::is_appimage_binary(&exe.to_string_lossy()) {
+            return None;
+        }
+        let environ = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+        environ
+            .split(|b| *b == 0)
+            .find_map(|var| var.strip_prefix(b"APPIMAGE="))
+            .map(|path| String::from_utf8_lossy(path).to_string())
+            .filter(|path| !path.is_empty())
+    }
+
+    fn is_appimage_binary(exe: &str) -> bool {
+        exe.strip_prefix(APPIMAGE_MOUNT_PREFIX)
+            .is_some_and(|rest| rest.contains('/'))
+    }
+}
+
+/// A process's identity, read from `/proc` at most once however many rules
+/// are compared against it
+pub struct LazyIdentity {
+    pid: i32,
+    identity: OnceCell<Option<AppIdentity>>,
+}
+
+impl LazyIdentity {
+    pub fn new(pid: i32) -> Self {
+        LazyIdentity { pid, identity: OnceCell::new() }
+    }
+
+    pub fn get(&self) -> Option<&AppIdentity> {
+        self.identity.get_or_init(|| AppIdentity::of(self.pid)).as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flatpak_info() {
+        let info = "[Application]\nname=org.telegram.desktop\nruntime=runtime/org.freedesktop.Platform/x86_64/23.08\n\n[Instance]\ninstance-id=123\n";
+        assert_eq!(AppIdentity::parse_flatpak_info(info), Some("org.telegram.desktop".to_string()));
+        assert_eq!(AppIdentity::parse_flatpak_info("[Runtime]\nname=org.gnome.Platform\n"), None);
+    }
+
+    #[test]
+    fn test_identity_from_cgroup() {
+        let flatpak = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-flatpak-com.spotify.Client-48291.scope\n";
+        assert_eq!(AppIdentity::flatpak_from_cgroup(flatpak), Some("com.spotify.Client".to_string()));
+
+        let snap = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/snap.firefox.firefox-5b1a.scope\n";
+        assert_eq!(AppIdentity::snap_from_cgroup(snap), Some("firefox".to_string()));
+        assert_eq!(AppIdentity::flatpak_from_cgroup(snap), None);
+
+        let plain = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(AppIdentity::snap_from_cgroup(plain), None);
+    }
+
+    #[test]
+    fn test_appimage_children_outside_the_mount_are_not_the_appimage() {
+        assert!(AppIdentity::is_appimage_binary("/tmp/.mount_ObsidiXy12Ab/obsidian"));
+        assert!(!AppIdentity::is_appimage_binary("/usr/bin/xdg-open"));
+        assert!(!AppIdentity::is_appimage_binary("/tmp/.mount_ObsidiXy12Ab"));
+    }
+}
@@ -1,23 +1,500 @@
+mod allowlist;
 mod auth;
 mod autostart;
+mod cgroup;
+mod conversation;
 mod database;
+mod desktop;
+mod detection;
+mod elevation;
+mod fanotify;
+mod icons;
 mod idle;
+mod identity;
+mod matching;
 mod monitor;
+mod policy_file;
+mod quota;
+mod schedule;
+mod session;
 mod state;
 mod stealth;
-
-use database::{Database, ProtectedApp, SecurityLog, SecurityPolicy};
-use monitor::{ProcessMonitor, AppCandidate, ProcessInfo};
+mod throttle;
+mod totp;
+
+use allowlist::{AllowedExecutable, AllowlistConfig, PolicyMode};
+use elevation::ElevationExemption;
+use database::{Database, Profile, ProtectedApp, SecurityLog, SecurityPolicy, UnlockGrant};
+use matching::{CompiledRule, MatchRule};
+use quota::{QuotaEvent, QuotaStatus};
+use schedule::{CompiledSchedule, Schedule};
+use monitor::{ProcessMonitor, AppCandidate, EnforcementBackend, KillReason, KillReport, ProcessInfo, TerminationStrategy};
+use session::CommandError;
 use state::{AppState, ShieldStatus};
+use throttle::AuthOutcome;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager, State};
 use chrono::Utc;
 
 // Tauri commands
 
+/// Run every unlock check through the throttle, logging and emitting the
+/// outcome. `Ok(false)` when the password or TOTP code was wrong; errors
+/// while attempts are throttled.
+async fn check_unlock(
+    state: &Arc<AppState>,
+    app_handle: &AppHandle,
+    password: String,
+    totp_code: Option<String>,
+) -> Result<bool, String> {
+    // PAM may block on prompts relayed to the UI, so keep it off the
+    // async workers that serve `respond_pam_prompt`
+    let shared = state.clone();
+    let relay_handle = app_handle.clone();
+    let (outcome, second_factor) = tauri::async_runtime::spawn_blocking(move || {
+        let relay = conversation::UiRelay {
+            app_handle: &relay_handle,
+            broker: &shared.pam_prompts,
+        };
+        let mut second_factor = None;
+        let outcome = shared.auth_throttle.attempt(|| {
+            match auth::AuthManager::verify_unlock(&shared.database, &password, totp_code.as_deref(), &relay)? {
+                auth::UnlockCheck::Granted { second_factor: factor } => {
+                    second_factor = factor;
+                    Ok(true)
+                }
+                auth::UnlockCheck::Denied => Ok(false),
+            }
+        })?;
+        Ok::<_, String>((outcome, second_factor))
+    })
+    .await
+    .map_err(|e| format!("Authentication task failed: {}", e))??;
+
+    match outcome {
+        AuthOutcome::Success => {
+            if second_factor == Some(totp::SecondFactor::RecoveryCode) {
+                let left = totp::TotpManager::recovery_codes_left(&state.database)?;
+                let log = state.database.add_security_log(
+                    format!("Unlocked with a recovery code ({} left)", left),
+                    "warning".to_string(),
+                    None,
+                ).map_err(|e| e.to_string())?;
+                app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+            }
+            Ok(true)
+        }
+        AuthOutcome::Failed { failed_attempts, throttle } => {
+            let log = state.database.add_security_log(
+                format!("Failed unlock attempt ({} in a row)", failed_attempts),
+                "warning".to_string(),
+                None,
+            ).map_err(|e| e.to_string())?;
+            app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+            if let Some(status) = throttle {
+                app_handle.emit("auth-throttled", &status).map_err(|e| e.to_string())?;
+            }
+            Ok(false)
+        }
+        AuthOutcome::Throttled(status) => {
+            app_handle.emit("auth-throttled", &status).map_err(|e| e.to_string())?;
+            Err(format!("Too many failed attempts, try again in {}s", status.remaining_secs))
+        }
+    }
+}
+
+/// Returns a session token for privileged commands, or `None` when the
+/// password or TOTP code was wrong. Errors while attempts are throttled.
+#[tauri::command]
+async fn authenticate(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    password: String,
+    totp_code: Option<String>,
+) -> Result<Option<String>, String> {
+    if check_unlock(state.inner(), &app_handle, password, totp_code).await? {
+        Ok(Some(state.sessions.issue()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Longest temporary unlock, in minutes
+const MAX_GRANT_MINUTES: i64 = 12 * 60;
+
+/// Record a temporary unlock for one app, log it and tell the UI
+fn create_unlock_grant(
+    state: &AppState,
+    app_handle: &AppHandle,
+    app_id: &str,
+    minutes: i64,
+    kill_on_expiry: bool,
+) -> Result<UnlockGrant, String> {
+    if !(1..=MAX_GRANT_MINUTES).contains(&minutes) {
+        return Err(format!("Unlock duration must be between 1 and {} minutes", MAX_GRANT_MINUTES));
+    }
+
+    let expires_at = (Utc::now() + chrono::Duration::minutes(minutes)).to_rfc3339();
+    let grant = state.database.add_unlock_grant(app_id, &expires_at, kill_on_expiry)
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => "Protected app not found".to_string(),
+            e => e.to_string(),
+        })?;
+    state.update_protected_processes()?;
+
+    let log = state.database.add_security_log(
+        format!(
+            "Temporary unlock granted for {} ({} min{})",
+            grant.app_name,
+            minutes,
+            if kill_on_expiry { ", closes on expiry" } else { "" }
+        ),
+        "info".to_string(),
+        Some(grant.process_name.clone()),
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+    emit_unlock_grants(state, app_handle);
+
+    Ok(grant)
+}
+
+fn emit_unlock_grants(state: &AppState, app_handle: &AppHandle) {
+    if let Ok(grants) = state.database.get_unlock_grants() {
+        let _ = app_handle.emit("unlock-grants", &grants);
+    }
+}
+
+/// Authenticate from the lock screen for a single app, leaving the shield
+/// locked for everything else. `None` when the credentials were wrong.
+#[tauri::command]
+async fn authenticate_app(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    password: String,
+    totp_code: Option<String>,
+    app_id: String,
+    minutes: i64,
+    kill_on_expiry: bool,
+) -> Result<Option<UnlockGrant>, String> {
+    if !check_unlock(state.inner(), &app_handle, password, totp_code).await? {
+        return Ok(None);
+    }
+    create_unlock_grant(&state, &app_handle, &app_id, minutes, kill_on_expiry).map(Some)
+}
+
+/// Grant a temporary unlock from the dashboard, for use once locked
+#[tauri::command]
+async fn grant_temporary_unlock(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    app_id: String,
+    minutes: i64,
+    kill_on_expiry: bool,
+) -> Result<UnlockGrant, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "grant_temporary_unlock")?;
+    Ok(create_unlock_grant(&state, &app_handle, &app_id, minutes, kill_on_expiry)?)
+}
+
+#[tauri::command]
+async fn revoke_unlock_grant(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    app_id: String,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "revoke_unlock_grant")?;
+
+    let grant = state.database.get_unlock_grants()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|grant| grant.app_id == app_id);
+    let grant = match grant {
+        Some(grant) => grant,
+        None => return Err(CommandError::Failed("No active unlock for this app".to_string())),
+    };
+
+    state.database.remove_unlock_grant(&app_id).map_err(|e| e.to_string())?;
+    // Same as expiry: instances started under the grant keep running unless it kills them
+    if !grant.kill_on_expiry {
+        state.monitor.spare_running(&grant.process_name);
+    }
+    state.update_protected_processes()?;
+    if grant.kill_on_expiry {
+        state.monitor.request_sweep();
+    }
+
+    let log = state.database.add_security_log(
+        format!("Temporary unlock for {} revoked", grant.app_name),
+        "info".to_string(),
+        Some(grant.process_name.clone()),
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+    emit_unlock_grants(&state, &app_handle);
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_unlock_grants(state: State<'_, Arc<AppState>>) -> Result<Vec<UnlockGrant>, String> {
+    state.database.get_unlock_grants().map_err(|e| e.to_string())
+}
+
+/// Remaining wait before unlocking is allowed again, for the lock screen
+#[tauri::command]
+async fn get_auth_throttle(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<throttle::ThrottleStatus>, String> {
+    state.auth_throttle.current()
+}
+
 #[tauri::command]
-async fn authenticate(password: String) -> Result<bool, String> {
-    auth::AuthManager::authenticate_current_user(&password)
+async fn get_auth_config(state: State<'_, Arc<AppState>>) -> Result<auth::AuthConfig, String> {
+    auth::AuthManager::config(&state.database)
+}
+
+/// First-run setup of the vault passphrase. Only allowed while the
+/// passphrase method is active and none exists; returns a session token.
+#[tauri::command]
+async fn setup_passphrase(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    passphrase: String,
+) -> Result<String, String> {
+    let config = auth::AuthManager::config(&state.database)?;
+    if config.method != auth::AuthMethod::Passphrase || config.passphrase_set {
+        return Err("A vault passphrase is already configured".to_string());
+    }
+
+    auth::AuthManager::set_passphrase(&state.database, &passphrase)?;
+
+    let log = state.database.add_security_log(
+        "Vault passphrase created".to_string(),
+        "success".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(state.sessions.issue())
+}
+
+/// Set or replace the vault passphrase. An existing passphrase must be
+/// confirmed, and wrong guesses count towards the unlock throttle.
+#[tauri::command]
+async fn change_passphrase(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    current: Option<String>,
+    passphrase: String,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "change_passphrase")?;
+
+    if auth::AuthManager::config(&state.database)?.passphrase_set {
+        let current = current.unwrap_or_default();
+        let outcome = state.auth_throttle.attempt(|| {
+            auth::AuthManager::verify_passphrase(&state.database, &current)
+        })?;
+        match outcome {
+            AuthOutcome::Success => {}
+            AuthOutcome::Failed { .. } => {
+                let log = state.database.add_security_log(
+                    "Rejected passphrase change: current passphrase incorrect".to_string(),
+                    "warning".to_string(),
+                    None,
+                ).map_err(|e| e.to_string())?;
+                app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+                return Err(CommandError::Failed("Current passphrase is incorrect".to_string()));
+            }
+            AuthOutcome::Throttled(status) => {
+                return Err(CommandError::Failed(format!(
+                    "Too many failed attempts, try again in {}s",
+                    status.remaining_secs
+                )));
+            }
+        }
+    }
+
+    auth::AuthManager::set_passphrase(&state.database, &passphrase)?;
+
+    let log = state.database.add_security_log(
+        "Vault passphrase changed".to_string(),
+        "success".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Start TOTP enrollment. The secret only takes effect once
+/// `confirm_totp_enrollment` verifies a code from the authenticator.
+#[tauri::command]
+async fn begin_totp_enrollment(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+) -> Result<totp::TotpEnrollment, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "begin_totp_enrollment")?;
+
+    let account = auth::AuthManager::get_current_user()?;
+    Ok(totp::TotpManager::begin_enrollment(&state.database, &account)?)
+}
+
+/// Returns the recovery codes, which are only ever shown here
+#[tauri::command]
+async fn confirm_totp_enrollment(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    code: String,
+) -> Result<Vec<String>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "confirm_totp_enrollment")?;
+
+    let account = auth::AuthManager::get_current_user()?;
+    let recovery_codes = totp::TotpManager::confirm_enrollment(&state.database, &account, &code)?;
+
+    let log = state.database.add_security_log(
+        "TOTP second factor enrolled".to_string(),
+        "success".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(recovery_codes)
+}
+
+/// Remove the second factor. Needs a current TOTP or recovery code.
+#[tauri::command]
+async fn disable_totp(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    code: String,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "disable_totp")?;
+
+    let account = auth::AuthManager::get_current_user()?;
+    if totp::TotpManager::verify(&state.database, &account, &code)?.is_none() {
+        return Err(CommandError::Failed("Invalid verification code".to_string()));
+    }
+    totp::TotpManager::disable(&state.database)?;
+
+    let log = state.database.add_security_log(
+        "TOTP second factor removed".to_string(),
+        "warning".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn regenerate_recovery_codes(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+) -> Result<Vec<String>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "regenerate_recovery_codes")?;
+
+    if !totp::TotpManager::is_enabled(&state.database)? {
+        return Err(CommandError::Failed("TOTP is not enabled".to_string()));
+    }
+    let recovery_codes = totp::TotpManager::regenerate_recovery_codes(&state.database)?;
+
+    let log = state.database.add_security_log(
+        "TOTP recovery codes regenerated".to_string(),
+        "info".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(recovery_codes)
+}
+
+#[tauri::command]
+async fn get_recovery_codes_left(state: State<'_, Arc<AppState>>) -> Result<i64, String> {
+    totp::TotpManager::recovery_codes_left(&state.database)
+}
+
+/// Answer a `pam-prompt` event; `None` cancels the conversation
+#[tauri::command]
+async fn respond_pam_prompt(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    response: Option<String>,
+) -> Result<(), String> {
+    state.pam_prompts.respond(&id, response)
+}
+
+#[tauri::command]
+async fn set_pam_service(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    service: String,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_pam_service")?;
+
+    let service = service.trim().to_string();
+    auth::AuthManager::set_pam_service(&state.database, &service)?;
+
+    let log = state.database.add_security_log(
+        format!("PAM service set to '{}'", service),
+        "info".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_auth_method(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+    method: auth::AuthMethod,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_auth_method")?;
+
+    auth::AuthManager::set_method(&state.database, method)?;
+
+    let description = match method {
+        auth::AuthMethod::System => "system password",
+        auth::AuthMethod::Passphrase => "vault passphrase",
+    };
+    let log = state.database.add_security_log(
+        format!("Unlock method set to {}", description),
+        "info".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Reject a privileged command unless `token` belongs to the live session,
+/// logging the denial
+fn require_session(
+    state: &AppState,
+    app_handle: &AppHandle,
+    token: Option<&str>,
+    command: &str,
+) -> Result<(), CommandError> {
+    state.sessions.validate(token).inspect_err(|e| {
+        eprintln!("Rejected {}: {}", command, e);
+        if let Ok(log) = state.database.add_security_log(
+            format!("Rejected {} without a valid session ({})", command, e),
+            "warning".to_string(),
+            None,
+        ) {
+            let _ = app_handle.emit("security-log", &log);
+        }
+    })
 }
 
 #[tauri::command]
@@ -31,10 +508,32 @@ async fn get_shield_status(state: State<'_, Arc<AppState>>) -> Result<ShieldStat
 }
 
 #[tauri::command]
-async fn activate_shield(state: State<'_, Arc<AppState>>) -> Result<(), String> {
-    state.activate_shield();
+async fn activate_shield(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "activate_shield")?;
+
+    let resumed = state.activate_shield();
     // Reset idle timer when user becomes active
     state.idle_tracker.reset();
+    // User policy edits made while locked
+    if user_policy_pending(&state) {
+        reload_policy_files(&app_handle, &state, true);
+    }
+
+    if resumed > 0 {
+        let log = state.database.add_security_log(
+            format!("Resumed {} frozen processes after unlock", resumed),
+            "info".to_string(),
+            None,
+        ).map_err(|e| e.to_string())?;
+        app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+        app_handle.emit("apps-thawed", resumed).map_err(|e| e.to_string())?;
+    }
+    app_handle.emit("shield-status", ShieldStatus::ACTIVE).map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -67,6 +566,7 @@ async fn get_protected_apps(state: State<'_, Arc<AppState>>) -> Result<Vec<Prote
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn add_protected_app(
     state: State<'_, Arc<AppState>>,
     app_handle: AppHandle,
@@ -74,9 +574,17 @@ async fn add_protected_app(
     process_name: String,
     icon: String,
     category: String,
-) -> Result<ProtectedApp, String> {
+    match_rule: Option<MatchRule>,
+    icon_name: Option<String>,
+    token: Option<String>,
+) -> Result<ProtectedApp, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "add_protected_app")?;
+
+    let match_rule = match_rule.unwrap_or_else(|| MatchRule::default_for(&process_name));
+    CompiledRule::new(&match_rule)?;
+
     let app = state.database
-        .add_protected_app(name.clone(), process_name, icon, category)
+        .add_protected_app(name.clone(), process_name, icon, category, match_rule, icon_name)
         .map_err(|e| e.to_string())?;
 
     // Update the monitor's protected process list
@@ -96,12 +604,36 @@ async fn add_protected_app(
     Ok(app)
 }
 
+/// Refuse in-app changes to an app a policy file declares
+fn ensure_not_managed(state: &AppState, id: &str) -> Result<(), String> {
+    let app = state.database.get_protected_apps()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|app| app.id == id);
+    match app.and_then(|app| app.source.map(|source| (app.name, source))) {
+        Some((name, source)) => Err(format!("{} is managed by {}", name, source)),
+        None => Ok(()),
+    }
+}
+
+/// Refuse in-app changes to a setting a policy file declares
+fn ensure_setting_not_managed(key: &str, declared: bool) -> Result<(), String> {
+    if declared {
+        return Err(format!("{} is set in policy.toml", key));
+    }
+    Ok(())
+}
+
 #[tauri::command]
 async fn remove_protected_app(
     state: State<'_, Arc<AppState>>,
     app_handle: AppHandle,
     id: String,
-) -> Result<(), String> {
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "remove_protected_app")?;
+    ensure_not_managed(&state, &id)?;
+
     state.database.remove_protected_app(&id).map_err(|e| e.to_string())?;
 
     // Update the monitor's protected process list
@@ -113,6 +645,253 @@ async fn remove_protected_app(
     Ok(())
 }
 
+#[tauri::command]
+async fn update_match_rule(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    match_rule: MatchRule,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "update_match_rule")?;
+    ensure_not_managed(&state, &id)?;
+
+    // Reject rules that don't compile before they reach the monitor
+    CompiledRule::new(&match_rule)?;
+
+    state.database.set_match_rule(&id, &match_rule)
+        .map_err(|e| e.to_string())?;
+
+    // Update the monitor's protected process list
+    state.update_protected_processes()?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_termination_strategy(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    strategy: TerminationStrategy,
+    grace_period_secs: i64,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_termination_strategy")?;
+    ensure_not_managed(&state, &id)?;
+
+    let grace = grace_period_secs.clamp(1, 120);
+    state.database.set_termination_strategy(&id, strategy, grace)
+        .map_err(|e| e.to_string())?;
+
+    // Update the monitor's protected process list
+    state.update_protected_processes()?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_hash_pinning(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    enabled: bool,
+    token: Option<String>,
+) -> Result<Option<String>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_hash_pinning")?;
+    ensure_not_managed(&state, &id)?;
+
+    let app = state.database.get_protected_apps()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|app| app.id == id)
+        .ok_or_else(|| format!("No protected app with id {}", id))?;
+
+    let hash = if enabled {
+        Some(ProcessMonitor::hash_for_app(&app.process_name, &app.match_rule)?)
+    } else {
+        None
+    };
+
+    state.database.set_exe_sha256(&id, hash.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    // Update the monitor's protected process list
+    state.update_protected_processes()?;
+
+    let event = match &hash {
+        Some(hash) => format!("Executable hash pinned for {}: {}", app.name, &hash[..16]),
+        None => format!("Executable hash unpinned for {}", app.name),
+    };
+    let log = state.database.add_security_log(event, "info".to_string(), Some(app.name))
+        .map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(hash)
+}
+
+/// Enforce an app by weekly schedule instead of by the shield, or go back
+/// to shield-only enforcement with `None`
+#[tauri::command]
+async fn set_app_schedule(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    schedule: Option<Schedule>,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_app_schedule")?;
+    ensure_not_managed(&state, &id)?;
+
+    if let Some(schedule) = &schedule {
+        CompiledSchedule::new(schedule)?;
+    }
+
+    let app = state.database.get_protected_apps()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|app| app.id == id)
+        .ok_or_else(|| format!("No protected app with id {}", id))?;
+
+    state.database.set_schedule(&id, schedule.as_ref())
+        .map_err(|e| e.to_string())?;
+
+    // Update the monitor's protected process list
+    state.update_protected_processes()?;
+
+    let event = match &schedule {
+        Some(schedule) => format!(
+            "Schedule set for {}: {} window(s), {} exception(s)",
+            app.name,
+            schedule.windows.len(),
+            schedule.exceptions.len()
+        ),
+        None => format!("Schedule removed for {}", app.name),
+    };
+    let log = state.database.add_security_log(event, "info".to_string(), Some(app.name))
+        .map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Limit how long an app may run per day while allowed, or lift the limit
+/// with `None`
+#[tauri::command]
+async fn set_app_quota(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    minutes: Option<i64>,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_app_quota")?;
+    ensure_not_managed(&state, &id)?;
+
+    let minutes = minutes.map(|minutes| minutes.clamp(1, 24 * 60));
+    let app = state.database.get_protected_apps()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|app| app.id == id)
+        .ok_or_else(|| format!("No protected app with id {}", id))?;
+
+    state.database.set_daily_quota(&id, minutes)
+        .map_err(|e| e.to_string())?;
+
+    // A limit below today's usage closes running instances right away
+    state.refresh_quotas()?;
+
+    let event = match minutes {
+        Some(minutes) => format!("Daily quota for {} set to {} minutes", app.name, minutes),
+        None => format!("Daily quota removed for {}", app.name),
+    };
+    let log = state.database.add_security_log(event, "info".to_string(), Some(app.name))
+        .map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Budget left today for every app with a daily quota
+#[tauri::command]
+async fn get_quota_status(state: State<'_, Arc<AppState>>) -> Result<Vec<QuotaStatus>, String> {
+    state.quotas.status()
+}
+
+/// Report matches for one app without enforcing them
+#[tauri::command]
+async fn set_app_audit_only(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    enabled: bool,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_app_audit_only")?;
+    ensure_not_managed(&state, &id)?;
+
+    let app = state.database.get_protected_apps()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|app| app.id == id)
+        .ok_or_else(|| format!("No protected app with id {}", id))?;
+
+    state.database.set_audit_only(&id, enabled)
+        .map_err(|e| e.to_string())?;
+
+    // Update the monitor's protected process list
+    state.update_protected_processes()?;
+    if !enabled {
+        // Instances already reported are enforced too, not just new ones
+        state.monitor.forget_reported();
+    }
+
+    let event = if enabled {
+        format!("Audit-only mode enabled for {}", app.name)
+    } else {
+        format!("Audit-only mode disabled for {}, now enforced", app.name)
+    };
+    let log = state.database.add_security_log(event, "info".to_string(), Some(app.name))
+        .map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Re-hash every pinned app that isn't policy-managed, e.g. after a package
+/// update replaced the binary
+#[tauri::command]
+async fn refresh_pinned_hashes(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+) -> Result<Vec<ProtectedApp>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "refresh_pinned_hashes")?;
+
+    let apps = state.database.get_protected_apps().map_err(|e| e.to_string())?;
+
+    for app in apps.iter().filter(|app| app.exe_sha256.is_some() && app.source.is_none()) {
+        let (event, log_type) = match ProcessMonitor::hash_for_app(&app.process_name, &app.match_rule) {
+            Ok(hash) if Some(&hash) == app.exe_sha256.as_ref() => continue,
+            Ok(hash) => {
+                state.database.set_exe_sha256(&app.id, Some(&hash))
+                    .map_err(|e| e.to_string())?;
+                (format!("Pinned hash refreshed for {}: {}", app.name, &hash[..16]), "info")
+            }
+            Err(e) => (format!("Could not refresh pinned hash for {}: {}", app.name, e), "warning"),
+        };
+
+        if let Ok(log) = state.database.add_security_log(event, log_type.to_string(), Some(app.name.clone())) {
+            let _ = app_handle.emit("security-log", &log);
+        }
+    }
+
+    // Update the monitor's protected process list
+    state.update_protected_processes()?;
+
+    Ok(state.database.get_protected_apps().map_err(|e| e.to_string())?)
+}
+
 #[tauri::command]
 async fn get_security_logs(
     state: State<'_, Arc<AppState>>,
@@ -122,44 +901,179 @@ async fn get_security_logs(
 }
 
 #[tauri::command]
-async fn get_security_policies(state: State<'_, Arc<AppState>>) -> Result<Vec<SecurityPolicy>, String> {
-    state.database.get_security_policies().map_err(|e| e.to_string())
+async fn get_security_policies(state: State<'_, Arc<AppState>>) -> Result<Vec<SecurityPolicy>, String> {
+    state.database.get_security_policies().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn toggle_security_policy(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "toggle_security_policy")?;
+    if let Some(key) = state.managed_policy.lock().unwrap().policies.declared_key(&id) {
+        return Err(format!("policies.{} is set in policy.toml", key).into());
+    }
+
+    state.database.toggle_policy(&id).map_err(|e| e.to_string())?;
+
+    // Handle special policies
+    let is_enabled = state.database.is_policy_enabled(&id).map_err(|e| e.to_string())?;
+    state.apply_policy(&id, is_enabled)?;
+
+    // Emit event to frontend
+    app_handle.emit("policy-toggled", id).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_profiles(state: State<'_, Arc<AppState>>) -> Result<Vec<Profile>, String> {
+    state.database.get_profiles().map_err(|e| e.to_string())
+}
+
+/// New profile, empty or as a copy of the active one
+#[tauri::command]
+async fn create_profile(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    name: String,
+    copy_current: bool,
+    token: Option<String>,
+) -> Result<Profile, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "create_profile")?;
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is required".to_string().into());
+    }
+    let profiles = state.database.get_profiles().map_err(|e| e.to_string())?;
+    if profiles.iter().any(|profile| profile.name.eq_ignore_ascii_case(&name)) {
+        return Err(format!("A profile named {} already exists", name).into());
+    }
+
+    let profile = state.database.create_profile(&name, copy_current).map_err(|e| e.to_string())?;
+
+    let log = state.database.add_security_log(
+        format!("Profile created: {}", name),
+        "info".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(profile)
+}
+
+/// Make another profile active; its apps, policies and idle timeout take
+/// effect immediately
+#[tauri::command]
+async fn switch_profile(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    token: Option<String>,
+) -> Result<Profile, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "switch_profile")?;
+
+    let profile = state.switch_profile(&id)?;
+
+    let log = state.database.add_security_log(
+        format!("Switched to profile: {}", profile.name),
+        "warning".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+    app_handle.emit("profile-changed", &profile).map_err(|e| e.to_string())?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+async fn delete_profile(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: String,
+    token: Option<String>,
+) -> Result<Vec<Profile>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "delete_profile")?;
+
+    let profiles = state.database.get_profiles().map_err(|e| e.to_string())?;
+    let profile = profiles.iter().find(|profile| profile.id == id)
+        .ok_or_else(|| "Profile not found".to_string())?;
+    if profile.active {
+        return Err("Switch to another profile before deleting this one".to_string().into());
+    }
+
+    state.database.delete_profile(&id).map_err(|e| e.to_string())?;
+
+    let log = state.database.add_security_log(
+        format!("Profile deleted: {}", profile.name),
+        "warning".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(state.database.get_profiles().map_err(|e| e.to_string())?)
+}
+
+#[tauri::command]
+async fn get_elevation_exemptions(state: State<'_, Arc<AppState>>) -> Result<Vec<ElevationExemption>, String> {
+    state.database.get_elevation_exemptions().map_err(|e| e.to_string())
 }
 
+/// Let a command be elevated while locked, e.g. "systemctl suspend", or
+/// with a final `*` also with further arguments
 #[tauri::command]
-async fn toggle_security_policy(
+async fn add_elevation_exemption(
     state: State<'_, Arc<AppState>>,
     app_handle: AppHandle,
-    id: String,
-) -> Result<(), String> {
-    state.database.toggle_policy(&id).map_err(|e| e.to_string())?;
+    command: String,
+    token: Option<String>,
+) -> Result<Vec<ElevationExemption>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "add_elevation_exemption")?;
+
+    let words: Vec<&str> = command.split_whitespace().collect();
+    if words.is_empty() {
+        return Err("Command is required".to_string().into());
+    }
+    if words[..words.len() - 1].contains(&"*") || words == ["*"] {
+        return Err("`*` may only end a command, to allow further arguments".to_string().into());
+    }
+    if elevation::system_program_name(words[0]).is_none() {
+        return Err(format!(
+            "{} must be a program name or a path under /usr/bin, /usr/sbin, /bin or /sbin",
+            words[0]
+        ).into());
+    }
+    let command = words.join(" ");
+    state.database.add_elevation_exemption(&command).map_err(|e| e.to_string())?;
+    state.update_elevation_guard()?;
 
-    // Handle special policies
-    let is_enabled = state.database.is_policy_enabled(&id).map_err(|e| e.to_string())?;
+    let log = state.database.add_security_log(
+        format!("Root elevation exemption added: {}", command),
+        "warning".to_string(),
+        None,
+    ).map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
 
-    match id.as_str() {
-        "policy_2" => {
-            // Stealth Mode
-            if is_enabled {
-                stealth::StealthMode::enable()?;
-            } else {
-                stealth::StealthMode::disable()?;
-            }
-        },
-        "policy_4" => {
-            // Session Lock on Idle
-            state.idle_tracker.set_enabled(is_enabled);
-            if is_enabled {
-                state.idle_tracker.reset();
-            }
-        },
-        _ => {}
-    }
+    Ok(state.database.get_elevation_exemptions().map_err(|e| e.to_string())?)
+}
 
-    // Emit event to frontend
-    app_handle.emit("policy-toggled", id).map_err(|e| e.to_string())?;
+#[tauri::command]
+async fn remove_elevation_exemption(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: i64,
+    token: Option<String>,
+) -> Result<Vec<ElevationExemption>, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "remove_elevation_exemption")?;
 
-    Ok(())
+    state.database.remove_elevation_exemption(id).map_err(|e| e.to_string())?;
+    state.update_elevation_guard()?;
+
+    Ok(state.database.get_elevation_exemptions().map_err(|e| e.to_string())?)
 }
 
 #[tauri::command]
@@ -197,6 +1111,21 @@ async fn get_app_candidates() -> Result<Vec<AppCandidate>, String> {
     Ok(result)
 }
 
+/// Real app icon as a data URI, or `None` when the theme has no such icon
+#[tauri::command]
+async fn get_app_icon(
+    app_handle: AppHandle,
+    icon_name: String,
+    size: Option<u32>,
+) -> Result<Option<String>, String> {
+    let cache_dir = app_handle.path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("icons");
+
+    icons::IconResolver::new(cache_dir).data_uri(&icon_name, size.unwrap_or(48))
+}
+
 #[tauri::command]
 async fn get_all_running_processes() -> Result<Vec<ProcessInfo>, String> {
     Ok(ProcessMonitor::get_all_processes())
@@ -204,7 +1133,14 @@ async fn get_all_running_processes() -> Result<Vec<ProcessInfo>, String> {
 
 // Settings commands
 #[tauri::command]
-async fn toggle_autostart(state: State<'_, Arc<AppState>>, enabled: bool) -> Result<(), String> {
+async fn toggle_autostart(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    enabled: bool,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "toggle_autostart")?;
+
     if enabled {
         autostart::AutoStart::enable()?;
     } else {
@@ -224,10 +1160,18 @@ async fn get_autostart_enabled(state: State<'_, Arc<AppState>>) -> Result<bool,
 }
 
 #[tauri::command]
-async fn set_idle_timeout(state: State<'_, Arc<AppState>>, minutes: i64) -> Result<(), String> {
+async fn set_idle_timeout(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    minutes: i64,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_idle_timeout")?;
+    ensure_setting_not_managed("settings.idle_timeout", state.managed_policy.lock().unwrap().settings.idle_timeout.is_some())?;
+
     let clamped = minutes.min(10).max(1);
     state.idle_tracker.set_timeout(clamped);
-    state.database.set_setting("idle_timeout", &clamped.to_string())
+    state.database.set_idle_timeout(clamped)
         .map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -238,25 +1182,615 @@ async fn get_idle_timeout(state: State<'_, Arc<AppState>>) -> Result<i64, String
 }
 
 #[tauri::command]
-async fn reset_idle_timer(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+async fn reset_idle_timer(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "reset_idle_timer")?;
+
     state.idle_tracker.reset();
     Ok(())
 }
 
+#[tauri::command]
+async fn get_enforcement_backend(state: State<'_, Arc<AppState>>) -> Result<EnforcementBackend, String> {
+    Ok(state.monitor.enforcement_backend())
+}
+
+/// Returns the backend actually in effect, which is kill-on-sight when
+/// pre-exec blocking was requested but isn't available
+#[tauri::command]
+async fn set_enforcement_backend(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    backend: EnforcementBackend,
+    token: Option<String>,
+) -> Result<EnforcementBackend, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_enforcement_backend")?;
+
+    let previous = state.monitor.enforcement_backend();
+    state.monitor.set_enforcement_backend(backend);
+
+    if backend == EnforcementBackend::PreExec && previous != EnforcementBackend::PreExec {
+        start_exec_guard(app_handle, state.inner().clone());
+    }
+
+    let effective = state.monitor.enforcement_backend();
+    state.database.set_setting("enforcement_backend", effective.as_str())
+        .map_err(|e| e.to_string())?;
+
+    Ok(effective)
+}
+
+/// Longest learning period for allowlist mode
+const MAX_LEARNING_MINUTES: i64 = 7 * 24 * 60;
+
+#[tauri::command]
+async fn get_allowlist_config(state: State<'_, Arc<AppState>>) -> Result<AllowlistConfig, String> {
+    state.allowlist_config()
+}
+
+fn allowlist_changed(
+    state: &Arc<AppState>,
+    app_handle: &AppHandle,
+    event: String,
+) -> Result<AllowlistConfig, String> {
+    state.update_allowlist()?;
+    let config = state.allowlist_config()?;
+
+    let log = state.database.add_security_log(event, "info".to_string(), None)
+        .map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("security-log", &log);
+    let _ = app_handle.emit("allowlist-updated", &config);
+    Ok(config)
+}
+
+/// Switch between blocking the protected apps and allowing only the
+/// allowlist. `learning_minutes` first records what runs for that long
+/// instead of enforcing.
+#[tauri::command]
+async fn set_policy_mode(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    mode: PolicyMode,
+    learning_minutes: Option<i64>,
+    token: Option<String>,
+) -> Result<AllowlistConfig, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_policy_mode")?;
+    ensure_setting_not_managed("settings.policy_mode", state.managed_policy.lock().unwrap().settings.policy_mode.is_some())?;
+
+    let learning_minutes = match (mode, learning_minutes) {
+        (PolicyMode::Allowlist, Some(minutes)) if !(1..=MAX_LEARNING_MINUTES).contains(&minutes) => {
+            return Err(format!("Learning period must be between 1 and {} minutes", MAX_LEARNING_MINUTES).into());
+        }
+        (PolicyMode::Allowlist, minutes) => minutes,
+        (PolicyMode::Blocklist, _) => None,
+    };
+
+    state.database.set_setting("policy_mode", mode.as_str())
+        .map_err(|e| e.to_string())?;
+    match learning_minutes {
+        Some(minutes) => {
+            let until = (Utc::now() + chrono::Duration::minutes(minutes)).to_rfc3339();
+            state.database.set_setting("allowlist_learning_until", &until)
+        }
+        None => state.database.delete_setting("allowlist_learning_until"),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let event = match (mode, learning_minutes) {
+        (PolicyMode::Blocklist, _) => "Policy mode set to blocklist".to_string(),
+        (PolicyMode::Allowlist, Some(minutes)) => {
+            format!("Policy mode set to allowlist, learning for {} minutes before enforcing", minutes)
+        }
+        (PolicyMode::Allowlist, None) => "Policy mode set to allowlist".to_string(),
+    };
+    Ok(allowlist_changed(state.inner(), &app_handle, event)?)
+}
+
+#[tauri::command]
+async fn set_allowlist_strategy(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    strategy: TerminationStrategy,
+    token: Option<String>,
+) -> Result<AllowlistConfig, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_allowlist_strategy")?;
+    ensure_setting_not_managed("settings.allowlist_strategy", state.managed_policy.lock().unwrap().settings.allowlist_strategy.is_some())?;
+
+    state.database.set_setting("allowlist_strategy", strategy.as_str())
+        .map_err(|e| e.to_string())?;
+    let event = format!("Unlisted executables will be handled with: {}", strategy.as_str());
+    Ok(allowlist_changed(state.inner(), &app_handle, event)?)
+}
+
+/// Allow the binary at `exe_path`, or with `any_path` every binary called
+/// `name`, wherever it is
+#[tauri::command]
+async fn add_allowed_executable(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    name: String,
+    exe_path: Option<String>,
+    any_path: Option<bool>,
+    token: Option<String>,
+) -> Result<AllowlistConfig, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "add_allowed_executable")?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Executable name is required".to_string().into());
+    }
+    let exe_path = exe_path.unwrap_or_default();
+    let exe_path = exe_path.trim();
+    match (exe_path.is_empty(), any_path.unwrap_or(false)) {
+        (true, false) => {
+            return Err("An executable path is required unless any binary with this name is allowed".to_string().into());
+        }
+        (false, true) => return Err("Give either an executable path or allow any path, not both".to_string().into()),
+        _ => {}
+    }
+    state.database.add_allowed_executable(name, exe_path, "manual")
+        .map_err(|e| e.to_string())?;
+
+    Ok(allowlist_changed(state.inner(), &app_handle, format!("Allowlisted executable: {}", name))?)
+}
+
+#[tauri::command]
+async fn remove_allowed_executable(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    id: i64,
+    token: Option<String>,
+) -> Result<AllowlistConfig, CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "remove_allowed_executable")?;
+
+    let entry: AllowedExecutable = state.database.get_allowed_executables()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| "No such allowlist entry".to_string())?;
+    state.database.remove_allowed_executable(id)
+        .map_err(|e| e.to_string())?;
+
+    Ok(allowlist_changed(state.inner(), &app_handle, format!("Removed from allowlist: {}", entry.name))?)
+}
+
+#[tauri::command]
+async fn get_audit_mode(state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+    Ok(state.monitor.is_audit_mode())
+}
+
+/// Report every match as "would have terminated" instead of acting on it
+#[tauri::command]
+async fn set_audit_mode(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    enabled: bool,
+    token: Option<String>,
+) -> Result<(), CommandError> {
+    require_session(&state, &app_handle, token.as_deref(), "set_audit_mode")?;
+    ensure_setting_not_managed("settings.audit_mode", state.managed_policy.lock().unwrap().settings.audit_mode.is_some())?;
+
+    state.database.set_setting("audit_mode", if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())?;
+    state.monitor.set_audit_mode(enabled);
+
+    let (event, log_type) = if enabled {
+        ("Audit mode enabled - matches are logged, nothing is terminated", "warning")
+    } else {
+        ("Audit mode disabled - enforcement resumed", "info")
+    };
+    let log = state.database.add_security_log(event.to_string(), log_type.to_string(), None)
+        .map_err(|e| e.to_string())?;
+    app_handle.emit("security-log", &log).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Security log entry for what was caught, before any action taken
+fn describe_detection(report: &KillReport) -> String {
+    match &report.reason {
+        KillReason::Protected => format!("Unauthorized launch attempt: {}", report.process_name),
+        KillReason::Unlisted => format!("Executable not on allowlist: {}", report.process_name),
+        KillReason::Elevation { cmdline, parent_pid, parent_name } => format!(
+            "Root elevation attempt: {} (parent: {} PID {})",
+            cmdline.join(" "),
+            parent_name.as_deref().unwrap_or("unknown"),
+            parent_pid
+        ),
+    }
+}
+
+/// Desktop notification through notify-send, without waiting on it
+fn notify_desktop(summary: &str, body: &str) {
+    let mut command = std::process::Command::new("notify-send");
+    command.args(["--app-name=Ficha", "--urgency=critical", summary, body]);
+    std::thread::spawn(move || {
+        if let Err(e) = command.status() {
+            eprintln!("Failed to send desktop notification: {}", e);
+        }
+    });
+}
+
+fn describe_termination(report: &KillReport) -> String {
+    let action = match (report.strategy, report.escalated) {
+        (TerminationStrategy::Kill, _) => "killed (SIGKILL)",
+        (TerminationStrategy::Terminate, false) => "terminated (SIGTERM)",
+        (TerminationStrategy::Terminate, true) => "killed (SIGTERM ignored, escalated to SIGKILL)",
+        (TerminationStrategy::Stop, _) => "frozen (SIGSTOP) until next unlock",
+        (TerminationStrategy::Freeze, _) => "frozen (cgroup freezer) until next unlock",
+    };
+
+    format!(
+        "Process [{}] {} by Ficha Kernel (PID: {}, {} PIDs in tree)",
+        report.process_name, action, report.pid, report.pids_terminated
+    )
+}
+
+/// Security log entry for a match that audit mode left alone
+fn describe_audit(report: &KillReport) -> String {
+    let (ppid, exe, cmdline) = match &report.audit {
+        Some(process) => (
+            process.ppid,
+            process.exe_path.clone().unwrap_or_else(|| "unknown".to_string()),
+            process.cmdline.join(" "),
+        ),
+        None => (0, "unknown".to_string(), String::new()),
+    };
+
+    format!(
+        "Would have terminated [{}]{} with {} (PID: {}, parent PID: {}, {} PIDs in tree, exe: {}, cmdline: {})",
+        report.process_name,
+        match report.reason {
+            KillReason::Protected => "",
+            KillReason::Unlisted => " (not on allowlist)",
+            KillReason::Elevation { .. } => " (root elevation attempt)",
+        },
+        report.strategy.as_str(),
+        report.pid,
+        ppid,
+        report.pids_terminated,
+        exe,
+        cmdline
+    )
+}
+
+/// Flag a threat to the UI and drop back to LOCKED (or FROZEN while apps
+/// are suspended) after 3 seconds
+fn flag_threat(app_handle: &AppHandle, state: &Arc<AppState>) {
+    state.set_shield_status(ShieldStatus::THREAT_DETECTED);
+    let _ = app_handle.emit("shield-status", ShieldStatus::THREAT_DETECTED);
+
+    let handle_clone = app_handle.clone();
+    let state_clone = state.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        {
+            let locked_status = state_clone.locked_status();
+            let mut status = state_clone.shield_status.lock().unwrap();
+            if matches!(*status, ShieldStatus::THREAT_DETECTED) {
+                *status = locked_status.clone();
+                let _ = handle_clone.emit("shield-status", locked_status);
+            }
+        }
+    });
+}
+
+/// Load the policy files and apply them. The user file is only re-read with
+/// `include_user`; otherwise the copy applied last is used and any change
+/// to it is deferred. A file that doesn't validate leaves the current state
+/// in place, with the reason logged.
+fn reload_policy_files(app_handle: &AppHandle, state: &Arc<AppState>, include_user: bool) {
+    let had_policy = *state.managed_policy.lock().unwrap() != policy_file::PolicyFile::default();
+    let deferred = !include_user && user_policy_pending(state);
+    if deferred {
+        log_policy_event(
+            app_handle,
+            state,
+            "User policy file changed while locked, applying it after the next unlock".to_string(),
+            "warning",
+        );
+    }
+
+    let user = if include_user {
+        policy_file::load_user()
+    } else {
+        Ok(state.user_policy.lock().unwrap().clone())
+    };
+    let loaded = user.and_then(|user| Ok((policy_file::load(user.clone())?, user)));
+
+    let (event, log_type) = match loaded {
+        Ok(((policy, _), _)) if deferred && policy == *state.managed_policy.lock().unwrap() => return,
+        Ok(((policy, sources), user)) => {
+            let apps = policy.apps.len();
+            let applied = state.apply_policy_file(policy);
+            if applied.is_ok() {
+                *state.user_policy.lock().unwrap() = user;
+            }
+            match applied {
+                Ok(()) if sources.is_empty() && !had_policy => return,
+                Ok(()) if sources.is_empty() => ("Policy files removed, in-app settings apply again".to_string(), "warning"),
+                Ok(()) => {
+                    let sources: Vec<String> = sources.iter().map(|path| path.display().to_string()).collect();
+                    (format!("Policy loaded from {}: {} managed app(s)", sources.join(", "), apps), "info")
+                }
+                Err(e) => (format!("Failed to apply policy file: {}", e), "error"),
+            }
+        }
+        Err(e) => (format!("Policy file rejected, keeping previous settings: {}", e), "error"),
+    };
+
+    log_policy_event(app_handle, state, event, log_type);
+    let _ = app_handle.emit("policy-reloaded", ());
+}
+
+fn log_policy_event(app_handle: &AppHandle, state: &AppState, event: String, log_type: &str) {
+    println!("{}", event);
+    if let Ok(log) = state.database.add_security_log(event, log_type.to_string(), None) {
+        let _ = app_handle.emit("security-log", &log);
+    }
+}
+
+/// Whether the user policy file differs from the copy applied last
+fn user_policy_pending(state: &AppState) -> bool {
+    policy_file::load_user() != Ok(state.user_policy.lock().unwrap().clone())
+}
+
+/// Apply the policy files, then reload them whenever they change. Changes
+/// to the user file only apply while unlocked.
+fn setup_policy_watch(app_handle: AppHandle, state: Arc<AppState>) {
+    reload_policy_files(&app_handle, &state, true);
+
+    let mut watcher = match policy_file::PolicyWatcher::new() {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Policy file hot reload unavailable: {}", e);
+            return;
+        }
+    };
+
+    std::thread::spawn(move || loop {
+        watcher.wait(std::time::Duration::from_millis(300));
+        reload_policy_files(&app_handle, &state, !state.monitor.is_monitoring());
+    });
+}
+
+/// Start fanotify pre-exec blocking, falling back to kill-on-sight (with a
+/// log entry saying why) when it can't be set up. A guard that is still
+/// running, e.g. after a quick switch away and back, is kept instead.
+fn start_exec_guard(app_handle: AppHandle, state: Arc<AppState>) {
+    if state
+        .exec_guard_running
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return;
+    }
+
+    let guard = match fanotify::ExecGuard::open() {
+        Ok(guard) => guard,
+        Err(reason) => {
+            state.exec_guard_running.store(false, Ordering::SeqCst);
+            eprintln!("Pre-exec blocking unavailable: {}", reason);
+            state.monitor.set_enforcement_backend(EnforcementBackend::KillOnSight);
+
+            if let Ok(log) = state.database.add_security_log(
+                format!("Pre-exec blocking unavailable, using kill-on-sight: {}", reason),
+                "warning".to_string(),
+                None,
+            ) {
+                let _ = app_handle.emit("security-log", &log);
+            }
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        let monitor = state.monitor.clone();
+        let database = state.database.clone();
+        let running = state.exec_guard_running.clone();
+
+        guard.run(monitor, running, move |pid, process_name, path| {
+            println!("Exec denied: {} (PID: {})", path, pid);
+
+            let now = Utc::now().format("%H:%M:%S").to_string();
+            let _ = database.update_last_attempt(&process_name, &now);
+
+            if let Ok(log) = database.add_security_log(
+                format!("Launch of [{}] blocked before exec: {}", process_name, path),
+                "error".to_string(),
+                Some(process_name.clone()),
+            ) {
+                let _ = app_handle.emit("security-log", &log);
+            }
+
+            let _ = app_handle.emit("exec-denied", (pid, process_name));
+            flag_threat(&app_handle, &state);
+        });
+    });
+}
+
+/// Re-enforce apps whose temporary unlock has run out
+fn setup_grant_expiry_task(app_handle: AppHandle, state: Arc<AppState>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+            let expired = match state.expire_unlock_grants() {
+                Ok(expired) => expired,
+                Err(e) => {
+                    eprintln!("Failed to expire unlock grants: {}", e);
+                    continue;
+                }
+            };
+
+            for grant in &expired {
+                println!("Temporary unlock expired: {}", grant.app_name);
+                if let Ok(log) = state.database.add_security_log(
+                    format!(
+                        "Temporary unlock for {} expired{}",
+                        grant.app_name,
+                        if grant.kill_on_expiry { ", closing it" } else { ", new launches blocked" }
+                    ),
+                    "warning".to_string(),
+                    Some(grant.process_name.clone()),
+                ) {
+                    let _ = app_handle.emit("security-log", &log);
+                }
+                let _ = app_handle.emit("grant-expired", grant);
+            }
+            if !expired.is_empty() {
+                emit_unlock_grants(&state, &app_handle);
+            }
+        }
+    });
+}
+
+/// Log scheduled apps entering and leaving their blocked windows
+fn setup_schedule_task(app_handle: AppHandle, state: Arc<AppState>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            for transition in state.monitor.schedule_transitions(Utc::now()) {
+                let event = if transition.blocking {
+                    format!("Schedule started for {}, now blocked", transition.process_name)
+                } else {
+                    // Frozen or stopped instances would otherwise wait for the next unlock
+                    state.monitor.resume_group(&transition.process_name);
+                    format!("Schedule ended for {}, now allowed", transition.process_name)
+                };
+                println!("{}", event);
+                if let Ok(log) = state.database.add_security_log(
+                    event,
+                    "info".to_string(),
+                    Some(transition.process_name.clone()),
+                ) {
+                    let _ = app_handle.emit("security-log", &log);
+                }
+                let _ = app_handle.emit("schedule-transition", &transition);
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    });
+}
+
+/// Bill running time against daily quotas, warn as they run low and
+/// enforce the apps that run out
+fn setup_quota_task(app_handle: AppHandle, state: Arc<AppState>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+            let running = state.monitor.running_unenforced(&ProcessMonitor::get_all_processes());
+            let tick = match state.quotas.tick(&running) {
+                Ok(tick) => tick,
+                Err(e) => {
+                    eprintln!("Failed to update daily quotas: {}", e);
+                    continue;
+                }
+            };
+
+            // Day rollover resumes what yesterday's quotas froze or stopped
+            let exhausted = tick.events.iter().any(|(_, event)| *event == QuotaEvent::Exhausted);
+            if tick.day_changed || exhausted {
+                if let Err(e) = state.refresh_quotas() {
+                    eprintln!("Failed to update protected processes: {}", e);
+                }
+            }
+
+            for (status, event) in tick.events {
+                let (message, log_type, event_name) = match event {
+                    QuotaEvent::Warning { minutes_left } => (
+                        format!("{} minutes left of today's quota for {}", minutes_left, status.app_name),
+                        "warning",
+                        "quota-warning",
+                    ),
+                    QuotaEvent::Exhausted => (
+                        format!("Daily quota for {} used up, closing it until tomorrow", status.app_name),
+                        "error",
+                        "quota-exhausted",
+                    ),
+                };
+                println!("{}", message);
+                if let Ok(log) = state.database.add_security_log(
+                    message,
+                    log_type.to_string(),
+                    Some(status.app_name.clone()),
+                ) {
+                    let _ = app_handle.emit("security-log", &log);
+                }
+                let _ = app_handle.emit(event_name, &status);
+            }
+        }
+    });
+}
+
+/// Record what the user runs during the allowlist learning period, and
+/// start enforcing once it is over
+fn setup_allowlist_learning_task(app_handle: AppHandle, state: Arc<AppState>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+            let until = match state.database.get_setting("allowlist_learning_until") {
+                Ok(Some(until)) => until,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Failed to read allowlist learning period: {}", e);
+                    continue;
+                }
+            };
+
+            let uid = auth::AuthManager::desktop_uid();
+            for process in ProcessMonitor::get_all_processes() {
+                if allowlist::is_safe_process(&process) || allowlist::process_uid(process.pid) != Some(uid) {
+                    continue;
+                }
+                // Only binaries are learned; a name alone would allow anything renamed to it
+                let exe_path = match process.exe_path.as_deref() {
+                    Some(exe_path) if !exe_path.ends_with(" (deleted)") => exe_path,
+                    _ => continue,
+                };
+                if let Ok(true) = state.database.add_allowed_executable(&process.name, exe_path, "learned") {
+                    println!("Allowlist learned: {} ({})", process.name, exe_path);
+                }
+            }
+
+            let finished = chrono::DateTime::parse_from_rfc3339(&until)
+                .map(|until| until.with_timezone(&Utc) <= Utc::now())
+                .unwrap_or(true);
+            if !finished {
+                continue;
+            }
+
+            if let Err(e) = state.database.delete_setting("allowlist_learning_until") {
+                eprintln!("Failed to end allowlist learning: {}", e);
+                continue;
+            }
+            let learned = state.database.get_allowed_executables()
+                .map(|entries| entries.len())
+                .unwrap_or(0);
+            let event = format!("Allowlist learning finished with {} executables, now enforcing", learned);
+            println!("{}", event);
+            if let Err(e) = allowlist_changed(&state, &app_handle, event) {
+                eprintln!("Failed to apply allowlist: {}", e);
+            }
+        }
+    });
+}
+
 fn setup_idle_monitoring_task(app_handle: AppHandle, state: Arc<AppState>) {
     tauri::async_runtime::spawn(async move {
         let idle_tracker = state.idle_tracker.clone();
-        let shield_status = state.shield_status.clone();
         let database = state.database.clone();
 
         idle_tracker.start_monitoring_loop(5000, move || {
             println!("Idle timeout detected - locking shield");
 
-            // Lock the shield
-            {
-                let mut status = shield_status.lock().unwrap();
-                *status = ShieldStatus::LOCKED;
-            }
+            // Lock the shield, which also ends the session
+            state.lock_shield();
 
             // Add log entry
             let _ = database.add_security_log(
@@ -268,42 +1802,71 @@ fn setup_idle_monitoring_task(app_handle: AppHandle, state: Arc<AppState>) {
             // Emit event to frontend
             let _ = app_handle.emit("shield-status", ShieldStatus::LOCKED);
             let _ = app_handle.emit("auto-locked", true);
+            let _ = app_handle.emit("session-expired", ());
         }).await;
     });
 }
 
 fn setup_monitoring_task(app_handle: AppHandle, state: Arc<AppState>) {
-    tauri::async_runtime::spawn(async move {
+    // Detection backends block on the kernel, so the monitor gets its own thread
+    std::thread::spawn(move || {
         let monitor = state.monitor.clone();
         let database = state.database.clone();
-        let shield_status = state.shield_status.clone();
+        let backend = detection::open_backend();
+
+        monitor.start_monitoring_loop(backend, 1000, move |report| {
+            if report.audit.is_some() {
+                if let Ok(log) = database.add_security_log(
+                    describe_audit(&report),
+                    "audit".to_string(),
+                    Some(report.process_name.clone()),
+                ) {
+                    let _ = app_handle.emit("security-log", &log);
+                }
+                let _ = app_handle.emit("process-audited", &report);
+                return;
+            }
 
-        monitor.start_monitoring_loop(1000, move |pid, process_name| {
-            println!("Process killed: {} (PID: {})", process_name, pid);
+            let pid = report.pid;
+            let process_name = report.process_name.clone();
 
             // Update last attempt timestamp
             let now = Utc::now().format("%H:%M:%S").to_string();
-            let _ = database.update_last_attempt(&process_name, &now);
+            if report.reason == KillReason::Protected {
+                let _ = database.update_last_attempt(&process_name, &now);
+            }
+
+            if report.alert_only {
+                println!("Process detected: {} (PID: {})", process_name, pid);
+                let event = describe_detection(&report);
+                if let Ok(log) = database.add_security_log(
+                    format!("{} - auto-kill is off, process left running", event),
+                    "error".to_string(),
+                    Some(process_name.clone()),
+                ) {
+                    let _ = app_handle.emit("security-log", &log);
+                }
+                let _ = app_handle.emit("process-detected", &report);
+                notify_desktop("Ficha: unauthorized app detected", &event);
+                flag_threat(&app_handle, &state);
+                return;
+            }
+
+            println!("Process killed: {} (PID: {})", process_name, pid);
 
             // Add security logs
             let log1 = database.add_security_log(
-                format!("Unauthorized launch attempt: {}", process_name),
+                describe_detection(&report),
                 "error".to_string(),
                 Some(process_name.clone()),
             );
 
             let log2 = database.add_security_log(
-                format!("Process [{}] killed by Ficha Kernel (PID: {})", process_name, pid),
+                describe_termination(&report),
                 "success".to_string(),
                 None,
             );
 
-            // Set threat detected status
-            {
-                let mut status = shield_status.lock().unwrap();
-                *status = ShieldStatus::THREAT_DETECTED;
-            }
-
             // Emit events to frontend
             if let Ok(log) = log1 {
                 let _ = app_handle.emit("security-log", &log);
@@ -311,23 +1874,11 @@ fn setup_monitoring_task(app_handle: AppHandle, state: Arc<AppState>) {
             if let Ok(log) = log2 {
                 let _ = app_handle.emit("security-log", &log);
             }
-            let _ = app_handle.emit("process-killed", (pid, process_name));
-            let _ = app_handle.emit("shield-status", ShieldStatus::THREAT_DETECTED);
-
-            // Reset to LOCKED after 3 seconds
-            let handle_clone = app_handle.clone();
-            let status_clone = shield_status.clone();
-            tauri::async_runtime::spawn(async move {
-                tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-                {
-                    let mut status = status_clone.lock().unwrap();
-                    if matches!(*status, ShieldStatus::THREAT_DETECTED) {
-                        *status = ShieldStatus::LOCKED;
-                        let _ = handle_clone.emit("shield-status", ShieldStatus::LOCKED);
-                    }
-                }
-            });
-        }).await;
+            let _ = app_handle.emit("process-killed", &report);
+
+            // Set threat detected status, reset to LOCKED after 3 seconds
+            flag_threat(&app_handle, &state);
+        });
     });
 }
 
@@ -357,9 +1908,9 @@ pub fn run() {
             // Initialize idle tracker
             let idle_tracker = idle::IdleTracker::new();
 
-            // Load idle timeout from database
-            if let Ok(timeout) = database.get_int_setting("idle_timeout", 10) {
-                idle_tracker.set_timeout(timeout);
+            // Load idle timeout of the active profile
+            if let Ok(profile) = database.get_active_profile() {
+                idle_tracker.set_timeout(profile.idle_timeout_mins);
             }
 
             // Check and apply stealth mode policy
@@ -372,12 +1923,48 @@ pub fn run() {
                 idle_tracker.set_enabled(true);
             }
 
+            // Alert only instead of terminating when auto-kill is disabled
+            if let Ok(false) = database.is_policy_enabled("policy_1") {
+                monitor.set_auto_kill(false);
+            }
+
+            // Audit mode survives restarts so a trial run isn't cut short
+            if let Ok(true) = database.get_bool_setting("audit_mode", false) {
+                monitor.set_audit_mode(true);
+            }
+
             // Create app state
             let state = Arc::new(AppState::new(database, monitor, idle_tracker));
 
             // Start monitoring tasks
             setup_monitoring_task(app.handle().clone(), state.clone());
             setup_idle_monitoring_task(app.handle().clone(), state.clone());
+            setup_grant_expiry_task(app.handle().clone(), state.clone());
+            setup_schedule_task(app.handle().clone(), state.clone());
+            setup_quota_task(app.handle().clone(), state.clone());
+            setup_allowlist_learning_task(app.handle().clone(), state.clone());
+
+            // Load the watch list, allowlist and elevation policy so
+            // schedules, quotas and allowlist mode apply before the first lock
+            if let Err(e) = state.update_protected_processes()
+                .and_then(|_| state.update_allowlist())
+                .and_then(|_| state.update_elevation_guard())
+            {
+                eprintln!("Failed to load enforcement rules: {}", e);
+            }
+
+            // Policy files take precedence over what was set in the app
+            setup_policy_watch(app.handle().clone(), state.clone());
+
+            // Pre-exec blocking if selected, falls back to kill-on-sight
+            let backend = state.database.get_setting("enforcement_backend")
+                .ok()
+                .flatten()
+                .and_then(|value| EnforcementBackend::parse(&value));
+            if backend == Some(EnforcementBackend::PreExec) {
+                state.monitor.set_enforcement_backend(EnforcementBackend::PreExec);
+                start_exec_guard(app.handle().clone(), state.clone());
+            }
 
             // Manage state
             app.manage(state);
@@ -386,6 +1973,22 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             authenticate,
+            get_auth_throttle,
+            authenticate_app,
+            grant_temporary_unlock,
+            revoke_unlock_grant,
+            get_unlock_grants,
+            get_auth_config,
+            setup_passphrase,
+            change_passphrase,
+            set_auth_method,
+            respond_pam_prompt,
+            set_pam_service,
+            begin_totp_enrollment,
+            confirm_totp_enrollment,
+            disable_totp,
+            regenerate_recovery_codes,
+            get_recovery_codes_left,
             get_current_username,
             get_shield_status,
             activate_shield,
@@ -393,18 +1996,43 @@ pub fn run() {
             get_protected_apps,
             add_protected_app,
             remove_protected_app,
+            update_match_rule,
+            set_termination_strategy,
+            set_hash_pinning,
+            set_app_schedule,
+            set_app_quota,
+            set_app_audit_only,
+            get_audit_mode,
+            set_audit_mode,
+            get_quota_status,
+            get_allowlist_config,
+            set_policy_mode,
+            set_allowlist_strategy,
+            add_allowed_executable,
+            remove_allowed_executable,
+            refresh_pinned_hashes,
             get_security_logs,
             get_security_policies,
             toggle_security_policy,
+            get_profiles,
+            create_profile,
+            switch_profile,
+            delete_profile,
+            get_elevation_exemptions,
+            add_elevation_exemption,
+            remove_elevation_exemption,
             get_running_processes,
             get_installed_apps,
             get_app_candidates,
+            get_app_icon,
             get_all_running_processes,
             toggle_autostart,
             get_autostart_enabled,
             set_idle_timeout,
             get_idle_timeout,
             reset_idle_timer,
+            get_enforcement_backend,
+            set_enforcement_backend,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,451 @@
+use crate::identity::{AppIdentity, LazyIdentity};
+use crate::monitor::ProcessInfo;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+// The kernel truncates comm to TASK_COMM_LEN - 1 bytes
+const COMM_LEN: usize = 15;
+
+/// What a match rule's pattern is compared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Exact `/proc/<pid>/comm`
+    Comm,
+    /// Exact `/proc/<pid>/exe` target
+    ExePath,
+    /// Exact file name of the executable
+    ExeBasename,
+    /// Glob against the exe path when the pattern has a '/', otherwise
+    /// against comm and the exe file name
+    Glob,
+    /// Regex against the space-joined command line
+    CmdlineRegex,
+    /// Glob against the script, jar or .exe an interpreter or Wine was
+    /// launched with; like `Glob`, a '/' makes it a path glob
+    Script,
+    /// Glob against the Flatpak application ID
+    Flatpak,
+    /// Glob against the snap name
+    Snap,
+    /// Glob against the `.AppImage` the process runs from; like `Glob`, a
+    /// '/' makes it a path glob
+    AppImage,
+}
+
+/// How a protected app is recognised among running processes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchRule {
+    pub kind: MatchKind,
+    pub pattern: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Globs that veto a match, compared like a `Glob` pattern
+    #[serde(default)]
+    pub exclusions: Vec<String>,
+}
+
+impl MatchRule {
+    /// The rule given to apps that only have a process name: the name itself
+    /// or a dashed variant (`brave` matches `brave-browser`, not `bravely`)
+    pub fn default_for(process_name: &str) -> Self {
+        MatchRule {
+            kind: MatchKind::Glob,
+            pattern: format!("{{{0},{0}-*}}", escape_glob(process_name)),
+            case_sensitive: false,
+            exclusions: Vec::new(),
+        }
+    }
+
+    /// Whether the executable identifies the app. Script and cmdline rules
+    /// run a shared interpreter and sandboxed apps run `bwrap` or a loader,
+    /// so hashing their binary would match every other app using it
+    pub fn identifies_binary(&self) -> bool {
+        !matches!(
+            self.kind,
+            MatchKind::CmdlineRegex | MatchKind::Script | MatchKind::Flatpak | MatchKind::Snap | MatchKind::AppImage
+        )
+    }
+
+    /// Binary path pinned by the rule, if it names one exactly
+    pub fn exe_path(&self) -> Option<&str> {
+        match self.kind {
+            MatchKind::ExePath => Some(&self.pattern),
+            _ => None,
+        }
+    }
+}
+
+/// Quote glob metacharacters so a name only ever matches itself, including
+/// the `,` that would otherwise split the `{name,name-*}` alternation
+fn escape_glob(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '?' | '*' | '[' | ']' | '{' | '}' | ',' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Exact(String),
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+/// A validated `MatchRule`, ready to be checked against processes
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    kind: MatchKind,
+    case_sensitive: bool,
+    matcher: Matcher,
+    exclusions: Vec<GlobMatcher>,
+}
+
+impl CompiledRule {
+    pub fn new(rule: &MatchRule) -> Result<Self, String> {
+        if rule.pattern.trim().is_empty() {
+            return Err("Match pattern must not be empty".to_string());
+        }
+
+        let matcher = match rule.kind {
+            MatchKind::Comm | MatchKind::ExePath | MatchKind::ExeBasename => {
+                Matcher::Exact(Self::fold(&rule.pattern, rule.case_sensitive))
+            }
+            MatchKind::Glob
+            | MatchKind::Script
+            | MatchKind::Flatpak
+            | MatchKind::Snap
+            | MatchKind::AppImage => {
+                Matcher::Glob(Self::glob(&rule.pattern, rule.case_sensitive)?)
+            }
+            MatchKind::CmdlineRegex => Matcher::Regex(
+                RegexBuilder::new(&rule.pattern)
+                    .case_insensitive(!rule.case_sensitive)
+                    .build()
+                    .map_err(|e| format!("Invalid regex '{}': {}", rule.pattern, e))?,
+            ),
+        };
+
+        let exclusions = rule
+            .exclusions
+            .iter()
+            .map(|pattern| Self::glob(pattern, rule.case_sensitive))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CompiledRule {
+            kind: rule.kind,
+            case_sensitive: rule.case_sensitive,
+            matcher,
+            exclusions,
+        })
+    }
+
+    fn glob(pattern: &str, case_sensitive: bool) -> Result<GlobMatcher, String> {
+        GlobBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map(|glob| glob.compile_matcher())
+            .map_err(|e| format!("Invalid glob '{}': {}", pattern, e))
+    }
+
+    fn fold(value: &str, case_sensitive: bool) -> String {
+        if case_sensitive {
+            value.to_string()
+        } else {
+            value.to_lowercase()
+        }
+    }
+
+    /// Does the rule's own glob match? Path globs see the exe path, name
+    /// globs see comm and the exe file name.
+    fn glob_matches(glob: &GlobMatcher, process: &ProcessInfo) -> bool {
+        if glob.glob().glob().contains('/') {
+            return exe_path(process).is_some_and(|exe| glob.is_match(exe));
+        }
+        glob.is_match(&process.name) || exe_basename(process).is_some_and(|name| glob.is_match(name))
+    }
+
+    /// Rules that look at where a process was installed from, not what it runs
+    fn is_identity(&self) -> bool {
+        matches!(self.kind, MatchKind::Flatpak | MatchKind::Snap | MatchKind::AppImage)
+    }
+
+    fn script_matches(glob: &GlobMatcher, target: &str) -> bool {
+        if glob.glob().glob().contains('/') {
+            return glob.is_match(target);
+        }
+        // Wine passes Windows paths
+        glob.is_match(target.rsplit(['/', '\\']).next().unwrap_or(target))
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        self.matches_with(process, &LazyIdentity::new(process.pid))
+    }
+
+    /// Like `matches`, sharing the process's identity between rules
+    pub fn matches_with(&self, process: &ProcessInfo, identity: &LazyIdentity) -> bool {
+        let matched = match &self.matcher {
+            Matcher::Exact(pattern) => match self.kind {
+                MatchKind::Comm => {
+                    let comm = Self::fold(&process.name, self.case_sensitive);
+                    let truncated: String = pattern.chars().take(COMM_LEN).collect();
+                    comm == *pattern || (pattern.len() > COMM_LEN && comm == truncated)
+                }
+                MatchKind::ExePath => exe_path(process)
+                    .is_some_and(|exe| Self::fold(exe, self.case_sensitive) == *pattern),
+                _ => exe_basename(process)
+                    .is_some_and(|name| Self::fold(name, self.case_sensitive) == *pattern),
+            },
+            Matcher::Glob(glob) if self.kind == MatchKind::Script => {
+                launch_target(process).is_some_and(|target| Self::script_matches(glob, &target))
+            }
+            Matcher::Glob(glob) if self.is_identity() => {
+                match (self.kind, identity.get()) {
+                    (MatchKind::Flatpak, Some(AppIdentity::Flatpak(id))) => glob.is_match(id),
+                    (MatchKind::Snap, Some(AppIdentity::Snap(name))) => glob.is_match(name),
+                    (MatchKind::AppImage, Some(AppIdentity::AppImage(path))) => Self::script_matches(glob, path),
+                    _ => false,
+                }
+            }
+            Matcher::Glob(glob) => Self::glob_matches(glob, process),
+            Matcher::Regex(regex) => regex.is_match(&process.cmdline.join(" ")),
+        };
+
+        matched && !self.exclusions.iter().any(|glob| Self::glob_matches(glob, process))
+    }
+}
+
+/// The exe target without the " (deleted)" suffix left by upgrades
+fn exe_path(process: &ProcessInfo) -> Option<&str> {
+    process
+        .exe_path
+        .as_deref()
+        .map(|exe| exe.strip_suffix(" (deleted)").unwrap_or(exe))
+}
+
+fn exe_basename(process: &ProcessInfo) -> Option<&str> {
+    exe_path(process).and_then(|exe| exe.rsplit('/').next())
+}
+
+/// Launchers that run the real app from one of their arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Launcher {
+    Python,
+    Java,
+    Node,
+    Electron,
+    Wine,
+}
+
+impl Launcher {
+    fn recognize(name: &str) -> Option<Self> {
+        let versioned = |prefix: &str| {
+            name.strip_prefix(prefix)
+                .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        };
+
+        match name {
+            "java" => Some(Launcher::Java),
+            "node" | "nodejs" => Some(Launcher::Node),
+            "wine" | "wine64" | "wine-preloader" | "wine64-preloader" | "wineloader" => Some(Launcher::Wine),
+            _ if versioned("python") || versioned("pypy") => Some(Launcher::Python),
+            _ if versioned("electron") => Some(Launcher::Electron),
+            _ => None,
+        }
+    }
+
+    /// Options that consume the following argument
+    fn takes_value(self, option: &str) -> bool {
+        match self {
+            Launcher::Python => matches!(option, "-W" | "-X" | "-Q"),
+            Launcher::Java => matches!(
+                option,
+                "-cp" | "-classpath" | "--class-path" | "-p" | "--module-path" | "--add-modules"
+            ),
+            Launcher::Node => matches!(option, "-r" | "--require" | "--import" | "--loader"),
+            Launcher::Electron | Launcher::Wine => false,
+        }
+    }
+
+    /// The script, jar, module or .exe being run, if there is one
+    fn target(self, args: &[String]) -> Option<String> {
+        if self == Launcher::Wine {
+            // Wine rewrites argv[0] to the Windows path of the program
+            return args
+                .iter()
+                .find(|arg| {
+                    let lower = arg.to_lowercase();
+                    lower.ends_with(".exe") || lower.ends_with(".msi") || lower.ends_with(".bat")
+                })
+                .cloned();
+        }
+
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                // Inline code has no script to match
+                "-c" | "-e" | "--eval" | "-p" | "--print" if self != Launcher::Java => return None,
+                "-m" if self == Launcher::Python => return iter.next().cloned(),
+                "-jar" if self == Launcher::Java => return iter.next().cloned(),
+                option if self.takes_value(option) => {
+                    iter.next();
+                }
+                option if option.starts_with('-') => {}
+                target => return Some(target.to_string()),
+            }
+        }
+        None
+    }
+}
+
+/// What an interpreter or Wine was asked to run, recognised from argv:
+/// `python3 app.py`, `python -m module`, `java -jar tool.jar`,
+/// `node server.js`, `electron /opt/foo/app.asar`, `wine game.exe`
+pub fn launch_target(process: &ProcessInfo) -> Option<String> {
+    let argv0 = process.cmdline.first()?;
+    let launcher = [exe_basename(process), argv0.rsplit('/').next()]
+        .into_iter()
+        .flatten()
+        .find_map(Launcher::recognize)?;
+    launcher.target(&process.cmdline)
+}
+
+/// `launch_target` for an argv that isn't running yet, e.g. an Exec line
+pub fn argv_launch_target(argv: &[String]) -> Option<String> {
+    let launcher = Launcher::recognize(argv.first()?.rsplit('/').next()?)?;
+    launcher.target(argv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, exe: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid: 0,
+            name: name.to_string(),
+            exe_path: Some(exe.to_string()),
+            ppid: 1,
+            session: 0,
+            cmdline: vec![exe.to_string()],
+        }
+    }
+
+    fn launched(exe: &str, args: &[&str]) -> ProcessInfo {
+        let name = exe.rsplit('/').next().unwrap();
+        ProcessInfo {
+            cmdline: args.iter().map(|arg| arg.to_string()).collect(),
+            ..process(name, exe)
+        }
+    }
+
+    #[test]
+    fn test_default_rule_is_not_a_prefix_match() {
+        let rule = CompiledRule::new(&MatchRule::default_for("code")).unwrap();
+        assert!(rule.matches(&process("code", "/usr/share/code/code")));
+        assert!(rule.matches(&process("code-insiders", "/usr/share/code-insiders/code-insiders")));
+        assert!(!rule.matches(&process("codium", "/usr/share/codium/codium")));
+        assert!(!rule.matches(&process("node", "/usr/share/code/resources/node")));
+
+        let rule = CompiledRule::new(&MatchRule::default_for("md")).unwrap();
+        assert!(!rule.matches(&process("mdadm", "/usr/sbin/mdadm")));
+    }
+
+    #[test]
+    fn test_default_rule_escapes_glob_metacharacters() {
+        let rule = CompiledRule::new(&MatchRule::default_for("a*b")).unwrap();
+        assert!(rule.matches(&process("a*b", "/opt/a*b")));
+        assert!(!rule.matches(&process("axxb", "/usr/bin/axxb")));
+
+        let rule = CompiledRule::new(&MatchRule::default_for("x,*")).unwrap();
+        assert!(rule.matches(&process("x,*", "/opt/x")));
+        assert!(!rule.matches(&process("firefox", "/usr/bin/firefox")));
+
+        let rule = CompiledRule::new(&MatchRule::default_for("app[1]?")).unwrap();
+        assert!(rule.matches(&process("app[1]?", "/opt/app")));
+        assert!(!rule.matches(&process("app1x", "/opt/app1x")));
+
+        let rule = CompiledRule::new(&MatchRule::default_for("a\\b")).unwrap();
+        assert!(rule.matches(&process("a\\b", "/opt/ab")));
+        assert!(!rule.matches(&process("ab", "/opt/ab")));
+    }
+
+    #[test]
+    fn test_exact_kinds_and_exclusions() {
+        let rule = CompiledRule::new(&MatchRule {
+            kind: MatchKind::ExePath,
+            pattern: "/opt/brave.com/brave/brave".to_string(),
+            case_sensitive: true,
+            exclusions: Vec::new(),
+        })
+        .unwrap();
+        assert!(rule.matches(&process("brave", "/opt/brave.com/brave/brave (deleted)")));
+        assert!(!rule.matches(&process("brave", "/tmp/brave")));
+
+        let rule = CompiledRule::new(&MatchRule {
+            kind: MatchKind::Comm,
+            pattern: "telegram-desktop".to_string(),
+            case_sensitive: false,
+            exclusions: vec!["/usr/lib/*".to_string()],
+        })
+        .unwrap();
+        assert!(rule.matches(&process("telegram-deskto", "/usr/bin/telegram-desktop")));
+        assert!(!rule.matches(&process("telegram-deskto", "/usr/lib/telegram/telegram-desktop")));
+    }
+
+    #[test]
+    fn test_launch_targets() {
+        let target = |exe, args: &[&str]| launch_target(&launched(exe, args));
+
+        assert_eq!(target("/usr/bin/python3.12", &["python3", "-u", "/home/me/app.py", "--port", "80"]), Some("/home/me/app.py".to_string()));
+        assert_eq!(target("/usr/bin/python3", &["python3", "-m", "http.server"]), Some("http.server".to_string()));
+        assert_eq!(target("/usr/bin/python3", &["python3", "-c", "print(1)"]), None);
+        assert_eq!(target("/usr/lib/jvm/bin/java", &["java", "-Xmx2g", "-cp", "lib/*", "-jar", "tool.jar"]), Some("tool.jar".to_string()));
+        assert_eq!(target("/usr/bin/node", &["node", "--require", "dotenv/config", "server.js"]), Some("server.js".to_string()));
+        assert_eq!(target("/opt/foo/electron", &["/opt/foo/electron", "--no-sandbox", "/opt/foo/app.asar"]), Some("/opt/foo/app.asar".to_string()));
+        assert_eq!(target("/usr/bin/wine64-preloader", &["C:\\Games\\game.exe", "-windowed"]), Some("C:\\Games\\game.exe".to_string()));
+        assert_eq!(target("/usr/bin/bash", &["bash", "script.sh"]), None);
+    }
+
+    #[test]
+    fn test_script_rules() {
+        let rule = |pattern: &str| {
+            CompiledRule::new(&MatchRule {
+                kind: MatchKind::Script,
+                pattern: pattern.to_string(),
+                case_sensitive: false,
+                exclusions: Vec::new(),
+            })
+            .unwrap()
+        };
+
+        let java = launched("/usr/bin/java", &["java", "-jar", "/opt/tools/Tool-1.2.jar"]);
+        assert!(rule("tool-*.jar").matches(&java));
+        assert!(rule("/opt/tools/*.jar").matches(&java));
+        assert!(!rule("other.jar").matches(&java));
+
+        let wine = launched("/usr/bin/wine64-preloader", &["C:\\Games\\Game.exe"]);
+        assert!(rule("game.exe").matches(&wine));
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_rejected() {
+        let rule = MatchRule {
+            kind: MatchKind::CmdlineRegex,
+            pattern: "python3 (".to_string(),
+            case_sensitive: false,
+            exclusions: Vec::new(),
+        };
+        assert!(CompiledRule::new(&rule).is_err());
+    }
+}
@@ -1,17 +1,195 @@
+use crate::allowlist::Allowlist;
+use crate::cgroup::CgroupFreezer;
+use crate::desktop::{self, DesktopEntry, ExecTarget};
+use crate::detection::DetectionBackend;
+use crate::elevation::ElevationGuard;
+use crate::identity::{AppIdentity, LazyIdentity};
+use crate::matching::{self, CompiledRule, MatchKind, MatchRule};
+use crate::schedule::{CompiledSchedule, ScheduleTransition};
+use chrono::{DateTime, Utc};
 use nix::sys::signal::{self, Signal};
 use nix::unistd::Pid;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tokio::time;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: i32,
     pub name: String,
     pub exe_path: Option<String>,
+    pub ppid: i32,
+    pub session: i32,
+    /// Full argv from /proc/[pid]/cmdline
+    pub cmdline: Vec<String>,
+}
+
+/// Result of taking down a protected process and everything it owns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillReport {
+    pub pid: i32,
+    pub process_name: String,
+    pub pids_terminated: usize,
+    pub strategy: TerminationStrategy,
+    /// SIGTERM was ignored and the tree had to be SIGKILLed
+    pub escalated: bool,
+    /// Protected app, unlisted process or blocked elevation attempt
+    #[serde(default)]
+    pub reason: KillReason,
+    /// Set in audit mode: the process that would have been terminated.
+    /// Nothing was signalled and `pids_terminated` counts what would have been.
+    #[serde(default)]
+    pub audit: Option<ProcessInfo>,
+    /// Auto-kill (`policy_1`) is off: the match was only alerted on and
+    /// nothing was signalled
+    #[serde(default)]
+    pub alert_only: bool,
+}
+
+/// Why a process was enforced
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KillReason {
+    /// Matched a protected app
+    #[default]
+    Protected,
+    /// Not on the allowlist in allowlist mode
+    Unlisted,
+    /// sudo, su, pkexec, doas or run0 while locked (Root Access Prevention)
+    Elevation {
+        cmdline: Vec<String>,
+        parent_pid: i32,
+        parent_name: Option<String>,
+    },
+}
+
+/// How a protected app's processes are stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationStrategy {
+    /// SIGKILL immediately
+    Kill,
+    /// SIGTERM, then SIGKILL whatever is left after the grace period
+    Terminate,
+    /// SIGSTOP until the next unlock
+    Stop,
+    /// cgroup v2 freezer until the next unlock, SIGSTOP if that's unavailable
+    Freeze,
+}
+
+impl TerminationStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TerminationStrategy::Kill => "kill",
+            TerminationStrategy::Terminate => "terminate",
+            TerminationStrategy::Stop => "stop",
+            TerminationStrategy::Freeze => "freeze",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "kill" => Some(TerminationStrategy::Kill),
+            "terminate" => Some(TerminationStrategy::Terminate),
+            "stop" => Some(TerminationStrategy::Stop),
+            "freeze" => Some(TerminationStrategy::Freeze),
+            _ => None,
+        }
+    }
+}
+
+/// A watch list entry as the monitor sees it
+#[derive(Debug, Clone)]
+pub struct ProtectedTarget {
+    pub process_name: String,
+    pub rule: MatchRule,
+    pub matcher: CompiledRule,
+    pub strategy: TerminationStrategy,
+    pub grace_period: Duration,
+    /// Pinned SHA-256 of the executable, matched whatever the binary is called
+    pub exe_sha256: Option<String>,
+    /// Under a temporary unlock, left alone until the grant is expired
+    pub granted: bool,
+    /// Enforced by time of day instead of by the shield
+    pub schedule: Option<CompiledSchedule>,
+    /// Daily quota used up, enforced until the day rolls over
+    pub quota_exhausted: bool,
+    /// Matches are only reported, never signalled
+    pub audit_only: bool,
+}
+
+impl ProtectedTarget {
+    /// Whether the entry is enforced right now: once its daily quota is
+    /// used up, inside its schedule's blocked windows, or while the shield
+    /// is locked when it has no schedule
+    pub fn is_enforced(&self, locked: bool, now: DateTime<Utc>) -> bool {
+        if self.granted {
+            return false;
+        }
+        if self.quota_exhausted {
+            return true;
+        }
+        match &self.schedule {
+            Some(schedule) => schedule.is_blocking(now),
+            None => locked,
+        }
+    }
+}
+
+/// Launch wrappers that say nothing about the app a desktop entry starts
+const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "xdg-open", "gtk-launch"];
+
+/// Identifies an executable's contents without reading it: (device, inode, mtime)
+type ExeKey = (u64, u64, i64);
+
+/// Directories searched when turning a protected process name into binaries
+const BINARY_DIRS: &[&str] = &[
+    "/usr/bin",
+    "/usr/local/bin",
+    "/bin",
+    "/snap/bin",
+    "/var/lib/flatpak/exports/bin",
+];
+
+/// A systemd `app-*.scope` and the PIDs in it
+struct AppScope {
+    name: String,
+    members: Vec<i32>,
+}
+
+impl AppScope {
+    /// The process the scope was created for: the PID launchers encode in
+    /// the name (`app-gnome-firefox-4242.scope`) while it is still a member,
+    /// otherwise the oldest member
+    fn main_pid(&self, start_time: impl Fn(i32) -> Option<u64>) -> Option<i32> {
+        let encoded = self
+            .name
+            .strip_suffix(".scope")
+            .and_then(|stem| stem.rsplit_once('-'))
+            .and_then(|(_, pid)| pid.parse::<i32>().ok())
+            .filter(|pid| self.members.contains(pid));
+        encoded.or_else(|| {
+            self.members
+                .iter()
+                .filter_map(|&pid| Some((start_time(pid)?, pid)))
+                .min()
+                .map(|(_, pid)| pid)
+        })
+    }
+}
+
+/// A SIGTERMed tree waiting for its grace period to run out
+struct PendingTermination {
+    /// PID and start time, so a PID reused by then isn't SIGKILLed
+    pids: Vec<(i32, u64)>,
+    deadline: Instant,
+    report: KillReport,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,11 +198,68 @@ pub struct AppCandidate {
     pub process_name: String,
     pub exe_path: Option<String>,
     pub category: String,
+    /// Rule to protect the candidate with, when the process name isn't enough
+    pub match_rule: Option<MatchRule>,
+    /// Icon name or path from the desktop entry
+    pub icon: Option<String>,
+    /// StartupWMClass from the desktop entry
+    pub wm_class: Option<String>,
+}
+
+/// How protected apps are stopped while the shield is locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementBackend {
+    /// Kill matching processes as soon as they are detected
+    KillOnSight,
+    /// Deny the exec of protected binaries with fanotify, before they run
+    PreExec,
+}
+
+impl EnforcementBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnforcementBackend::KillOnSight => "kill_on_sight",
+            EnforcementBackend::PreExec => "pre_exec",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "kill_on_sight" => Some(EnforcementBackend::KillOnSight),
+            "pre_exec" => Some(EnforcementBackend::PreExec),
+            _ => None,
+        }
+    }
 }
 
 pub struct ProcessMonitor {
     is_monitoring: Arc<Mutex<bool>>,
-    protected_processes: Arc<Mutex<Vec<String>>>,
+    protected_processes: Arc<Mutex<Vec<ProtectedTarget>>>,
+    enforcement_backend: Arc<Mutex<EnforcementBackend>>,
+    pending_terminations: Arc<Mutex<Vec<PendingTermination>>>,
+    /// SIGSTOPped PID -> entry it was stopped for
+    stopped_pids: Arc<Mutex<HashMap<i32, String>>>,
+    freezer: Arc<Mutex<CgroupFreezer>>,
+    hash_cache: Arc<Mutex<HashMap<ExeKey, String>>>,
+    /// Check every running process on the next pass, not just new ones
+    sweep_requested: Arc<Mutex<bool>>,
+    /// Left running after their temporary unlock ran out
+    spared_pids: Arc<Mutex<HashSet<i32>>>,
+    /// Last seen blocking state of each scheduled entry, by process name
+    schedule_states: Arc<Mutex<HashMap<String, bool>>>,
+    /// Default-deny enforcement while locked, when allowlist mode is active
+    allowlist: Arc<Mutex<Option<Arc<Allowlist>>>>,
+    /// Root Access Prevention while locked, when the policy is enabled
+    elevation_guard: Arc<Mutex<Option<Arc<ElevationGuard>>>>,
+    /// Report every match instead of acting on it
+    audit_mode: Arc<Mutex<bool>>,
+    /// Act on protected app matches; when off (`policy_1`) they are only
+    /// alerted on
+    auto_kill: Arc<Mutex<bool>>,
+    /// Already reported without being signalled (audit or alert-only), so
+    /// each process is reported once
+    reported_pids: Arc<Mutex<HashSet<i32>>>,
 }
 
 impl ProcessMonitor {
@@ -32,9 +267,113 @@ impl ProcessMonitor {
         ProcessMonitor {
             is_monitoring: Arc::new(Mutex::new(false)),
             protected_processes: Arc::new(Mutex::new(Vec::new())),
+            enforcement_backend: Arc::new(Mutex::new(EnforcementBackend::KillOnSight)),
+            pending_terminations: Arc::new(Mutex::new(Vec::new())),
+            stopped_pids: Arc::new(Mutex::new(HashMap::new())),
+            freezer: Arc::new(Mutex::new(CgroupFreezer::new())),
+            hash_cache: Arc::new(Mutex::new(HashMap::new())),
+            sweep_requested: Arc::new(Mutex::new(false)),
+            spared_pids: Arc::new(Mutex::new(HashSet::new())),
+            schedule_states: Arc::new(Mutex::new(HashMap::new())),
+            allowlist: Arc::new(Mutex::new(None)),
+            elevation_guard: Arc::new(Mutex::new(None)),
+            audit_mode: Arc::new(Mutex::new(false)),
+            auto_kill: Arc::new(Mutex::new(true)),
+            reported_pids: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
+    /// Have the monitor loop re-check everything already running, e.g. when
+    /// a temporary unlock runs out
+    pub fn request_sweep(&self) {
+        *self.sweep_requested.lock().unwrap() = true;
+    }
+
+    fn take_sweep_request(&self) -> bool {
+        std::mem::take(&mut *self.sweep_requested.lock().unwrap())
+    }
+
+    /// Leave the running instances of a watch list entry alone, for a grant
+    /// that ran out without killing. New launches are still enforced.
+    /// Returns how many processes were spared.
+    pub fn spare_running(&self, process_name: &str) -> usize {
+        let target = match self
+            .get_protected_processes()
+            .into_iter()
+            .find(|target| target.process_name == process_name)
+        {
+            Some(target) => target,
+            None => return 0,
+        };
+
+        let pids: Vec<i32> = Self::get_all_processes()
+            .into_iter()
+            .filter(|process| target.matcher.matches(process))
+            .map(|process| process.pid)
+            .collect();
+        let count = pids.len();
+        self.spared_pids.lock().unwrap().extend(pids);
+        count
+    }
+
+    /// Scheduled entries whose blocking state changed since the last call.
+    /// Entering a blocked window requests a sweep so instances that were
+    /// already running are caught too.
+    pub fn schedule_transitions(&self, now: DateTime<Utc>) -> Vec<ScheduleTransition> {
+        let mut states = self.schedule_states.lock().unwrap();
+        let mut current = HashMap::new();
+        let mut transitions = Vec::new();
+
+        for target in self.get_protected_processes() {
+            let blocking = match &target.schedule {
+                Some(schedule) => schedule.is_blocking(now),
+                None => continue,
+            };
+            let previous = states.get(&target.process_name).copied();
+            // A schedule seen for the first time has no transition to report
+            if previous.is_some_and(|previous| previous != blocking) {
+                transitions.push(ScheduleTransition {
+                    process_name: target.process_name.clone(),
+                    blocking,
+                });
+            }
+            if blocking && previous != Some(true) {
+                self.request_sweep();
+            }
+            current.insert(target.process_name, blocking);
+        }
+
+        *states = current;
+        transitions
+    }
+
+    /// Entries with a matching process running while they are allowed to,
+    /// which is the time counted against daily quotas
+    pub fn running_unenforced(&self, processes: &[ProcessInfo]) -> HashSet<String> {
+        let locked = self.is_monitoring();
+        let now = Utc::now();
+        let unenforced: Vec<ProtectedTarget> = self
+            .get_protected_processes()
+            .into_iter()
+            .filter(|target| !target.is_enforced(locked, now))
+            .collect();
+        let mut running = HashSet::new();
+        for process in processes {
+            let identity = LazyIdentity::new(process.pid);
+            for target in &unenforced {
+                if target.matcher.matches_with(process, &identity) {
+                    running.insert(target.process_name.clone());
+                }
+            }
+        }
+        running
+    }
+
+    /// Forget spared processes, so the next lock enforces them again
+    pub fn clear_spared(&self) {
+        self.spared_pids.lock().unwrap().clear();
+    }
+
     pub fn set_monitoring(&self, enabled: bool) {
         let mut monitoring = self.is_monitoring.lock().unwrap();
         *monitoring = enabled;
@@ -44,11 +383,128 @@ impl ProcessMonitor {
         *self.is_monitoring.lock().unwrap()
     }
 
-    pub fn update_protected_processes(&self, processes: Vec<String>) {
+    pub fn update_protected_processes(&self, processes: Vec<ProtectedTarget>) {
         let mut protected = self.protected_processes.lock().unwrap();
         *protected = processes;
     }
 
+    /// Enforce elevation attempts while locked, or stop with `None`
+    pub fn set_elevation_guard(&self, guard: Option<ElevationGuard>) {
+        *self.elevation_guard.lock().unwrap() = guard.map(Arc::new);
+    }
+
+    pub fn set_audit_mode(&self, enabled: bool) {
+        *self.audit_mode.lock().unwrap() = enabled;
+        self.forget_reported();
+    }
+
+    pub fn is_audit_mode(&self) -> bool {
+        *self.audit_mode.lock().unwrap()
+    }
+
+    /// Terminate protected app matches (`policy_1` on) or only alert on them
+    pub fn set_auto_kill(&self, enabled: bool) {
+        *self.auto_kill.lock().unwrap() = enabled;
+        self.forget_reported();
+    }
+
+    pub fn is_auto_kill(&self) -> bool {
+        *self.auto_kill.lock().unwrap()
+    }
+
+    /// Let processes that were only reported be matched again, and enforce
+    /// them right away if enforcement has just resumed
+    pub fn forget_reported(&self) {
+        self.reported_pids.lock().unwrap().clear();
+        self.request_sweep();
+    }
+
+    /// Install allowlist enforcement, or go back to the protected apps only
+    /// with `None`. Only the kill-on-sight path enforces it.
+    pub fn set_allowlist(&self, allowlist: Option<Allowlist>) {
+        *self.allowlist.lock().unwrap() = allowlist.map(Arc::new);
+    }
+
+    pub fn get_protected_processes(&self) -> Vec<ProtectedTarget> {
+        self.protected_processes.lock().unwrap().clone()
+    }
+
+    /// Thaw frozen apps and SIGCONT everything that was stopped while locked
+    pub fn resume_suspended(&self) -> usize {
+        let stopped: Vec<i32> = self.stopped_pids.lock().unwrap().drain().map(|(pid, _)| pid).collect();
+        let mut resumed = stopped
+            .into_iter()
+            .filter(|pid| signal::kill(Pid::from_raw(*pid), Signal::SIGCONT).is_ok())
+            .count();
+        resumed += self.freezer.lock().unwrap().thaw_all();
+
+        if resumed > 0 {
+            println!("Resumed {} suspended processes", resumed);
+        }
+        resumed
+    }
+
+    /// Thaw or SIGCONT what was suspended for one entry, e.g. when its
+    /// schedule stops blocking it while the shield is still unlocked
+    pub fn resume_group(&self, process_name: &str) -> usize {
+        let stopped: Vec<i32> = {
+            let mut stopped_pids = self.stopped_pids.lock().unwrap();
+            let pids: Vec<i32> = stopped_pids
+                .iter()
+                .filter(|(_, group)| group.as_str() == process_name)
+                .map(|(pid, _)| *pid)
+                .collect();
+            for pid in &pids {
+                stopped_pids.remove(pid);
+            }
+            pids
+        };
+        let mut resumed = stopped
+            .into_iter()
+            .filter(|pid| signal::kill(Pid::from_raw(*pid), Signal::SIGCONT).is_ok())
+            .count();
+        resumed += self.freezer.lock().unwrap().thaw(process_name);
+
+        if resumed > 0 {
+            println!("Resumed {} suspended processes of {}", resumed, process_name);
+        }
+        resumed
+    }
+
+    /// Forget SIGTERMed trees still in their grace period, so nothing is
+    /// SIGKILLed after the user has unlocked. Entries that stay enforced
+    /// while unlocked (schedules, quotas) are picked up again by a sweep.
+    pub fn cancel_pending_terminations(&self) -> usize {
+        let cancelled: usize = self
+            .pending_terminations
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|pending| pending.pids.len())
+            .sum();
+        if cancelled > 0 {
+            println!("Cancelled pending termination of {} processes", cancelled);
+            self.request_sweep();
+        }
+        cancelled
+    }
+
+    /// Number of processes stopped or frozen until the next unlock
+    pub fn suspended_count(&self) -> usize {
+        self.stopped_pids.lock().unwrap().len() + self.freezer.lock().unwrap().frozen_pids().len()
+    }
+
+    /// Kill-on-sight keeps running under the pre-exec backend as a safety net
+    /// for processes that were already running when the shield locked.
+    pub fn set_enforcement_backend(&self, backend: EnforcementBackend) {
+        let mut current = self.enforcement_backend.lock().unwrap();
+        *current = backend;
+    }
+
+    pub fn enforcement_backend(&self) -> EnforcementBackend {
+        *self.enforcement_backend.lock().unwrap()
+    }
+
     pub fn get_all_processes() -> Vec<ProcessInfo> {
         let mut processes = Vec::new();
 
@@ -68,7 +524,7 @@ impl ProcessMonitor {
         processes
     }
 
-    fn get_process_info(pid: i32) -> Option<ProcessInfo> {
+    pub(crate) fn get_process_info(pid: i32) -> Option<ProcessInfo> {
         // Read /proc/[pid]/comm for process name
         let comm_path = PathBuf::from(format!("/proc/{}/comm", pid));
         let name = fs::read_to_string(&comm_path).ok()?.trim().to_string();
@@ -78,112 +534,581 @@ impl ProcessMonitor {
             .ok()
             .and_then(|p| p.to_str().map(|s| s.to_string()));
 
+        // Read /proc/[pid]/stat for the parent and session
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let (ppid, session) = Self::parse_stat(&stat)?;
+
+        // Kernel threads have an empty cmdline
+        let cmdline = fs::read(format!("/proc/{}/cmdline", pid))
+            .map(|raw| Self::parse_cmdline(&raw))
+            .unwrap_or_default();
+
         Some(ProcessInfo {
             pid,
             name,
             exe_path,
+            ppid,
+            session,
+            cmdline,
         })
     }
 
-    pub fn check_and_kill_protected(&self) -> Vec<(i32, String)> {
-        let mut killed = Vec::new();
-
-        if !self.is_monitoring() {
-            return killed;
+    /// Split the NUL-separated contents of /proc/[pid]/cmdline into argv
+    fn parse_cmdline(raw: &[u8]) -> Vec<String> {
+        if raw.is_empty() {
+            return Vec::new();
         }
+        raw.strip_suffix(&[0])
+            .unwrap_or(raw)
+            .split(|b| *b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect()
+    }
 
+    /// Extract (ppid, session) from the contents of /proc/[pid]/stat.
+    /// The comm field may contain spaces and parentheses, so fields are
+    /// counted from the last ')'.
+    fn parse_stat(stat: &str) -> Option<(i32, i32)> {
+        let rest = stat.get(stat.rfind(')')? + 1..)?;
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+
+        // state ppid pgrp session ...
+        let ppid = fields.get(1)?.parse().ok()?;
+        let session = fields.get(3)?.parse().ok()?;
+        Some((ppid, session))
+    }
+
+    /// Stop every process in `processes` that matches the watch list, along
+    /// with its process tree, using the entry's termination strategy. The
+    /// candidates come from whichever `DetectionBackend` is active.
+    ///
+    /// SIGTERMed trees are reported later by `resolve_pending_terminations`.
+    pub fn check_and_kill_protected(&self, processes: Vec<ProcessInfo>) -> Vec<KillReport> {
+        let mut reports = Vec::new();
+
+        let locked = self.is_monitoring();
+        let now = Utc::now();
         let protected = self.protected_processes.lock().unwrap().clone();
-        let processes = Self::get_all_processes();
+        let allowlist = if locked { self.allowlist.lock().unwrap().clone() } else { None };
+        let elevation = if locked { self.elevation_guard.lock().unwrap().clone() } else { None };
+        if allowlist.is_none()
+            && elevation.is_none()
+            && !protected.iter().any(|target| target.is_enforced(locked, now))
+        {
+            return reports;
+        }
+
+        // Pins from before shared binaries were refused are ignored
+        let pinned: Vec<&ProtectedTarget> = protected
+            .iter()
+            .filter(|target| target.exe_sha256.is_some() && target.rule.identifies_binary())
+            .collect();
+        // Full process table, only read once something matches
+        let mut all_processes: Option<Vec<ProcessInfo>> = None;
+        // Already stopped or waiting on a grace period
+        let mut handled: HashSet<i32> = self.stopped_pids.lock().unwrap().keys().copied().collect();
+        {
+            let mut spared = self.spared_pids.lock().unwrap();
+            spared.retain(|pid| Self::is_alive(*pid));
+            handled.extend(spared.iter().copied());
+        }
+        handled.extend(self.freezer.lock().unwrap().frozen_pids());
+        let audit_mode = self.is_audit_mode();
+        let auto_kill = self.is_auto_kill();
+        {
+            let mut reported = self.reported_pids.lock().unwrap();
+            reported.retain(|pid| Self::is_alive(*pid));
+            handled.extend(reported.iter().copied());
+        }
+        for pending in self.pending_terminations.lock().unwrap().iter() {
+            handled.extend(pending.pids.iter().map(|(pid, _)| *pid));
+        }
 
         for process in processes {
-            // Check if process name matches any protected process
-            let is_protected = protected.iter().any(|protected_name| {
-                Self::process_matches(protected_name, &process.name, &process.exe_path)
-            });
-
-            if is_protected {
-                // Kill the process with SIGKILL
-                if Self::kill_process(process.pid) {
-                    killed.push((process.pid, process.name.clone()));
-                    println!("Killed protected process: {} (PID: {})", process.name, process.pid);
+            if handled.contains(&process.pid) {
+                continue;
+            }
+
+            let identity = LazyIdentity::new(process.pid);
+            let target = protected
+                .iter()
+                .find(|target| target.matcher.matches_with(&process, &identity))
+                .or_else(|| {
+                    // Renamed or copied binaries only give themselves away by content
+                    if pinned.is_empty() {
+                        return None;
+                    }
+                    let hash = self.exe_hash(process.pid)?;
+                    pinned
+                        .iter()
+                        .copied()
+                        .find(|target| target.exe_sha256.as_deref() == Some(hash.as_str()))
+                });
+
+            let (group, strategy, grace_period, reason, audit_only) = match target {
+                Some(target) if target.is_enforced(locked, now) => {
+                    (target.process_name.clone(), target.strategy, target.grace_period, KillReason::Protected, target.audit_only)
+                }
+                Some(_) => continue,
+                None if elevation.as_ref().is_some_and(|guard| guard.blocks(&process)) => {
+                    let all = all_processes.get_or_insert_with(Self::get_all_processes);
+                    let reason = KillReason::Elevation {
+                        cmdline: process.cmdline.clone(),
+                        parent_pid: process.ppid,
+                        parent_name: all.iter().find(|p| p.pid == process.ppid).map(|p| p.name.clone()),
+                    };
+                    // Elevation prompts are short-lived, so they're always killed
+                    (process.name.clone(), TerminationStrategy::Kill, Duration::ZERO, reason, false)
+                }
+                None => match &allowlist {
+                    Some(list) if !list.allows(&process) && list.applies_to(&process) => {
+                        let all = all_processes.get_or_insert_with(Self::get_all_processes);
+                        if Self::is_related_to_self(process.pid, all) {
+                            continue;
+                        }
+                        (process.name.clone(), list.strategy, list.grace_period, KillReason::Unlisted, false)
+                    }
+                    _ => continue,
+                },
+            };
+
+            let all = all_processes.get_or_insert_with(Self::get_all_processes);
+            // A single command (unlisted, or sudo in a terminal) must not
+            // take the terminal and shell it was started from with it
+            let kill_set = match reason {
+                KillReason::Protected => Self::collect_kill_set(&process, all),
+                KillReason::Unlisted | KillReason::Elevation { .. } => Self::collect_process_tree(&process, all),
+            };
+            let targets: Vec<i32> = kill_set
+                .into_iter()
+                .filter(|pid| handled.insert(*pid))
+                .collect();
+
+            if audit_mode || audit_only {
+                println!(
+                    "Audit: would have applied {} to {} (PID: {}, {} PIDs total)",
+                    strategy.as_str(), process.name, process.pid, targets.len()
+                );
+                self.reported_pids.lock().unwrap().extend(targets.iter().copied());
+                reports.push(KillReport {
+                    pid: process.pid,
+                    process_name: process.name.clone(),
+                    pids_terminated: targets.len(),
+                    strategy,
+                    escalated: false,
+                    reason,
+                    audit: Some(process.clone()),
+                    alert_only: false,
+                });
+                continue;
+            }
+
+            // Auto-kill covers protected apps; Root Access Prevention and
+            // allowlist mode have their own switches
+            if !auto_kill && reason == KillReason::Protected {
+                println!("Alert: {} detected (PID: {}), auto-kill is off", process.name, process.pid);
+                self.reported_pids.lock().unwrap().extend(targets.iter().copied());
+                reports.push(KillReport {
+                    pid: process.pid,
+                    process_name: process.name.clone(),
+                    pids_terminated: 0,
+                    strategy,
+                    escalated: false,
+                    reason,
+                    audit: None,
+                    alert_only: true,
+                });
+                continue;
+            }
+
+            let (strategy, signalled) = match strategy {
+                TerminationStrategy::Freeze => {
+                    let frozen = self.freezer.lock().unwrap().freeze(&group, &targets);
+                    match frozen {
+                        Ok(frozen) => (TerminationStrategy::Freeze, frozen),
+                        Err(e) => {
+                            eprintln!("cgroup freeze failed ({}), falling back to SIGSTOP", e);
+                            (TerminationStrategy::Stop, Self::signal_all(targets, Signal::SIGSTOP))
+                        }
+                    }
+                }
+                TerminationStrategy::Kill => (TerminationStrategy::Kill, Self::signal_all(targets, Signal::SIGKILL)),
+                TerminationStrategy::Terminate => (TerminationStrategy::Terminate, Self::signal_all(targets, Signal::SIGTERM)),
+                TerminationStrategy::Stop => (TerminationStrategy::Stop, Self::signal_all(targets, Signal::SIGSTOP)),
+            };
+
+            if signalled.is_empty() {
+                continue;
+            }
+
+            println!(
+                "Applied {} to protected process: {} (PID: {}, {} PIDs total)",
+                strategy.as_str(), process.name, process.pid, signalled.len()
+            );
+
+            let report = KillReport {
+                pid: process.pid,
+                process_name: process.name.clone(),
+                pids_terminated: signalled.len(),
+                strategy,
+                escalated: false,
+                reason,
+                audit: None,
+                alert_only: false,
+            };
+
+            match strategy {
+                TerminationStrategy::Kill => reports.push(report),
+                TerminationStrategy::Terminate => {
+                    self.pending_terminations.lock().unwrap().push(PendingTermination {
+                        pids: signalled
+                            .into_iter()
+                            .filter_map(|pid| Some((pid, Self::start_time(pid)?)))
+                            .collect(),
+                        deadline: Instant::now() + grace_period,
+                        report,
+                    });
+                }
+                TerminationStrategy::Stop => {
+                    self.stopped_pids.lock().unwrap().extend(signalled.into_iter().map(|pid| (pid, group.clone())));
+                    reports.push(report);
                 }
+                TerminationStrategy::Freeze => reports.push(report),
             }
         }
 
-        killed
+        reports
     }
 
-    /// Improved matching logic that handles app name variations
-    /// e.g., "brave" matches "brave", "brave-browser", "brave-browser-stable"
-    fn process_matches(protected_name: &str, process_name: &str, exe_path: &Option<String>) -> bool {
-        let protected_lower = protected_name.to_lowercase();
-        let process_lower = process_name.to_lowercase();
+    /// Report SIGTERMed trees that have exited, and SIGKILL the ones whose
+    /// grace period ran out
+    pub fn resolve_pending_terminations(&self) -> Vec<KillReport> {
+        let mut pending = self.pending_terminations.lock().unwrap();
+        let now = Instant::now();
+        let mut reports = Vec::new();
 
-        // Exact match
-        if process_lower == protected_lower {
-            return true;
+        pending.retain_mut(|termination| {
+            termination
+                .pids
+                .retain(|(pid, started)| Self::is_alive(*pid) && Self::start_time(*pid) == Some(*started));
+
+            if termination.pids.is_empty() {
+                reports.push(termination.report.clone());
+                return false;
+            }
+
+            if now >= termination.deadline {
+                for (pid, _) in &termination.pids {
+                    Self::send_signal(*pid, Signal::SIGKILL);
+                }
+                println!(
+                    "Grace period expired for {} (PID: {}), escalated to SIGKILL",
+                    termination.report.process_name, termination.report.pid
+                );
+                termination.report.escalated = true;
+                reports.push(termination.report.clone());
+                return false;
+            }
+
+            true
+        });
+
+        reports
+    }
+
+    /// SHA-256 of the binary behind /proc/[pid]/exe, cached by inode and
+    /// mtime so each executable is only read once
+    fn exe_hash(&self, pid: i32) -> Option<String> {
+        if pid == std::process::id() as i32 {
+            return None;
         }
 
-        // Check if process name starts with protected name (e.g., "brave" matches "brave-browser")
-        if process_lower.starts_with(&protected_lower) {
-            return true;
+        let exe = PathBuf::from(format!("/proc/{}/exe", pid));
+        let meta = fs::metadata(&exe).ok()?;
+        let key = (meta.dev(), meta.ino(), meta.mtime());
+
+        if let Some(hash) = self.hash_cache.lock().unwrap().get(&key) {
+            return Some(hash.clone());
         }
 
-        // Check if protected name starts with process name (e.g., "brave-browser" matches "brave")
-        if protected_lower.starts_with(&process_lower) {
-            return true;
+        let hash = Self::hash_file(&exe).ok()?;
+        self.hash_cache.lock().unwrap().insert(key, hash.clone());
+        Some(hash)
+    }
+
+    pub fn hash_file(path: &Path) -> io::Result<String> {
+        let mut file = fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Hash to pin for an app: the file on disk a running instance was
+    /// started from when there is one, otherwise the first binary its name
+    /// resolves to. An instance still running the binary a package update
+    /// replaced points at the new file, so refreshing pins that.
+    pub fn hash_for_app(process_name: &str, rule: &MatchRule) -> Result<String, String> {
+        if !rule.identifies_binary() {
+            return Err(format!("{} runs a shared interpreter or sandbox binary, so it can't be hash pinned", process_name));
         }
+        let matcher = CompiledRule::new(rule)?;
+        let running = Self::get_all_processes()
+            .into_iter()
+            .filter(|process| matcher.matches(process))
+            .filter_map(|process| process.exe_path)
+            .map(|exe| PathBuf::from(exe.strip_suffix(" (deleted)").unwrap_or(&exe)))
+            .find(|path| path.is_file());
 
-        // Check executable path
-        if let Some(exe) = exe_path {
-            let exe_lower = exe.to_lowercase();
+        let path = running
+            .or_else(|| Self::resolve_binaries(process_name, rule).into_iter().next())
+            .ok_or_else(|| format!("No executable found for {}", process_name))?;
 
-            // Check if exe contains the protected name
-            if exe_lower.contains(&protected_lower) {
-                return true;
+        Self::hash_file(&path).map_err(|e| format!("Failed to hash {:?}: {}", path, e))
+    }
+
+    /// Canonical binaries an app runs from: the path pinned by an exe-path
+    /// rule, otherwise the process name looked up in the usual directories
+    pub fn resolve_binaries(process_name: &str, rule: &MatchRule) -> Vec<PathBuf> {
+        let home = Self::home_dir().unwrap_or_default();
+        let candidates: Vec<PathBuf> = match (rule.kind, rule.exe_path()) {
+            (_, Some(path)) => vec![PathBuf::from(path)],
+            // Sandboxed apps are started through their exported launchers
+            (MatchKind::Flatpak, None) => vec![
+                PathBuf::from("/var/lib/flatpak/exports/bin").join(&rule.pattern),
+                home.join(".local/share/flatpak/exports/bin").join(&rule.pattern),
+            ],
+            (MatchKind::Snap, None) => vec![PathBuf::from("/snap/bin").join(&rule.pattern)],
+            (MatchKind::AppImage, None) => vec![PathBuf::from(&rule.pattern)],
+            (_, None) => {
+                let mut dirs: Vec<PathBuf> = std::env::var("PATH")
+                    .unwrap_or_default()
+                    .split(':')
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+                dirs.extend(BINARY_DIRS.iter().map(PathBuf::from));
+
+                // Vendor installs such as /opt/google/chrome or /opt/brave.com/brave
+                if let Ok(entries) = fs::read_dir("/opt") {
+                    for vendor in entries.flatten() {
+                        dirs.push(vendor.path());
+                        if let Ok(products) = fs::read_dir(vendor.path()) {
+                            dirs.extend(products.flatten().map(|p| p.path()).filter(|p| p.is_dir()));
+                        }
+                    }
+                }
+
+                dirs.iter().map(|dir| dir.join(process_name)).collect()
             }
+        };
 
-            // Extract binary name from path and check
-            if let Some(binary_name) = exe.split('/').last() {
-                let binary_lower = binary_name.to_lowercase();
-                if binary_lower == protected_lower
-                    || binary_lower.starts_with(&protected_lower)
-                    || protected_lower.starts_with(&binary_lower) {
-                    return true;
+        let mut resolved: Vec<PathBuf> = Vec::new();
+        for candidate in candidates {
+            if !candidate.is_file() {
+                continue;
+            }
+            if let Ok(path) = fs::canonicalize(&candidate) {
+                if !resolved.contains(&path) {
+                    resolved.push(path);
                 }
             }
         }
+        resolved
+    }
 
-        false
+    /// Zombies still have a /proc entry but are already gone for our purposes
+    fn is_alive(pid: i32) -> bool {
+        match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => stat
+                .rfind(')')
+                .and_then(|i| stat[i + 1..].split_whitespace().next())
+                .map(|state| state != "Z" && state != "X")
+                .unwrap_or(false),
+            Err(_) => false,
+        }
     }
 
-    fn kill_process(pid: i32) -> bool {
-        match signal::kill(Pid::from_raw(pid), Signal::SIGKILL) {
+    /// Whether `pid` is Ficha itself, one of its ancestors or one of its
+    /// descendants, none of which allowlist mode may take down
+    fn is_related_to_self(pid: i32, processes: &[ProcessInfo]) -> bool {
+        let parents: HashMap<i32, i32> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+        let ancestry = |mut pid: i32| {
+            let mut chain = vec![pid];
+            while let Some(&ppid) = parents.get(&pid) {
+                if ppid <= 1 || chain.contains(&ppid) {
+                    break;
+                }
+                chain.push(ppid);
+                pid = ppid;
+            }
+            chain
+        };
+
+        let own_pid = std::process::id() as i32;
+        ancestry(pid).contains(&own_pid) || ancestry(own_pid).contains(&pid)
+    }
+
+    /// PIDs to take down with `root`: its descendants, the session it leads
+    /// and the per-app cgroup scope it was launched in. Ficha and its
+    /// ancestors are never included.
+    fn collect_kill_set(root: &ProcessInfo, processes: &[ProcessInfo]) -> Vec<i32> {
+        let scope = Self::app_scope(root.pid);
+        Self::kill_set(root, processes, scope.as_ref(), Self::start_time)
+    }
+
+    fn kill_set(
+        root: &ProcessInfo,
+        processes: &[ProcessInfo],
+        scope: Option<&AppScope>,
+        start_time: impl Fn(i32) -> Option<u64>,
+    ) -> Vec<i32> {
+        let mut roots = vec![root.pid];
+
+        // Session members are walked too, so their children come along
+        if root.session == root.pid {
+            for process in processes {
+                if process.session == root.pid && process.pid != root.pid {
+                    roots.push(process.pid);
+                }
+            }
+        }
+
+        // Apps started from a terminal, an IDE or another app's "open link"
+        // share the launcher's scope, so only the app it was created for owns it
+        if let Some(scope) = scope.filter(|scope| scope.main_pid(&start_time) == Some(root.pid)) {
+            for &pid in &scope.members {
+                if !roots.contains(&pid) {
+                    roots.push(pid);
+                }
+            }
+        }
+
+        Self::with_descendants(roots, processes)
+    }
+
+    /// `root` and its descendants only, for a single offending command
+    /// rather than a whole app
+    fn collect_process_tree(root: &ProcessInfo, processes: &[ProcessInfo]) -> Vec<i32> {
+        Self::with_descendants(vec![root.pid], processes)
+    }
+
+    fn with_descendants(mut targets: Vec<i32>, processes: &[ProcessInfo]) -> Vec<i32> {
+        let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+        for process in processes {
+            children.entry(process.ppid).or_default().push(process.pid);
+        }
+
+        let mut queue: VecDeque<i32> = targets.iter().copied().collect();
+        while let Some(pid) = queue.pop_front() {
+            for &child in children.get(&pid).into_iter().flatten() {
+                if !targets.contains(&child) {
+                    targets.push(child);
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        let exempt = Self::own_ancestry(processes);
+        targets.retain(|pid| *pid > 1 && !exempt.contains(pid));
+        targets
+    }
+
+    /// Ficha's own PID and every ancestor up to init
+    fn own_ancestry(processes: &[ProcessInfo]) -> HashSet<i32> {
+        let parents: HashMap<i32, i32> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+        let mut ancestry = HashSet::new();
+        let mut pid = std::process::id() as i32;
+
+        while pid > 0 && ancestry.insert(pid) {
+            pid = parents.get(&pid).copied().unwrap_or(0);
+        }
+
+        ancestry
+    }
+
+    /// The cgroup `pid` runs in, if it is a per-app scope (systemd
+    /// `app-*.scope`) rather than a shared session
+    fn app_scope(pid: i32) -> Option<AppScope> {
+        let cgroup = CgroupFreezer::cgroup_of(pid)?;
+        let leaf = cgroup.rsplit('/').next()?;
+        if !leaf.starts_with("app-") || !leaf.ends_with(".scope") {
+            return None;
+        }
+        if CgroupFreezer::cgroup_of(std::process::id() as i32).as_deref() == Some(cgroup.as_str()) {
+            return None;
+        }
+
+        let procs = fs::read_to_string(format!("/sys/fs/cgroup{}/cgroup.procs", cgroup)).ok()?;
+        Some(AppScope {
+            name: leaf.to_string(),
+            members: procs.lines().filter_map(|line| line.trim().parse().ok()).collect(),
+        })
+    }
+
+    /// Start time of `pid` in clock ticks since boot, which tells a process
+    /// apart from a later one that reused its PID
+    fn start_time(pid: i32) -> Option<u64> {
+        Self::parse_start_time(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+    }
+
+    fn parse_start_time(stat: &str) -> Option<u64> {
+        let rest = stat.get(stat.rfind(')')? + 1..)?;
+        // starttime is field 22; fields after the comm start at 3 (state)
+        rest.split_whitespace().nth(19)?.parse().ok()
+    }
+
+    /// Signal from the root down so launchers can't respawn children.
+    /// Returns the PIDs that were signalled.
+    fn signal_all(pids: Vec<i32>, signal: Signal) -> Vec<i32> {
+        pids.into_iter()
+            .filter(|pid| Self::send_signal(*pid, signal))
+            .collect()
+    }
+
+    fn send_signal(pid: i32, signal: Signal) -> bool {
+        match signal::kill(Pid::from_raw(pid), signal) {
             Ok(_) => true,
             Err(e) => {
-                eprintln!("Failed to kill process {}: {}", pid, e);
+                eprintln!("Failed to send {} to process {}: {}", signal, pid, e);
                 false
             }
         }
     }
 
-    pub async fn start_monitoring_loop<F>(
+    /// Blocking monitor loop, run on its own thread.
+    /// `interval_ms` bounds how long the backend may wait for new candidates.
+    pub fn start_monitoring_loop<F>(
         &self,
+        mut backend: Box<dyn DetectionBackend>,
         interval_ms: u64,
         on_kill: F,
     ) where
-        F: Fn(i32, String) + Send + 'static,
+        F: Fn(KillReport) + Send + 'static,
     {
-        let mut interval = time::interval(Duration::from_millis(interval_ms));
+        let timeout = Duration::from_millis(interval_ms);
+        let mut was_monitoring = false;
+
+        println!("Process detection backend: {}", backend.name());
 
         loop {
-            interval.tick().await;
+            let mut candidates = backend.next_candidates(timeout);
+            let monitoring = self.is_monitoring();
 
-            if self.is_monitoring() {
-                let killed = self.check_and_kill_protected();
-                for (pid, name) in killed {
-                    on_kill(pid, name);
-                }
+            // Event backends only report new execs, so sweep everything that
+            // was already running when the shield locks or a grant runs out
+            let sweep = self.take_sweep_request();
+            if (monitoring && !was_monitoring) || sweep {
+                candidates = Self::get_all_processes();
+            }
+            was_monitoring = monitoring;
+
+            // Scheduled entries are enforced whatever the shield says
+            for report in self.check_and_kill_protected(candidates) {
+                on_kill(report);
+            }
+
+            for report in self.resolve_pending_terminations() {
+                on_kill(report);
             }
         }
     }
@@ -200,6 +1125,51 @@ impl ProcessMonitor {
                 continue;
             }
 
+            // Sandboxed and bundled apps are offered by where they came from
+            if let Some(identity) = AppIdentity::of(process.pid) {
+                let (id, category, kind) = match &identity {
+                    AppIdentity::Flatpak(id) => (id.clone(), "Flatpak", MatchKind::Flatpak),
+                    AppIdentity::Snap(name) => (name.clone(), "Snap", MatchKind::Snap),
+                    AppIdentity::AppImage(path) => (path.clone(), "AppImage", MatchKind::AppImage),
+                };
+                if seen.insert(id.clone()) {
+                    let base = id.rsplit('/').next().unwrap_or(&id);
+                    candidates.push(Self::identity_candidate(
+                        Self::format_display_name(base),
+                        base,
+                        process.exe_path,
+                        category,
+                        kind,
+                        &id,
+                    ));
+                }
+                continue;
+            }
+
+            // Interpreted apps are offered by their script rather than the interpreter
+            if let Some(target) = matching::launch_target(&process) {
+                let script = target.rsplit(['/', '\\']).next().unwrap_or(&target).to_string();
+                if !script.is_empty() && seen.insert(script.clone()) {
+                    // Path globs only apply with a '/'; Windows paths match by file name
+                    let pattern = globset::escape(if target.contains('/') { &target } else { &script });
+                    candidates.push(AppCandidate {
+                        name: format!("{} ({})", script, process.name),
+                        process_name: script,
+                        exe_path: Some(target.clone()),
+                        category: "Running".to_string(),
+                        match_rule: Some(MatchRule {
+                            kind: MatchKind::Script,
+                            pattern,
+                            case_sensitive: true,
+                            exclusions: Vec::new(),
+                        }),
+                        icon: None,
+                        wm_class: None,
+                    });
+                }
+                continue;
+            }
+
             // Only add if we haven't seen this process name before
             if seen.insert(process.name.clone()) {
                 let display_name = Self::format_display_name(&process.name);
@@ -208,6 +1178,9 @@ impl ProcessMonitor {
                     process_name: process.name,
                     exe_path: process.exe_path,
                     category: "Running".to_string(),
+                    match_rule: None,
+                    icon: None,
+                    wm_class: None,
                 });
             }
         }
@@ -217,55 +1190,203 @@ impl ProcessMonitor {
         candidates
     }
 
-    /// Get common installed applications from standard Linux paths
+    /// Everything installed: desktop entries from the XDG data dirs, plus
+    /// snaps, flatpaks and AppImages that don't ship one
     pub fn get_installed_apps() -> Vec<AppCandidate> {
-        let mut apps = Vec::new();
-        let search_paths = vec![
-            "/usr/bin",
-            "/usr/local/bin",
-            "/snap/bin",
-            "/var/lib/flatpak/exports/bin",
-        ];
+        let mut apps: Vec<AppCandidate> = desktop::discover()
+            .into_iter()
+            .filter_map(|entry| Self::desktop_candidate(&entry))
+            .collect();
 
-        let common_apps = vec![
-            ("firefox", "Firefox", "Browser"),
-            ("google-chrome", "Google Chrome", "Browser"),
-            ("google-chrome-stable", "Google Chrome", "Browser"),
-            ("chromium", "Chromium", "Browser"),
-            ("chromium-browser", "Chromium", "Browser"),
-            ("brave", "Brave Browser", "Browser"),
-            ("brave-browser", "Brave Browser", "Browser"),
-            ("brave-browser-stable", "Brave Browser", "Browser"),
-            ("code", "Visual Studio Code", "Development"),
-            ("discord", "Discord", "Communication"),
-            ("slack", "Slack", "Communication"),
-            ("spotify", "Spotify", "Media"),
-            ("vlc", "VLC Media Player", "Media"),
-            ("steam", "Steam", "Gaming"),
-            ("gimp", "GIMP", "Graphics"),
-            ("obs", "OBS Studio", "Media"),
-            ("telegram", "Telegram", "Communication"),
-            ("telegram-desktop", "Telegram", "Communication"),
-            ("zoom", "Zoom", "Communication"),
-        ];
+        let mut seen: HashSet<String> = apps.iter().map(|app| app.process_name.clone()).collect();
+        for app in Self::flatpak_apps().into_iter().chain(Self::snap_apps()).chain(Self::appimage_apps()) {
+            if seen.insert(app.process_name.clone()) {
+                apps.push(app);
+            }
+        }
+
+        apps.sort_by_key(|app| app.name.to_lowercase());
+        apps
+    }
+
+    fn desktop_candidate(entry: &DesktopEntry) -> Option<AppCandidate> {
+        let category = entry.picker_category();
+        let mut candidate = match entry.exec_target()? {
+            ExecTarget::Binary(path, argv) => {
+                let process_name = path.file_name()?.to_string_lossy().to_string();
 
-        for (binary, display_name, category) in common_apps {
-            // Check if binary exists in any search path
-            for path in &search_paths {
-                let full_path = format!("{}/{}", path, binary);
-                if std::path::Path::new(&full_path).exists() {
-                    apps.push(AppCandidate {
-                        name: display_name.to_string(),
-                        process_name: binary.to_string(),
-                        exe_path: Some(full_path),
+                // Protecting the interpreter would block far more than the app
+                if let Some(script) = matching::argv_launch_target(&argv) {
+                    let script_name = script.rsplit(['/', '\\']).next().unwrap_or(&script).to_string();
+                    let pattern = globset::escape(if script.contains('/') { &script } else { &script_name });
+                    AppCandidate {
+                        name: entry.name.clone(),
+                        process_name: script_name,
+                        exe_path: Some(script),
                         category: category.to_string(),
-                    });
-                    break;
+                        match_rule: Some(MatchRule {
+                            kind: MatchKind::Script,
+                            pattern,
+                            case_sensitive: true,
+                            exclusions: Vec::new(),
+                        }),
+                        icon: None,
+                        wm_class: None,
+                    }
+                } else if SHELLS.contains(&process_name.as_str()) {
+                    return None;
+                } else {
+                    AppCandidate {
+                        name: entry.name.clone(),
+                        process_name,
+                        exe_path: Some(path.to_string_lossy().to_string()),
+                        category: category.to_string(),
+                        match_rule: None,
+                        icon: None,
+                        wm_class: None,
+                    }
                 }
             }
+            ExecTarget::Flatpak(app_id) => Self::identity_candidate(
+                entry.name.clone(), &app_id, None, category, MatchKind::Flatpak, &app_id,
+            ),
+            ExecTarget::Snap(name) => Self::identity_candidate(
+                entry.name.clone(), &name, Some(format!("/snap/bin/{}", name)), category, MatchKind::Snap, &name,
+            ),
+        };
+
+        candidate.icon = entry.icon.clone();
+        candidate.wm_class = entry.startup_wm_class.clone();
+        Some(candidate)
+    }
+
+    fn identity_candidate(name: String, process_name: &str, exe_path: Option<String>, category: &str, kind: MatchKind, id: &str) -> AppCandidate {
+        AppCandidate {
+            name,
+            process_name: process_name.to_string(),
+            exe_path,
+            category: category.to_string(),
+            match_rule: Some(MatchRule {
+                kind,
+                pattern: globset::escape(id),
+                case_sensitive: true,
+                exclusions: Vec::new(),
+            }),
+            icon: None,
+            wm_class: None,
         }
+    }
+
+    fn home_dir() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(PathBuf::from)
+    }
+
+    /// System and per-user flatpak installations
+    fn flatpak_apps() -> Vec<AppCandidate> {
+        let mut roots = vec![PathBuf::from("/var/lib/flatpak")];
+        roots.extend(Self::home_dir().map(|home| home.join(".local/share/flatpak")));
 
-        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut apps = Vec::new();
+        for root in roots {
+            let entries = match fs::read_dir(root.join("app")) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let app_id = entry.file_name().to_string_lossy().to_string();
+                let current = entry.path().join("current/active");
+                if !current.exists() || apps.iter().any(|app: &AppCandidate| app.process_name == app_id) {
+                    continue;
+                }
+
+                // The exported desktop entry carries the human-readable name
+                let desktop_id = format!("{}.desktop", app_id);
+                let desktop = current.join("export/share/applications").join(&desktop_id);
+                let name = fs::read_to_string(desktop)
+                    .ok()
+                    .and_then(|content| DesktopEntry::parse(&desktop_id, &content))
+                    .map(|entry| entry.name)
+                    .unwrap_or_else(|| app_id.clone());
+                let exe = root.join("exports/bin").join(&app_id);
+
+                apps.push(Self::identity_candidate(
+                    name,
+                    &app_id,
+                    exe.exists().then(|| exe.to_string_lossy().to_string()),
+                    "Flatpak",
+                    MatchKind::Flatpak,
+                    &app_id,
+                ));
+            }
+        }
+        apps
+    }
+
+    /// Installed snaps that declare apps, which leaves out bases and themes
+    fn snap_apps() -> Vec<AppCandidate> {
+        let entries = match fs::read_dir("/snap") {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut apps = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let has_apps = fs::read_to_string(entry.path().join("current/meta/snap.yaml"))
+                .map(|yaml| yaml.lines().any(|line| line.trim_end() == "apps:"))
+                .unwrap_or(false);
+            if !has_apps || name == "snapd" {
+                continue;
+            }
+
+            let exe = PathBuf::from("/snap/bin").join(&name);
+            apps.push(Self::identity_candidate(
+                Self::format_display_name(&name),
+                &name,
+                exe.exists().then(|| exe.to_string_lossy().to_string()),
+                "Snap",
+                MatchKind::Snap,
+                &name,
+            ));
+        }
+        apps
+    }
+
+    /// AppImages in the places AppImageLauncher and users usually keep them
+    fn appimage_apps() -> Vec<AppCandidate> {
+        let home = match Self::home_dir() {
+            Some(home) => home,
+            None => return Vec::new(),
+        };
+
+        let mut apps = Vec::new();
+        for dir in [home.join("Applications"), home.join(".local/bin")] {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if !file_name.to_lowercase().ends_with(".appimage") {
+                    continue;
+                }
+
+                // Drop the extension and any "-1.2.3-x86_64" suffix
+                let stem = &file_name[..file_name.len() - ".appimage".len()];
+                let base = stem.split(['-', '_']).next().unwrap_or(stem);
+                let path_str = path.to_string_lossy().to_string();
+
+                apps.push(Self::identity_candidate(
+                    Self::format_display_name(base),
+                    base,
+                    Some(path_str.clone()),
+                    "AppImage",
+                    MatchKind::AppImage,
+                    &path_str,
+                ));
+            }
+        }
         apps
     }
 
@@ -300,6 +1421,45 @@ impl ProcessMonitor {
 mod tests {
     use super::*;
 
+    /// A `sleep 30` that ignores SIGTERM, once it is running
+    fn spawn_ignoring_term() -> (std::process::Child, ProcessInfo) {
+        let child = std::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 30"])
+            .spawn()
+            .expect("failed to spawn sh");
+        let pid = child.id() as i32;
+        // The ignored disposition only holds once sh has set it and exec'd
+        for _ in 0..100 {
+            if fs::read_to_string(format!("/proc/{}/comm", pid)).is_ok_and(|comm| comm.trim() == "sleep") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let process = ProcessInfo {
+            pid,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        (child, process)
+    }
+
+    /// Whether `pid` reaches the /proc/[pid]/stat state within a second
+    fn wait_for_state(pid: i32, state: &str) -> bool {
+        (0..100).any(|_| {
+            let current = fs::read_to_string(format!("/proc/{}/stat", pid))
+                .ok()
+                .and_then(|stat| Some(stat[stat.rfind(')')? + 1..].split_whitespace().next()?.to_string()));
+            if current.as_deref() == Some(state) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+            false
+        })
+    }
+
     #[test]
     fn test_get_all_processes() {
         let processes = ProcessMonitor::get_all_processes();
@@ -311,6 +1471,611 @@ mod tests {
         assert!(found_self, "Should find current process");
     }
 
+    #[test]
+    fn test_parse_stat() {
+        let stat = "4242 (Web Content (x)) S 4200 4200 4100 0 -1 4194560";
+        assert_eq!(ProcessMonitor::parse_stat(stat), Some((4200, 4100)));
+        assert_eq!(ProcessMonitor::parse_stat("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(
+            ProcessMonitor::parse_cmdline(b"java\0-jar\0my tool.jar\0"),
+            vec!["java", "-jar", "my tool.jar"]
+        );
+        // Empty arguments are kept so positions stay meaningful
+        assert_eq!(ProcessMonitor::parse_cmdline(b"node\0\0app.js\0"), vec!["node", "", "app.js"]);
+        assert!(ProcessMonitor::parse_cmdline(b"").is_empty());
+    }
+
+    #[test]
+    fn test_collect_kill_set_includes_descendants() {
+        let process = |pid, ppid, session| ProcessInfo {
+            pid,
+            name: "brave".to_string(),
+            exe_path: None,
+            ppid,
+            session,
+            cmdline: Vec::new(),
+        };
+        let processes = vec![
+            process(100, 1, 100),
+            process(101, 100, 100),
+            process(102, 101, 100),
+            process(200, 1, 200),
+        ];
+
+        let mut targets = ProcessMonitor::collect_kill_set(&processes[1], &processes);
+        targets.sort();
+        assert_eq!(targets, vec![101, 102]);
+    }
+
+    #[test]
+    fn test_process_tree_leaves_the_session_leader() {
+        let process = |pid, ppid, session| ProcessInfo {
+            pid,
+            name: "bash".to_string(),
+            exe_path: None,
+            ppid,
+            session,
+            cmdline: Vec::new(),
+        };
+        // A terminal's shell leads its session; the command is one of its children
+        let processes = vec![
+            process(100, 1, 100),
+            process(101, 100, 101),
+            process(102, 101, 101),
+            process(103, 102, 101),
+            process(104, 101, 101),
+        ];
+
+        let mut targets = ProcessMonitor::collect_process_tree(&processes[2], &processes);
+        targets.sort();
+        assert_eq!(targets, vec![102, 103]);
+
+        let mut targets = ProcessMonitor::collect_kill_set(&processes[1], &processes);
+        targets.sort();
+        assert_eq!(targets, vec![101, 102, 103, 104]);
+    }
+
+    #[test]
+    fn test_foreign_app_scope_is_left_alone() {
+        let process = |pid, ppid, session| ProcessInfo {
+            pid,
+            name: "brave".to_string(),
+            exe_path: None,
+            ppid,
+            session,
+            cmdline: Vec::new(),
+        };
+        // A terminal (100) and its shell (101) in the terminal's scope; brave
+        // (102) was started from the shell and inherited the scope
+        let processes = vec![
+            process(100, 1, 100),
+            process(101, 100, 101),
+            process(102, 101, 101),
+            process(103, 102, 101),
+            process(104, 101, 101),
+        ];
+        let terminal = AppScope {
+            name: "app-gnome-org.gnome.Terminal-100.scope".to_string(),
+            members: vec![100, 101, 102, 103, 104],
+        };
+        let no_start_time = |_| None;
+
+        let mut targets = ProcessMonitor::kill_set(&processes[2], &processes, Some(&terminal), no_start_time);
+        targets.sort();
+        assert_eq!(targets, vec![102, 103]);
+
+        // The scope's own app takes it all down
+        let mut targets = ProcessMonitor::kill_set(&processes[0], &processes, Some(&terminal), no_start_time);
+        targets.sort();
+        assert_eq!(targets, vec![100, 101, 102, 103, 104]);
+
+        // Without a PID in the name, the oldest member owns the scope
+        let unnamed = AppScope {
+            name: "app-org.gnome.Terminal-a1b2.scope".to_string(),
+            ..terminal
+        };
+        let start_time = |pid: i32| Some(pid as u64);
+        let mut targets = ProcessMonitor::kill_set(&processes[2], &processes, Some(&unnamed), start_time);
+        targets.sort();
+        assert_eq!(targets, vec![102, 103]);
+        assert_eq!(unnamed.main_pid(start_time), Some(100));
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        let stat = "4242 (Web Content (x)) S 4200 4200 4100 0 -1 4194560 1 0 0 0 5 2 0 0 20 0 8 0 987654 1000 50";
+        assert_eq!(ProcessMonitor::parse_start_time(stat), Some(987654));
+        assert_eq!(ProcessMonitor::parse_start_time("4242 (x) S 1"), None);
+    }
+
+    #[test]
+    fn test_exe_hash_is_cached() {
+        let monitor = ProcessMonitor::new();
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().expect("failed to spawn sleep");
+
+        let hash = monitor.exe_hash(child.id() as i32);
+        let again = monitor.exe_hash(child.id() as i32);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let expected = ProcessMonitor::resolve_binaries("sleep", &MatchRule::default_for("sleep"))
+            .first()
+            .map(|path| ProcessMonitor::hash_file(path).unwrap());
+        assert_eq!(hash, expected);
+        assert_eq!(hash, again);
+        assert_eq!(monitor.hash_cache.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_hash_for_app_follows_updated_binary() {
+        let binary = |name: &str| ProcessMonitor::resolve_binaries(name, &MatchRule::default_for(name)).remove(0);
+        let dir = std::env::temp_dir().join(format!("ficha-hash-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let name = format!("fh{}", std::process::id());
+        let exe = dir.join(&name);
+        fs::copy(binary("sleep"), &exe).unwrap();
+        let mut child = std::process::Command::new(&exe).arg("30").spawn().expect("failed to spawn sleep copy");
+
+        // A package update replaces the file while the old binary keeps running
+        fs::remove_file(&exe).unwrap();
+        fs::copy(binary("true"), &exe).unwrap();
+        let hash = ProcessMonitor::hash_for_app(&name, &MatchRule::default_for(&name));
+        let _ = child.kill();
+        let _ = child.wait();
+        let expected = ProcessMonitor::hash_file(&exe).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(hash, Ok(expected));
+    }
+
+    #[test]
+    fn test_granted_targets_are_left_alone() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        let rule = MatchRule::default_for("sleep");
+        let target = |granted| ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Kill,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        };
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.update_protected_processes(vec![target(true)]);
+        assert!(monitor.check_and_kill_protected(vec![process.clone()]).is_empty());
+
+        monitor.update_protected_processes(vec![target(false)]);
+        let reports = monitor.check_and_kill_protected(vec![process]);
+        let _ = child.kill();
+        let _ = child.wait();
+        assert_eq!(reports.len(), 1);
+    }
+
+    #[test]
+    fn test_script_rules_are_not_matched_by_interpreter_hash() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        // `sleep` stands in for the interpreter another script runs under
+        let rule = MatchRule {
+            kind: MatchKind::Script,
+            pattern: "/opt/app/main.py".to_string(),
+            case_sensitive: false,
+            exclusions: Vec::new(),
+        };
+        assert!(ProcessMonitor::hash_for_app("main.py", &rule).is_err());
+
+        let monitor = ProcessMonitor::new();
+        let interpreter_hash = monitor.exe_hash(process.pid);
+        monitor.set_monitoring(true);
+        monitor.set_audit_mode(true);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "main.py".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Kill,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: interpreter_hash,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        let reports = monitor.check_and_kill_protected(vec![process]);
+        let _ = child.kill();
+        let _ = child.wait();
+        assert!(reports.is_empty());
+    }
+
+    #[test]
+    fn test_audit_mode_reports_without_signalling() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        let rule = MatchRule::default_for("sleep");
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.set_audit_mode(true);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Kill,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        let reports = monitor.check_and_kill_protected(vec![process.clone()]);
+        let again = monitor.check_and_kill_protected(vec![process.clone()]);
+        let alive = ProcessMonitor::is_alive(process.pid);
+
+        // Leaving audit mode enforces what was only reported
+        monitor.set_audit_mode(false);
+        let kills = monitor.check_and_kill_protected(vec![process.clone()]);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].audit.as_ref().map(|p| p.pid), Some(process.pid));
+        assert!(again.is_empty(), "A process is only reported once");
+        assert!(alive, "Audit mode must not signal");
+        assert_eq!(kills.len(), 1);
+        assert!(kills[0].audit.is_none());
+    }
+
+    #[test]
+    fn test_auto_kill_off_alerts_until_reenabled() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        let rule = MatchRule::default_for("sleep");
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.set_auto_kill(false);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Kill,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        let alerts = monitor.check_and_kill_protected(vec![process.clone()]);
+        let again = monitor.check_and_kill_protected(vec![process.clone()]);
+        let alive = ProcessMonitor::is_alive(process.pid);
+
+        // Turning auto-kill back on enforces what was only alerted on
+        monitor.set_auto_kill(true);
+        let kills = monitor.check_and_kill_protected(vec![process.clone()]);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].alert_only && alerts[0].audit.is_none());
+        assert!(again.is_empty(), "A process is only alerted on once");
+        assert!(alive, "Alert-only must not signal");
+        assert_eq!(kills.len(), 1);
+        assert!(!kills[0].alert_only);
+    }
+
+    #[test]
+    fn test_auto_kill_off_still_blocks_elevation() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        // `sleep` stands in for sudo
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sudo".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.set_auto_kill(false);
+        let own_uid = crate::elevation::real_uid(process.pid).unwrap();
+        monitor.set_elevation_guard(Some(ElevationGuard::for_uid(own_uid, &[])));
+
+        let reports = monitor.check_and_kill_protected(vec![process]);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(reports[0].reason, KillReason::Elevation { .. }));
+        // Root Access Prevention doesn't depend on auto-kill
+        assert!(!reports[0].alert_only);
+        assert_eq!(reports[0].pids_terminated, 1);
+    }
+
+    #[test]
+    fn test_terminate_escalates_after_grace_period() {
+        let (mut child, process) = spawn_ignoring_term();
+        let rule = MatchRule::default_for("sleep");
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Terminate,
+            grace_period: Duration::from_millis(200),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        // SIGTERMed trees are only reported once they are resolved
+        assert!(monitor.check_and_kill_protected(vec![process]).is_empty());
+        assert!(monitor.resolve_pending_terminations().is_empty());
+        std::thread::sleep(Duration::from_millis(300));
+        let reports = monitor.resolve_pending_terminations();
+        let status = child.wait().unwrap();
+
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].escalated);
+        assert_eq!(reports[0].strategy, TerminationStrategy::Terminate);
+        assert_eq!(std::os::unix::process::ExitStatusExt::signal(&status), Some(Signal::SIGKILL as i32));
+    }
+
+    #[test]
+    fn test_unlock_cancels_pending_terminations() {
+        let (mut child, process) = spawn_ignoring_term();
+        let rule = MatchRule::default_for("sleep");
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Terminate,
+            grace_period: Duration::from_millis(100),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        monitor.check_and_kill_protected(vec![process.clone()]);
+        let cancelled = monitor.cancel_pending_terminations();
+        std::thread::sleep(Duration::from_millis(200));
+        let reports = monitor.resolve_pending_terminations();
+        let alive = ProcessMonitor::is_alive(process.pid);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(cancelled, 1);
+        assert!(reports.is_empty());
+        assert!(alive, "Nothing may be SIGKILLed after unlocking");
+    }
+
+    #[test]
+    fn test_reused_pid_is_not_escalated() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        let started = ProcessMonitor::start_time(process.pid).unwrap();
+
+        let monitor = ProcessMonitor::new();
+        monitor.pending_terminations.lock().unwrap().push(PendingTermination {
+            // The PID now belongs to a process started after the SIGTERM
+            pids: vec![(process.pid, started + 1)],
+            deadline: Instant::now(),
+            report: KillReport {
+                pid: process.pid,
+                process_name: process.name.clone(),
+                pids_terminated: 1,
+                strategy: TerminationStrategy::Terminate,
+                escalated: false,
+                reason: KillReason::Protected,
+                audit: None,
+                alert_only: false,
+            },
+        });
+
+        let reports = monitor.resolve_pending_terminations();
+        let alive = ProcessMonitor::is_alive(process.pid);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(reports.len(), 1);
+        assert!(!reports[0].escalated);
+        assert!(alive);
+    }
+
+    #[test]
+    fn test_stop_suspends_until_resumed() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        let rule = MatchRule::default_for("sleep");
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Stop,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        let reports = monitor.check_and_kill_protected(vec![process.clone()]);
+        let stopped = wait_for_state(process.pid, "T");
+        // Already stopped, so not signalled again
+        let again = monitor.check_and_kill_protected(vec![process.clone()]);
+        let resumed = monitor.resume_suspended();
+        let running = wait_for_state(process.pid, "S");
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].strategy, TerminationStrategy::Stop);
+        assert!(stopped, "SIGSTOP should stop the process");
+        assert!(again.is_empty());
+        assert_eq!(resumed, 1);
+        assert!(running, "SIGCONT should resume the process");
+    }
+
+    #[test]
+    fn test_resume_group_only_continues_that_entry() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("failed to spawn sleep");
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            name: "sleep".to_string(),
+            exe_path: None,
+            ppid: std::process::id() as i32,
+            session: 0,
+            cmdline: vec!["sleep".to_string(), "30".to_string()],
+        };
+        let rule = MatchRule::default_for("sleep");
+
+        let monitor = ProcessMonitor::new();
+        monitor.set_monitoring(true);
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "sleep".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Stop,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted: false,
+            schedule: None,
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        let reports = monitor.check_and_kill_protected(vec![process]);
+        let stopped = monitor.suspended_count();
+        let other = monitor.resume_group("discord");
+        let resumed = monitor.resume_group("sleep");
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(stopped, 1);
+        assert_eq!(other, 0);
+        assert_eq!(resumed, 1);
+        assert_eq!(monitor.suspended_count(), 0);
+    }
+
+    #[test]
+    fn test_schedule_transitions_are_reported_once() {
+        use crate::schedule::{Schedule, ScheduleWindow};
+        use chrono::{TimeZone, Weekday};
+
+        let rule = MatchRule::default_for("discord");
+        let schedule = Schedule {
+            timezone: Some("UTC".to_string()),
+            windows: vec![ScheduleWindow {
+                days: vec![Weekday::Mon],
+                start: "09:00".to_string(),
+                end: "17:00".to_string(),
+            }],
+            exceptions: Vec::new(),
+        };
+        let monitor = ProcessMonitor::new();
+        monitor.update_protected_processes(vec![ProtectedTarget {
+            process_name: "discord".to_string(),
+            matcher: CompiledRule::new(&rule).unwrap(),
+            rule: rule.clone(),
+            strategy: TerminationStrategy::Kill,
+            grace_period: Duration::from_secs(1),
+            exe_sha256: None,
+            granted: false,
+            schedule: Some(CompiledSchedule::new(&schedule).unwrap()),
+            quota_exhausted: false,
+            audit_only: false,
+        }]);
+
+        // Monday 2026-12-14
+        let morning = Utc.with_ymd_and_hms(2026, 12, 14, 8, 0, 0).unwrap();
+        let working = Utc.with_ymd_and_hms(2026, 12, 14, 10, 0, 0).unwrap();
+        assert!(monitor.schedule_transitions(morning).is_empty());
+        assert!(!monitor.take_sweep_request());
+
+        let transitions = monitor.schedule_transitions(working);
+        assert_eq!(transitions.len(), 1);
+        assert!(transitions[0].blocking);
+        assert!(monitor.take_sweep_request());
+        assert!(monitor.schedule_transitions(working).is_empty());
+
+        // Enforced while unlocked, not outside the window even when locked
+        let target = &monitor.get_protected_processes()[0];
+        assert!(target.is_enforced(false, working));
+        assert!(!target.is_enforced(true, morning));
+    }
+
     #[test]
     fn test_process_monitor_state() {
         let monitor = ProcessMonitor::new();
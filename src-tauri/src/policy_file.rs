@@ -0,0 +1,526 @@
+use crate::allowlist::PolicyMode;
+use crate::matching::{CompiledRule, MatchRule};
+use crate::monitor::TerminationStrategy;
+use crate::schedule::{CompiledSchedule, Schedule};
+use nix::libc;
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, InotifyEvent, WatchDescriptor};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const SYSTEM_POLICY: &str = "/etc/ficha/policy.toml";
+const FILE_NAME: &str = "policy.toml";
+
+/// Policy ids and the keys they go by in `[policies]`
+const POLICY_KEYS: [(&str, &str); 4] = [
+    ("policy_1", "auto_kill"),
+    ("policy_2", "stealth_mode"),
+    ("policy_3", "root_access_prevention"),
+    ("policy_4", "idle_lock"),
+];
+
+/// Declarative policy from `/etc/ficha/policy.toml` and
+/// `~/.config/ficha/policy.toml`, applied to the active profile.
+///
+/// Precedence, highest first: the system file, the user file, then what was
+/// set in the app. Whatever a file declares is written over the database on
+/// every load; what no file declares keeps its in-app value. Apps declared
+/// in a file are managed: they can't be changed in the app and are removed
+/// once no file declares them.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    #[serde(default)]
+    pub settings: FileSettings,
+    #[serde(default)]
+    pub policies: FilePolicies,
+    #[serde(default)]
+    pub apps: Vec<ManagedApp>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileSettings {
+    /// Minutes, 1-10
+    pub idle_timeout: Option<i64>,
+    pub policy_mode: Option<PolicyMode>,
+    pub allowlist_strategy: Option<TerminationStrategy>,
+    pub audit_mode: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilePolicies {
+    pub auto_kill: Option<bool>,
+    pub stealth_mode: Option<bool>,
+    pub root_access_prevention: Option<bool>,
+    pub idle_lock: Option<bool>,
+}
+
+impl FilePolicies {
+    /// Declared states by policy id
+    pub fn states(&self) -> Vec<(&'static str, bool)> {
+        let values = [self.auto_kill, self.stealth_mode, self.root_access_prevention, self.idle_lock];
+        POLICY_KEYS
+            .iter()
+            .zip(values)
+            .filter_map(|((id, _), value)| value.map(|enabled| (*id, enabled)))
+            .collect()
+    }
+
+    /// `[policies]` key of policy `id` when it is set
+    pub fn declared_key(&self, id: &str) -> Option<&'static str> {
+        let declared = self.states().iter().any(|(policy, _)| *policy == id);
+        POLICY_KEYS
+            .iter()
+            .find(|(policy, _)| declared && *policy == id)
+            .map(|(_, key)| *key)
+    }
+}
+
+/// A protected app declared in a policy file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagedApp {
+    pub name: String,
+    pub process_name: String,
+    #[serde(default = "default_icon")]
+    pub icon: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    pub icon_name: Option<String>,
+    /// Defaults to the process name rule used for apps added by name
+    #[serde(rename = "match")]
+    pub match_rule: Option<MatchRule>,
+    #[serde(default = "default_strategy")]
+    pub strategy: TerminationStrategy,
+    #[serde(default = "default_grace_period")]
+    pub grace_period_secs: i64,
+    pub schedule: Option<Schedule>,
+    pub daily_quota_mins: Option<i64>,
+    #[serde(default)]
+    pub audit_only: bool,
+    /// File the app was declared in
+    #[serde(skip)]
+    pub source: String,
+}
+
+fn default_icon() -> String {
+    "📦".to_string()
+}
+
+fn default_category() -> String {
+    "Managed".to_string()
+}
+
+fn default_strategy() -> TerminationStrategy {
+    TerminationStrategy::Kill
+}
+
+fn default_grace_period() -> i64 {
+    5
+}
+
+impl ManagedApp {
+    pub fn match_rule(&self) -> MatchRule {
+        self.match_rule.clone().unwrap_or_else(|| MatchRule::default_for(&self.process_name))
+    }
+}
+
+impl PolicyFile {
+    /// Parse and validate, with every problem found reported on its own line
+    pub fn parse(text: &str, source: &Path) -> Result<Self, String> {
+        let mut policy: PolicyFile = toml::from_str(text)
+            .map_err(|e| format!("{}: {}", source.display(), e.to_string().trim_end()))?;
+
+        let errors = policy.validate();
+        if !errors.is_empty() {
+            return Err(errors
+                .iter()
+                .map(|error| format!("{}: {}", source.display(), error))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+
+        for app in &mut policy.apps {
+            app.source = source.display().to_string();
+        }
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if let Some(minutes) = self.settings.idle_timeout {
+            if !(1..=10).contains(&minutes) {
+                errors.push(format!("settings.idle_timeout: must be between 1 and 10 minutes, got {}", minutes));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for (i, app) in self.apps.iter().enumerate() {
+            let at = format!("apps[{}] ({})", i, app.process_name);
+            if app.name.trim().is_empty() {
+                errors.push(format!("{}: name is empty", at));
+            }
+            if app.process_name.trim().is_empty() {
+                errors.push(format!("apps[{}]: process_name is empty", i));
+            } else if !seen.insert(app.process_name.as_str()) {
+                errors.push(format!("{}: process_name is declared more than once", at));
+            }
+            if let Err(e) = CompiledRule::new(&app.match_rule()) {
+                errors.push(format!("{}: match: {}", at, e));
+            }
+            if !(1..=120).contains(&app.grace_period_secs) {
+                errors.push(format!("{}: grace_period_secs must be between 1 and 120, got {}", at, app.grace_period_secs));
+            }
+            if let Some(Err(e)) = app.schedule.as_ref().map(CompiledSchedule::new) {
+                errors.push(format!("{}: schedule: {}", at, e));
+            }
+            if let Some(minutes) = app.daily_quota_mins {
+                if !(1..=1440).contains(&minutes) {
+                    errors.push(format!("{}: daily_quota_mins must be between 1 and 1440, got {}", at, minutes));
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// `over` wins for every value it declares; its apps replace same-named
+    /// ones
+    pub fn merge(self, over: PolicyFile) -> PolicyFile {
+        let declared: HashSet<String> = over.apps.iter().map(|app| app.process_name.clone()).collect();
+        let mut apps = over.apps;
+        apps.extend(self.apps.into_iter().filter(|app| !declared.contains(&app.process_name)));
+
+        PolicyFile {
+            settings: FileSettings {
+                idle_timeout: over.settings.idle_timeout.or(self.settings.idle_timeout),
+                policy_mode: over.settings.policy_mode.or(self.settings.policy_mode),
+                allowlist_strategy: over.settings.allowlist_strategy.or(self.settings.allowlist_strategy),
+                audit_mode: over.settings.audit_mode.or(self.settings.audit_mode),
+            },
+            policies: FilePolicies {
+                auto_kill: over.policies.auto_kill.or(self.policies.auto_kill),
+                stealth_mode: over.policies.stealth_mode.or(self.policies.stealth_mode),
+                root_access_prevention: over.policies.root_access_prevention.or(self.policies.root_access_prevention),
+                idle_lock: over.policies.idle_lock.or(self.policies.idle_lock),
+            },
+            apps,
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/ficha/policy.toml`, or under `~/.config`
+pub fn user_policy_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("ficha").join(FILE_NAME))
+}
+
+/// Policy files in order of increasing precedence
+pub fn policy_paths() -> Vec<PathBuf> {
+    user_policy_path().into_iter().chain([PathBuf::from(SYSTEM_POLICY)]).collect()
+}
+
+/// Read and parse one policy file; `None` when it doesn't exist
+fn read(path: &Path) -> Result<Option<PolicyFile>, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => PolicyFile::parse(&text, path).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// The user policy file as it is on disk now
+pub fn load_user() -> Result<Option<PolicyFile>, String> {
+    match user_policy_path() {
+        Some(path) => read(&path),
+        None => Ok(None),
+    }
+}
+
+/// Merge the system file over `user`, which is the user file as last
+/// accepted rather than necessarily as it is on disk. Returns the merged
+/// policy and the files it came from; an invalid system file fails the load.
+pub fn load(user: Option<PolicyFile>) -> Result<(PolicyFile, Vec<PathBuf>), String> {
+    let mut merged = PolicyFile::default();
+    let mut sources = Vec::new();
+
+    if let (Some(user), Some(path)) = (user, user_policy_path()) {
+        merged = merged.merge(user);
+        sources.push(path);
+    }
+    let system = PathBuf::from(SYSTEM_POLICY);
+    if let Some(policy) = read(&system)? {
+        merged = merged.merge(policy);
+        sources.push(system);
+    }
+
+    Ok((merged, sources))
+}
+
+/// Watches the directories of the policy files, since editors usually
+/// replace a file rather than write to it, and their parents, so a
+/// directory created or recreated later is picked up too
+pub struct PolicyWatcher {
+    inotify: Inotify,
+    dirs: Vec<WatchedDir>,
+}
+
+struct WatchedDir {
+    path: PathBuf,
+    /// Set while the directory exists and is watched
+    watch: Option<WatchDescriptor>,
+    /// Watch on the parent, for the directory coming (back) into existence
+    parent: Option<WatchDescriptor>,
+}
+
+impl PolicyWatcher {
+    /// Creates the user policy directory so it can be watched; the system
+    /// one is watched once it exists
+    pub fn new() -> Result<Self, String> {
+        Self::watching(policy_paths())
+    }
+
+    fn watching(paths: Vec<PathBuf>) -> Result<Self, String> {
+        let inotify = Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)
+            .map_err(|e| format!("inotify_init: {}", e))?;
+
+        let mut dirs = Vec::new();
+        for path in paths {
+            let Some(dir) = path.parent() else { continue };
+            if path.as_path() != Path::new(SYSTEM_POLICY) {
+                let _ = fs::create_dir_all(dir);
+            }
+            let parent = dir.parent().and_then(|parent| {
+                inotify
+                    .add_watch(parent, Self::parent_flags())
+                    .map_err(|e| eprintln!("Not watching {}: {}", parent.display(), e))
+                    .ok()
+            });
+            let mut watched = WatchedDir { path: dir.to_path_buf(), watch: None, parent };
+            Self::watch_dir(&inotify, &mut watched);
+            dirs.push(watched);
+        }
+
+        let watcher = PolicyWatcher { inotify, dirs };
+        if !watcher.is_watching() {
+            return Err("no policy directory could be watched".to_string());
+        }
+        Ok(watcher)
+    }
+
+    fn dir_flags() -> AddWatchFlags {
+        AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_MOVED_TO
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_DELETE
+    }
+
+    fn parent_flags() -> AddWatchFlags {
+        AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO | AddWatchFlags::IN_ONLYDIR
+    }
+
+    fn watch_dir(inotify: &Inotify, dir: &mut WatchedDir) {
+        if !dir.path.is_dir() {
+            return;
+        }
+        match inotify.add_watch(&dir.path, Self::dir_flags()) {
+            Ok(watch) => dir.watch = Some(watch),
+            Err(e) => eprintln!("Not watching {}: {}", dir.path.display(), e),
+        }
+    }
+
+    fn is_watching(&self) -> bool {
+        self.dirs.iter().any(|dir| dir.watch.is_some() || dir.parent.is_some())
+    }
+
+    /// Block until a policy file changed. Changes arriving within `settle`
+    /// of each other are reported once.
+    pub fn wait(&mut self, settle: Duration) {
+        loop {
+            if self.poll(None) && self.drain() {
+                while self.poll(Some(settle)) {
+                    self.drain();
+                }
+                return;
+            }
+        }
+    }
+
+    fn poll(&self, timeout: Option<Duration>) -> bool {
+        let mut pfd = libc::pollfd {
+            fd: self.inotify.as_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.map(|t| t.as_millis() as libc::c_int).unwrap_or(-1);
+        unsafe { libc::poll(&mut pfd, 1, timeout) > 0 }
+    }
+
+    /// Read pending events and keep the watches in step with directories
+    /// coming and going; whether any event was about a policy file
+    fn drain(&mut self) -> bool {
+        let mut relevant = false;
+        while let Ok(events) = self.inotify.read_events() {
+            if events.is_empty() {
+                break;
+            }
+            for event in events {
+                relevant |= self.handle(&event);
+            }
+        }
+        relevant
+    }
+
+    fn handle(&mut self, event: &InotifyEvent) -> bool {
+        let was_watching = self.is_watching();
+        let mut relevant = false;
+
+        for dir in &mut self.dirs {
+            if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+                // The watched inode is gone, e.g. the directory was deleted
+                if dir.watch == Some(event.wd) {
+                    eprintln!("Policy directory {} removed, waiting for it to return", dir.path.display());
+                    dir.watch = None;
+                    relevant = true;
+                }
+                if dir.parent == Some(event.wd) {
+                    dir.parent = None;
+                }
+            } else if dir.parent == Some(event.wd) && dir.watch.is_none() {
+                if event.name.as_deref() == dir.path.file_name() {
+                    Self::watch_dir(&self.inotify, dir);
+                    // It may have been moved in with a policy file inside
+                    relevant |= dir.watch.is_some();
+                }
+            } else if dir.watch == Some(event.wd) {
+                relevant |= event.name.as_deref().is_some_and(|name| name == FILE_NAME);
+            }
+        }
+
+        if was_watching && !self.is_watching() {
+            eprintln!("Policy file hot reload stopped: no policy directory or parent left to watch");
+        }
+        relevant
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<PolicyFile, String> {
+        PolicyFile::parse(text, Path::new("policy.toml"))
+    }
+
+    #[test]
+    fn test_parse_reports_precise_errors() {
+        let policy = parse(
+            r#"
+            [settings]
+            idle_timeout = 5
+            policy_mode = "blocklist"
+
+            [policies]
+            auto_kill = false
+
+            [[apps]]
+            name = "Steam"
+            process_name = "steam"
+            strategy = "terminate"
+            match = { kind = "exe_basename", pattern = "steam" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(policy.settings.idle_timeout, Some(5));
+        assert_eq!(policy.policies.states(), vec![("policy_1", false)]);
+        assert_eq!(policy.apps[0].strategy, TerminationStrategy::Terminate);
+        assert_eq!(policy.apps[0].source, "policy.toml");
+
+        let error = parse("[policies]\nauto_kil = true\n").unwrap_err();
+        assert!(error.contains("line 2") && error.contains("auto_kil"), "{}", error);
+
+        let error = parse(
+            r#"
+            settings = { idle_timeout = 30 }
+            [[apps]]
+            name = "A"
+            process_name = "a"
+            match = { kind = "cmdline_regex", pattern = "(" }
+            [[apps]]
+            name = "B"
+            process_name = "a"
+            daily_quota_mins = 0
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("settings.idle_timeout"), "{}", error);
+        assert!(error.contains("apps[0] (a): match"), "{}", error);
+        assert!(error.contains("apps[1] (a): process_name is declared more than once"), "{}", error);
+        assert!(error.contains("apps[1] (a): daily_quota_mins"), "{}", error);
+    }
+
+    #[test]
+    fn test_merge_prefers_higher_precedence() {
+        let user = parse(
+            r#"
+            policies = { auto_kill = false, idle_lock = true }
+            [[apps]]
+            name = "Steam"
+            process_name = "steam"
+            [[apps]]
+            name = "Discord"
+            process_name = "discord"
+            "#,
+        )
+        .unwrap();
+        let system = parse(
+            r#"
+            policies = { auto_kill = true }
+            [[apps]]
+            name = "Steam (managed)"
+            process_name = "steam"
+            strategy = "freeze"
+            "#,
+        )
+        .unwrap();
+
+        let merged = user.merge(system);
+        assert_eq!(merged.policies.states(), vec![("policy_1", true), ("policy_4", true)]);
+        assert_eq!(merged.apps.len(), 2);
+        let steam = merged.apps.iter().find(|app| app.process_name == "steam").unwrap();
+        assert_eq!(steam.strategy, TerminationStrategy::Freeze);
+    }
+
+    #[test]
+    fn test_watcher_follows_recreated_directory() {
+        let root = std::env::temp_dir().join(format!("ficha-watch-{}", std::process::id()));
+        let dir = root.join("ficha");
+        let _ = fs::remove_dir_all(&root);
+        let mut watcher = PolicyWatcher::watching(vec![dir.join(FILE_NAME)]).unwrap();
+        assert!(watcher.dirs[0].watch.is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(watcher.drain());
+        assert!(watcher.dirs[0].watch.is_none());
+
+        fs::create_dir(&dir).unwrap();
+        watcher.drain();
+        assert!(watcher.dirs[0].watch.is_some());
+
+        fs::write(dir.join(FILE_NAME), "").unwrap();
+        let changed = watcher.drain();
+        let _ = fs::remove_dir_all(&root);
+        assert!(changed);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::database::Database;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minutes left at which a warning is sent, largest first
+const WARNING_MINS: [i64; 2] = [15, 5];
+/// Longest gap counted between two ticks, so a suspend or a stalled task
+/// isn't billed as running time
+const MAX_TICK_SECS: i64 = 30;
+
+/// Remaining daily budget of one protected app
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaStatus {
+    pub app_id: String,
+    pub app_name: String,
+    pub process_name: String,
+    pub quota_secs: i64,
+    pub used_secs: i64,
+    pub remaining_secs: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuotaEvent {
+    /// `minutes_left` crossed one of the warning thresholds
+    Warning { minutes_left: i64 },
+    Exhausted,
+}
+
+/// Result of one accounting tick
+pub struct QuotaTick {
+    pub events: Vec<(QuotaStatus, QuotaEvent)>,
+    /// The local date changed, so yesterday's exhausted quotas are lifted
+    pub day_changed: bool,
+}
+
+/// Counts how long quota'd apps run while allowed, per local day
+pub struct QuotaTracker {
+    database: Arc<Database>,
+    last_tick: Mutex<Option<(Instant, String)>>,
+}
+
+impl QuotaTracker {
+    pub fn new(database: Arc<Database>) -> Self {
+        QuotaTracker {
+            database,
+            last_tick: Mutex::new(None),
+        }
+    }
+
+    pub fn today() -> String {
+        Local::now().format("%Y-%m-%d").to_string()
+    }
+
+    /// Budget left today for every app with a quota
+    pub fn status(&self) -> Result<Vec<QuotaStatus>, String> {
+        let usage = self.database.get_app_usage(&Self::today()).map_err(|e| e.to_string())?;
+        let apps = self.database.get_protected_apps().map_err(|e| e.to_string())?;
+
+        Ok(apps
+            .into_iter()
+            .filter_map(|app| {
+                let quota_secs = app.daily_quota_mins? * 60;
+                let used_secs = usage.get(&app.id).copied().unwrap_or(0);
+                Some(QuotaStatus {
+                    remaining_secs: (quota_secs - used_secs).max(0),
+                    app_id: app.id,
+                    app_name: app.name,
+                    process_name: app.process_name,
+                    quota_secs,
+                    used_secs,
+                })
+            })
+            .collect())
+    }
+
+    /// Bill the time since the last tick to the quota'd apps in `running`
+    /// (by process name)
+    pub fn tick(&self, running: &HashSet<String>) -> Result<QuotaTick, String> {
+        let now = Instant::now();
+        let today = Self::today();
+
+        let (elapsed, day_changed) = {
+            let mut last = self.last_tick.lock().unwrap();
+            match last.as_mut() {
+                Some((billed_until, day)) => {
+                    let elapsed = (now - *billed_until).as_secs();
+                    // Carry the sub-second remainder over to the next tick
+                    *billed_until += Duration::from_secs(elapsed);
+                    let day_changed = *day != today;
+                    *day = today.clone();
+                    (elapsed as i64, day_changed)
+                }
+                None => {
+                    *last = Some((now, today.clone()));
+                    (0, false)
+                }
+            }
+        };
+
+        let elapsed = elapsed.min(MAX_TICK_SECS);
+        let mut events = Vec::new();
+        if elapsed == 0 || running.is_empty() {
+            return Ok(QuotaTick { events, day_changed });
+        }
+
+        for status in self.status()? {
+            if !running.contains(&status.process_name) || status.remaining_secs == 0 {
+                continue;
+            }
+
+            let used_secs = self
+                .database
+                .add_app_usage(&status.app_id, &today, elapsed)
+                .map_err(|e| e.to_string())?;
+            let before = status.remaining_secs;
+            let status = QuotaStatus {
+                remaining_secs: (status.quota_secs - used_secs).max(0),
+                used_secs,
+                ..status
+            };
+
+            if let Some(event) = crossing(before, status.remaining_secs) {
+                events.push((status, event));
+            }
+        }
+
+        Ok(QuotaTick { events, day_changed })
+    }
+}
+
+/// The most urgent threshold passed when the remaining budget drops from
+/// `before` to `after` seconds
+fn crossing(before: i64, after: i64) -> Option<QuotaEvent> {
+    if before > 0 && after == 0 {
+        return Some(QuotaEvent::Exhausted);
+    }
+    WARNING_MINS
+        .iter()
+        .rev()
+        .find(|mins| before > **mins * 60 && after <= **mins * 60)
+        .map(|mins| QuotaEvent::Warning { minutes_left: *mins })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossing() {
+        assert_eq!(crossing(16 * 60, 15 * 60), Some(QuotaEvent::Warning { minutes_left: 15 }));
+        assert_eq!(crossing(15 * 60, 14 * 60), None);
+        assert_eq!(crossing(5 * 60 + 3, 5 * 60 - 2), Some(QuotaEvent::Warning { minutes_left: 5 }));
+        assert_eq!(crossing(20 * 60, 4 * 60), Some(QuotaEvent::Warning { minutes_left: 5 }));
+        assert_eq!(crossing(20 * 60, 0), Some(QuotaEvent::Exhausted));
+        assert_eq!(crossing(0, 0), None);
+    }
+}
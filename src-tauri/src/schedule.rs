@@ -0,0 +1,182 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// A weekly stretch of time during which the app is blocked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleWindow {
+    pub days: Vec<Weekday>,
+    /// "HH:MM"; an end at or before the start runs past midnight into the
+    /// next day
+    pub start: String,
+    pub end: String,
+}
+
+/// Overrides the weekly windows for one calendar date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleException {
+    pub date: NaiveDate,
+    /// Blocked for the whole day when true, allowed for the whole day otherwise
+    pub blocked: bool,
+}
+
+/// When a protected app is enforced, independently of the shield
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// IANA zone the windows are written in; the system zone when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    pub windows: Vec<ScheduleWindow>,
+    #[serde(default)]
+    pub exceptions: Vec<ScheduleException>,
+}
+
+/// Payload of the `schedule-transition` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduleTransition {
+    pub process_name: String,
+    /// Whether the app entered (true) or left a blocked window
+    pub blocking: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Window {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Window {
+    fn contains(&self, at: NaiveDateTime) -> bool {
+        let (day, time) = (at.weekday(), at.time());
+        if self.start < self.end {
+            self.days.contains(&day) && self.start <= time && time < self.end
+        } else {
+            (self.days.contains(&day) && time >= self.start)
+                || (self.days.contains(&day.pred()) && time < self.end)
+        }
+    }
+}
+
+/// A validated `Schedule`, ready to be evaluated
+#[derive(Debug, Clone)]
+pub struct CompiledSchedule {
+    timezone: Option<Tz>,
+    windows: Vec<Window>,
+    exceptions: Vec<ScheduleException>,
+}
+
+impl CompiledSchedule {
+    pub fn new(schedule: &Schedule) -> Result<Self, String> {
+        let timezone = match schedule.timezone.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(name) => Some(
+                name.parse::<Tz>()
+                    .map_err(|_| format!("Unknown timezone '{}'", name))?,
+            ),
+        };
+
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+        };
+
+        let windows = schedule
+            .windows
+            .iter()
+            .map(|window| {
+                if window.days.is_empty() {
+                    return Err("Schedule window has no days".to_string());
+                }
+                Ok(Window {
+                    days: window.days.clone(),
+                    start: time(&window.start)?,
+                    end: time(&window.end)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if windows.is_empty() && schedule.exceptions.is_empty() {
+            return Err("Schedule needs at least one window or exception".to_string());
+        }
+
+        Ok(CompiledSchedule {
+            timezone,
+            windows,
+            exceptions: schedule.exceptions.clone(),
+        })
+    }
+
+    fn local(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        match self.timezone {
+            Some(tz) => now.with_timezone(&tz).naive_local(),
+            None => now.with_timezone(&chrono::Local).naive_local(),
+        }
+    }
+
+    /// Whether the app should be blocked at `now`
+    pub fn is_blocking(&self, now: DateTime<Utc>) -> bool {
+        let local = self.local(now);
+        if let Some(exception) = self.exceptions.iter().find(|e| e.date == local.date()) {
+            return exception.blocked;
+        }
+        self.windows.iter().any(|window| window.contains(local))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn focus_time() -> Schedule {
+        Schedule {
+            timezone: Some("Europe/Berlin".to_string()),
+            windows: vec![ScheduleWindow {
+                days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+                start: "09:00".to_string(),
+                end: "17:00".to_string(),
+            }],
+            exceptions: vec![ScheduleException {
+                date: NaiveDate::from_ymd_opt(2026, 12, 25).unwrap(),
+                blocked: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_weekday_windows_in_timezone() {
+        let schedule = CompiledSchedule::new(&focus_time()).unwrap();
+        // Wednesday 2026-12-16, 08:30 UTC is 09:30 in Berlin
+        assert!(schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 16, 8, 30, 0).unwrap()));
+        assert!(!schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 16, 16, 0, 0).unwrap()));
+        // Saturday, and a Friday holiday
+        assert!(!schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 19, 10, 0, 0).unwrap()));
+        assert!(!schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 25, 10, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_overnight_window_and_validation() {
+        let schedule = CompiledSchedule::new(&Schedule {
+            timezone: Some("UTC".to_string()),
+            windows: vec![ScheduleWindow {
+                days: vec![Weekday::Fri],
+                start: "22:00".to_string(),
+                end: "06:00".to_string(),
+            }],
+            exceptions: Vec::new(),
+        })
+        .unwrap();
+        assert!(schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 18, 23, 0, 0).unwrap()));
+        assert!(schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 19, 5, 59, 0).unwrap()));
+        assert!(!schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 19, 6, 0, 0).unwrap()));
+        assert!(!schedule.is_blocking(Utc.with_ymd_and_hms(2026, 12, 17, 23, 0, 0).unwrap()));
+
+        let mut bad = focus_time();
+        bad.timezone = Some("Mars/Olympus".to_string());
+        assert!(CompiledSchedule::new(&bad).is_err());
+        bad = focus_time();
+        bad.windows[0].end = "25:00".to_string();
+        assert!(CompiledSchedule::new(&bad).is_err());
+    }
+}
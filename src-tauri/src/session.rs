@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A session ends after this long without a privileged command
+const SESSION_IDLE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Error returned by commands that need an authenticated session, so the
+/// UI can tell a rejected token apart from a failed operation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CommandError {
+    Unauthorized(String),
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Unauthorized(reason) => write!(f, "unauthorized: {}", reason),
+            CommandError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed(message)
+    }
+}
+
+struct Session {
+    token: String,
+    last_used: Instant,
+}
+
+/// The single session minted by a successful `authenticate`. Privileged
+/// commands must present its token; locking or idling ends it.
+pub struct SessionManager {
+    session: Mutex<Option<Session>>,
+    idle_ttl: Duration,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::with_ttl(SESSION_IDLE_TTL)
+    }
+
+    fn with_ttl(idle_ttl: Duration) -> Self {
+        SessionManager {
+            session: Mutex::new(None),
+            idle_ttl,
+        }
+    }
+
+    /// Start a new session, replacing any existing one
+    pub fn issue(&self) -> String {
+        let token = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        *self.session.lock().unwrap() = Some(Session {
+            token: token.clone(),
+            last_used: Instant::now(),
+        });
+        token
+    }
+
+    /// Check `token` against the live session and extend it on success
+    pub fn validate(&self, token: Option<&str>) -> Result<(), CommandError> {
+        let token = token.ok_or_else(|| CommandError::Unauthorized("no session token".to_string()))?;
+        let mut guard = self.session.lock().unwrap();
+
+        let session = match guard.as_mut() {
+            Some(session) => session,
+            None => return Err(CommandError::Unauthorized("no active session".to_string())),
+        };
+
+        if session.last_used.elapsed() >= self.idle_ttl {
+            *guard = None;
+            return Err(CommandError::Unauthorized("session expired".to_string()));
+        }
+
+        if !constant_time_eq(session.token.as_bytes(), token.as_bytes()) {
+            return Err(CommandError::Unauthorized("invalid session token".to_string()));
+        }
+
+        session.last_used = Instant::now();
+        Ok(())
+    }
+
+    /// End the session. Returns whether there was one.
+    pub fn revoke(&self) -> bool {
+        self.session.lock().unwrap().take().is_some()
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_lifecycle() {
+        let sessions = SessionManager::new();
+        assert!(sessions.validate(Some("anything")).is_err());
+
+        let token = sessions.issue();
+        assert_eq!(sessions.validate(Some(&token)), Ok(()));
+        assert!(matches!(sessions.validate(Some("forged")), Err(CommandError::Unauthorized(_))));
+        assert!(matches!(sessions.validate(None), Err(CommandError::Unauthorized(_))));
+
+        assert!(sessions.revoke());
+        assert!(sessions.validate(Some(&token)).is_err());
+    }
+
+    #[test]
+    fn test_session_expires_when_idle() {
+        let sessions = SessionManager::with_ttl(Duration::from_millis(20));
+        let token = sessions.issue();
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(
+            sessions.validate(Some(&token)),
+            Err(CommandError::Unauthorized("session expired".to_string()))
+        );
+    }
+}
@@ -1,13 +1,29 @@
-use crate::database::Database;
+use crate::allowlist::{Allowlist, AllowlistConfig, PolicyMode};
+use crate::database::{Database, Profile, ProtectedApp, SecurityPolicy, UnlockGrant};
+use crate::elevation::ElevationGuard;
 use crate::idle::IdleTracker;
-use crate::monitor::ProcessMonitor;
+use crate::matching::CompiledRule;
+use crate::monitor::{ProcessMonitor, ProtectedTarget, TerminationStrategy};
+use crate::policy_file::PolicyFile;
+use crate::conversation::PromptBroker;
+use crate::quota::QuotaTracker;
+use crate::schedule::CompiledSchedule;
+use crate::session::SessionManager;
+use crate::stealth::StealthMode;
+use crate::throttle::AuthThrottle;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum ShieldStatus {
     LOCKED,
     ACTIVE,
     THREAT_DETECTED,
+    /// Locked, with protected apps stopped or frozen until the next unlock
+    FROZEN,
 }
 
 pub struct AppState {
@@ -15,15 +31,34 @@ pub struct AppState {
     pub monitor: Arc<ProcessMonitor>,
     pub shield_status: Arc<Mutex<ShieldStatus>>,
     pub idle_tracker: Arc<IdleTracker>,
+    pub sessions: Arc<SessionManager>,
+    pub auth_throttle: Arc<AuthThrottle>,
+    pub pam_prompts: Arc<PromptBroker>,
+    pub quotas: Arc<QuotaTracker>,
+    /// Last policy file content applied, re-applied on profile switches
+    pub managed_policy: Arc<Mutex<PolicyFile>>,
+    /// The user policy file as last applied. The user can edit it without
+    /// authenticating, so changes made while locked wait for the next unlock.
+    pub user_policy: Arc<Mutex<Option<PolicyFile>>>,
+    /// Set while a fanotify exec guard thread is running
+    pub exec_guard_running: Arc<AtomicBool>,
 }
 
 impl AppState {
     pub fn new(database: Database, monitor: ProcessMonitor, idle_tracker: IdleTracker) -> Self {
+        let database = Arc::new(database);
         AppState {
-            database: Arc::new(database),
+            auth_throttle: Arc::new(AuthThrottle::new(database.clone())),
+            quotas: Arc::new(QuotaTracker::new(database.clone())),
+            database,
             monitor: Arc::new(monitor),
             shield_status: Arc::new(Mutex::new(ShieldStatus::LOCKED)),
             idle_tracker: Arc::new(idle_tracker),
+            sessions: Arc::new(SessionManager::new()),
+            pam_prompts: Arc::new(PromptBroker::new()),
+            managed_policy: Arc::new(Mutex::new(PolicyFile::default())),
+            user_policy: Arc::new(Mutex::new(None)),
+            exec_guard_running: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -36,15 +71,31 @@ impl AppState {
         *shield = status;
     }
 
-    pub fn activate_shield(&self) {
+    /// Returns the number of suspended processes that were resumed
+    pub fn activate_shield(&self) -> usize {
         self.set_shield_status(ShieldStatus::ACTIVE);
         self.monitor.set_monitoring(false);
+        self.monitor.cancel_pending_terminations();
+        let resumed = self.monitor.resume_suspended();
         println!("Shield activated - monitoring disabled");
+        resumed
+    }
+
+    /// Status to show while locked
+    pub fn locked_status(&self) -> ShieldStatus {
+        if self.monitor.suspended_count() > 0 {
+            ShieldStatus::FROZEN
+        } else {
+            ShieldStatus::LOCKED
+        }
     }
 
     pub fn lock_shield(&self) {
         self.set_shield_status(ShieldStatus::LOCKED);
         self.monitor.set_monitoring(true);
+        self.monitor.clear_spared();
+        // Unlocking again needs a fresh authentication
+        self.sessions.revoke();
         println!("Shield locked - monitoring enabled");
     }
 
@@ -53,15 +104,263 @@ impl AppState {
         self.set_shield_status(ShieldStatus::THREAT_DETECTED);
     }
 
+    /// Drop grants that have run out and put their apps back under
+    /// enforcement, sweeping running instances of those set to be killed.
+    /// Returns the expired grants.
+    pub fn expire_unlock_grants(&self) -> Result<Vec<UnlockGrant>, String> {
+        let now = Utc::now();
+        let expired: Vec<UnlockGrant> = self.database.get_unlock_grants()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|grant| {
+                DateTime::parse_from_rfc3339(&grant.expires_at)
+                    .map(|until| until.with_timezone(&Utc) <= now)
+                    .unwrap_or(true)
+            })
+            .collect();
+
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+
+        for grant in &expired {
+            self.database.remove_unlock_grant(&grant.app_id)
+                .map_err(|e| e.to_string())?;
+        }
+        for grant in expired.iter().filter(|grant| !grant.kill_on_expiry) {
+            self.monitor.spare_running(&grant.process_name);
+        }
+        self.update_protected_processes()?;
+
+        if expired.iter().any(|grant| grant.kill_on_expiry) {
+            self.monitor.request_sweep();
+        }
+        Ok(expired)
+    }
+
     pub fn update_protected_processes(&self) -> Result<(), String> {
         let apps = self.database.get_protected_apps()
             .map_err(|e| e.to_string())?;
+        // Grants stay in force until `expire_unlock_grants` removes them
+        let granted: HashSet<String> = self.database.get_unlock_grants()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|grant| grant.app_id)
+            .collect();
+        let usage = self.database.get_app_usage(&QuotaTracker::today())
+            .map_err(|e| e.to_string())?;
 
-        let process_names: Vec<String> = apps.iter()
-            .map(|app| app.process_name.clone())
+        let targets: Vec<ProtectedTarget> = apps.iter()
+            .filter_map(|app| match Self::compile_target(app) {
+                Ok((matcher, schedule)) => Some(ProtectedTarget {
+                    process_name: app.process_name.clone(),
+                    rule: app.match_rule.clone(),
+                    matcher,
+                    strategy: app.termination_strategy,
+                    grace_period: Duration::from_secs(app.grace_period_secs.max(0) as u64),
+                    exe_sha256: app.exe_sha256.clone(),
+                    granted: granted.contains(&app.id),
+                    schedule,
+                    quota_exhausted: app.daily_quota_mins.is_some_and(|mins| {
+                        usage.get(&app.id).copied().unwrap_or(0) >= mins * 60
+                    }),
+                    audit_only: app.audit_only,
+                }),
+                Err(e) => {
+                    eprintln!("Skipping {}: {}", app.name, e);
+                    None
+                }
+            })
             .collect();
 
-        self.monitor.update_protected_processes(process_names);
+        self.monitor.update_protected_processes(targets);
         Ok(())
     }
+
+    /// Reapply daily quotas after usage, the day or a limit changed: sweep
+    /// entries that just ran out, resume the ones that no longer have
+    pub fn refresh_quotas(&self) -> Result<(), String> {
+        let exhausted_before: HashSet<String> = self.monitor.get_protected_processes()
+            .into_iter()
+            .filter(|target| target.quota_exhausted)
+            .map(|target| target.process_name)
+            .collect();
+        self.update_protected_processes()?;
+
+        let locked = self.monitor.is_monitoring();
+        let now = Utc::now();
+        for target in self.monitor.get_protected_processes() {
+            let was_exhausted = exhausted_before.contains(&target.process_name);
+            if target.quota_exhausted && !was_exhausted {
+                self.monitor.request_sweep();
+            } else if was_exhausted && !target.is_enforced(locked, now) {
+                self.monitor.resume_group(&target.process_name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn allowlist_config(&self) -> Result<AllowlistConfig, String> {
+        let setting = |key: &str| self.database.get_setting(key).map_err(|e| e.to_string());
+
+        Ok(AllowlistConfig {
+            mode: setting("policy_mode")?
+                .and_then(|value| PolicyMode::parse(&value))
+                .unwrap_or(PolicyMode::Blocklist),
+            learning_until: setting("allowlist_learning_until")?,
+            strategy: setting("allowlist_strategy")?
+                .and_then(|value| TerminationStrategy::parse(&value))
+                .unwrap_or(TerminationStrategy::Kill),
+            entries: self.database.get_allowed_executables().map_err(|e| e.to_string())?,
+            safe_processes: crate::allowlist::safe_processes(),
+        })
+    }
+
+    /// Hand allowlist enforcement to the monitor when allowlist mode is on
+    /// and learning has finished
+    pub fn update_allowlist(&self) -> Result<(), String> {
+        let config = self.allowlist_config()?;
+        let allowlist = (config.mode == PolicyMode::Allowlist && config.learning_until.is_none())
+            .then(|| Allowlist::new(&config.entries, config.strategy, Duration::from_secs(5)));
+        self.monitor.set_allowlist(allowlist);
+        Ok(())
+    }
+
+    /// Enforce Root Access Prevention (`policy_3`) when it is enabled
+    pub fn update_elevation_guard(&self) -> Result<(), String> {
+        let guard = if self.database.is_policy_enabled("policy_3").map_err(|e| e.to_string())? {
+            let exemptions = self.database.get_elevation_exemptions().map_err(|e| e.to_string())?;
+            Some(ElevationGuard::new(&exemptions))
+        } else {
+            None
+        };
+        self.monitor.set_elevation_guard(guard);
+        Ok(())
+    }
+
+    /// Put a policy's new state into effect
+    pub fn apply_policy(&self, id: &str, enabled: bool) -> Result<(), String> {
+        match id {
+            "policy_1" => {
+                // Auto-kill unauthorized apps, alert-only when off
+                self.monitor.set_auto_kill(enabled);
+            },
+            "policy_2" => {
+                // Stealth Mode
+                if enabled {
+                    StealthMode::enable()?;
+                } else {
+                    StealthMode::disable()?;
+                }
+            },
+            "policy_3" => {
+                // Root Access Prevention
+                self.update_elevation_guard()?;
+            },
+            "policy_4" => {
+                // Session Lock on Idle
+                self.idle_tracker.set_enabled(enabled);
+                if enabled {
+                    self.idle_tracker.reset();
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Make `id` the active profile: the monitor gets its watch list in one
+    /// swap, then policies that differ and the idle timeout are applied
+    pub fn switch_profile(&self, id: &str) -> Result<Profile, String> {
+        let before = self.database.get_security_policies().map_err(|e| e.to_string())?;
+        self.database.set_active_profile(id).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => "Profile not found".to_string(),
+            e => e.to_string(),
+        })?;
+
+        // Policy files apply to whichever profile is active
+        let managed = self.managed_policy.lock().unwrap().clone();
+        self.write_policy_file(&managed)?;
+        self.reload_from_database(&before)
+    }
+
+    /// Merge a loaded policy file into the database and put it into effect
+    pub fn apply_policy_file(&self, policy: PolicyFile) -> Result<(), String> {
+        let before = self.database.get_security_policies().map_err(|e| e.to_string())?;
+        self.write_policy_file(&policy)?;
+        *self.managed_policy.lock().unwrap() = policy;
+        self.reload_from_database(&before)?;
+        Ok(())
+    }
+
+    fn write_policy_file(&self, policy: &PolicyFile) -> Result<(), String> {
+        let db = &self.database;
+        db.sync_managed_apps(&policy.apps).map_err(|e| e.to_string())?;
+        for (id, enabled) in policy.policies.states() {
+            db.set_policy_enabled(id, enabled).map_err(|e| e.to_string())?;
+        }
+
+        let settings = &policy.settings;
+        if let Some(minutes) = settings.idle_timeout {
+            db.set_idle_timeout(minutes).map_err(|e| e.to_string())?;
+        }
+        if let Some(mode) = settings.policy_mode {
+            // Only a change of mode ends a learning period
+            if self.allowlist_config()?.mode != mode {
+                db.set_setting("policy_mode", mode.as_str()).map_err(|e| e.to_string())?;
+                db.delete_setting("allowlist_learning_until").map_err(|e| e.to_string())?;
+            }
+        }
+        if let Some(strategy) = settings.allowlist_strategy {
+            db.set_setting("allowlist_strategy", strategy.as_str()).map_err(|e| e.to_string())?;
+        }
+        if let Some(enabled) = settings.audit_mode {
+            db.set_setting("audit_mode", if enabled { "true" } else { "false" }).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Bring the monitor and idle tracker in line with the database, applying
+    /// policies whose state differs from `before`
+    fn reload_from_database(&self, before: &[SecurityPolicy]) -> Result<Profile, String> {
+        let profile = self.database.get_active_profile().map_err(|e| e.to_string())?;
+
+        let audited_before: HashSet<String> = self.monitor.get_protected_processes()
+            .into_iter()
+            .filter(|target| target.audit_only)
+            .map(|target| target.process_name)
+            .collect();
+        self.update_protected_processes()?;
+        // Apps leaving audit-only are enforced on their running instances too
+        if self.monitor.get_protected_processes()
+            .iter()
+            .any(|target| !target.audit_only && audited_before.contains(&target.process_name))
+        {
+            self.monitor.forget_reported();
+        }
+        self.update_allowlist()?;
+        self.idle_tracker.set_timeout(profile.idle_timeout_mins);
+
+        let audit_mode = self.database.get_bool_setting("audit_mode", false).map_err(|e| e.to_string())?;
+        if self.monitor.is_audit_mode() != audit_mode {
+            self.monitor.set_audit_mode(audit_mode);
+        }
+
+        for policy in self.database.get_security_policies().map_err(|e| e.to_string())? {
+            let changed = before
+                .iter()
+                .any(|old| old.id == policy.id && old.enabled != policy.enabled);
+            if changed {
+                self.apply_policy(&policy.id, policy.enabled)?;
+            }
+        }
+
+        Ok(profile)
+    }
+
+    fn compile_target(app: &ProtectedApp) -> Result<(CompiledRule, Option<CompiledSchedule>), String> {
+        let matcher = CompiledRule::new(&app.match_rule)?;
+        let schedule = app.schedule.as_ref().map(CompiledSchedule::new).transpose()?;
+        Ok((matcher, schedule))
+    }
 }
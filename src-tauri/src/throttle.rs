@@ -0,0 +1,135 @@
+use crate::database::Database;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+/// Failures allowed before any backoff applies
+const FREE_ATTEMPTS: i64 = 3;
+/// Wait after the first throttled failure, doubled for each one after it
+const BASE_BACKOFF_SECS: i64 = 5;
+const MAX_BACKOFF_SECS: i64 = 5 * 60;
+/// Failures after which every further failure locks unlocking for
+/// `LOCKOUT_SECS`
+const LOCKOUT_THRESHOLD: i64 = 10;
+const LOCKOUT_SECS: i64 = 30 * 60;
+
+/// Payload of the `auth-throttled` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThrottleStatus {
+    pub remaining_secs: i64,
+    pub failed_attempts: i64,
+    pub locked_out: bool,
+}
+
+pub enum AuthOutcome {
+    Success,
+    Failed {
+        failed_attempts: i64,
+        /// Set when this failure starts a wait before the next attempt
+        throttle: Option<ThrottleStatus>,
+    },
+    /// Rejected without checking the password
+    Throttled(ThrottleStatus),
+}
+
+/// Backoff and lockout for unlock attempts, persisted in the database so a
+/// restart doesn't reset the counter
+pub struct AuthThrottle {
+    database: Arc<Database>,
+    // Serialises attempts so parallel calls can't all slip past the check
+    attempt: Mutex<()>,
+}
+
+impl AuthThrottle {
+    pub fn new(database: Arc<Database>) -> Self {
+        AuthThrottle {
+            database,
+            attempt: Mutex::new(()),
+        }
+    }
+
+    /// Seconds to wait after `failures` consecutive failures, counted from
+    /// the latest one
+    fn wait_secs(failures: i64) -> i64 {
+        if failures >= LOCKOUT_THRESHOLD {
+            LOCKOUT_SECS
+        } else if failures > FREE_ATTEMPTS {
+            let doublings = (failures - FREE_ATTEMPTS - 1).min(16) as u32;
+            (BASE_BACKOFF_SECS << doublings).min(MAX_BACKOFF_SECS)
+        } else {
+            0
+        }
+    }
+
+    fn status(failures: i64, last_failure: Option<&str>, now: DateTime<Utc>) -> Option<ThrottleStatus> {
+        let wait = Self::wait_secs(failures);
+        if wait == 0 {
+            return None;
+        }
+
+        let last = DateTime::parse_from_rfc3339(last_failure?).ok()?.with_timezone(&Utc);
+        let remaining = wait - (now - last).num_seconds();
+        (remaining > 0).then_some(ThrottleStatus {
+            remaining_secs: remaining,
+            failed_attempts: failures,
+            locked_out: failures >= LOCKOUT_THRESHOLD,
+        })
+    }
+
+    /// Current wait before another attempt is accepted, if any
+    pub fn current(&self) -> Result<Option<ThrottleStatus>, String> {
+        let (failures, last) = self.database.get_auth_failures().map_err(|e| e.to_string())?;
+        Ok(Self::status(failures, last.as_deref(), Utc::now()))
+    }
+
+    /// Run `check` unless attempts are throttled, and record the result
+    pub fn attempt<F>(&self, check: F) -> Result<AuthOutcome, String>
+    where
+        F: FnOnce() -> Result<bool, String>,
+    {
+        let _serial = self.attempt.lock().unwrap();
+
+        if let Some(status) = self.current()? {
+            return Ok(AuthOutcome::Throttled(status));
+        }
+
+        let success = check()?;
+        self.database.record_auth_attempt(success).map_err(|e| e.to_string())?;
+
+        if success {
+            return Ok(AuthOutcome::Success);
+        }
+
+        let (failed_attempts, _) = self.database.get_auth_failures().map_err(|e| e.to_string())?;
+        Ok(AuthOutcome::Failed {
+            failed_attempts,
+            throttle: self.current()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_backoff_schedule() {
+        let waits: Vec<i64> = (1..=11).map(AuthThrottle::wait_secs).collect();
+        assert_eq!(waits, vec![0, 0, 0, 5, 10, 20, 40, 80, 160, LOCKOUT_SECS, LOCKOUT_SECS]);
+    }
+
+    #[test]
+    fn test_status_counts_down_from_last_failure() {
+        let now = Utc::now();
+        let last = (now - Duration::seconds(3)).to_rfc3339();
+
+        let status = AuthThrottle::status(5, Some(&last), now).unwrap();
+        assert_eq!(status.remaining_secs, 7);
+        assert!(!status.locked_out);
+
+        assert_eq!(AuthThrottle::status(4, Some(&last), now + Duration::seconds(2)), None);
+        assert!(AuthThrottle::status(LOCKOUT_THRESHOLD, Some(&last), now).unwrap().locked_out);
+        assert_eq!(AuthThrottle::status(2, Some(&last), now), None);
+    }
+}
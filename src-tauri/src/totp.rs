@@ -0,0 +1,208 @@
+use crate::database::Database;
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const SECRET_SETTING: &str = "totp_secret";
+const PENDING_SECRET_SETTING: &str = "totp_pending_secret";
+/// Time step of the last accepted code, so a code can't be replayed
+const LAST_STEP_SETTING: &str = "totp_last_step";
+
+const ISSUER: &str = "Ficha";
+const STEP_SECS: u64 = 30;
+/// Steps either side of now that are still accepted, for clock drift
+const SKEW_STEPS: u64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// What the UI needs to add the account to an authenticator app
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    /// The URI as an SVG QR code
+    pub qr_svg: String,
+}
+
+/// How a second factor was accepted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecondFactor {
+    Totp,
+    RecoveryCode,
+}
+
+/// RFC 6238 codes (SHA-1, 6 digits, 30s) as a second unlock factor, with
+/// one-time recovery codes for a lost device
+pub struct TotpManager;
+
+impl TotpManager {
+    fn build(secret_base32: &str, account: &str) -> Result<TOTP, String> {
+        let bytes = Secret::Encoded(secret_base32.to_string())
+            .to_bytes()
+            .map_err(|e| format!("Invalid TOTP secret: {:?}", e))?;
+        TOTP::new(
+            Algorithm::SHA1,
+            6,
+            0,
+            STEP_SECS,
+            bytes,
+            Some(ISSUER.to_string()),
+            account.replace(':', "_"),
+        )
+        .map_err(|e| format!("Invalid TOTP parameters: {}", e))
+    }
+
+    pub fn is_enabled(database: &Database) -> Result<bool, String> {
+        Ok(database.get_setting(SECRET_SETTING).map_err(|e| e.to_string())?.is_some())
+    }
+
+    pub fn recovery_codes_left(database: &Database) -> Result<i64, String> {
+        database.count_recovery_codes().map_err(|e| e.to_string())
+    }
+
+    /// Generate a new secret and keep it pending until a code confirms it
+    pub fn begin_enrollment(database: &Database, account: &str) -> Result<TotpEnrollment, String> {
+        let secret = match Secret::generate_secret().to_encoded() {
+            Secret::Encoded(secret) => secret,
+            Secret::Raw(_) => return Err("Failed to encode TOTP secret".to_string()),
+        };
+        let totp = Self::build(&secret, account)?;
+        let otpauth_uri = totp.get_url();
+
+        let qr_svg = qrcode::QrCode::new(otpauth_uri.as_bytes())
+            .map_err(|e| format!("Failed to render QR code: {}", e))?
+            .render::<qrcode::render::svg::Color>()
+            .min_dimensions(200, 200)
+            .build();
+
+        database
+            .set_setting(PENDING_SECRET_SETTING, &secret)
+            .map_err(|e| e.to_string())?;
+
+        Ok(TotpEnrollment { secret, otpauth_uri, qr_svg })
+    }
+
+    /// Activate the pending secret once `code` proves the authenticator has
+    /// it. Returns fresh recovery codes, the only time they're shown.
+    pub fn confirm_enrollment(database: &Database, account: &str, code: &str) -> Result<Vec<String>, String> {
+        let secret = database
+            .get_setting(PENDING_SECRET_SETTING)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "No TOTP enrollment in progress".to_string())?;
+
+        let step = Self::matching_step(&Self::build(&secret, account)?, code)
+            .ok_or_else(|| "Invalid verification code".to_string())?;
+
+        database.set_setting(SECRET_SETTING, &secret).map_err(|e| e.to_string())?;
+        database.set_setting(LAST_STEP_SETTING, &step.to_string()).map_err(|e| e.to_string())?;
+        database.delete_setting(PENDING_SECRET_SETTING).map_err(|e| e.to_string())?;
+
+        Self::regenerate_recovery_codes(database)
+    }
+
+    pub fn disable(database: &Database) -> Result<(), String> {
+        for key in [SECRET_SETTING, PENDING_SECRET_SETTING, LAST_STEP_SETTING] {
+            database.delete_setting(key).map_err(|e| e.to_string())?;
+        }
+        database.replace_recovery_codes(&[]).map_err(|e| e.to_string())
+    }
+
+    /// Replace all recovery codes, returning the new ones in plain text
+    pub fn regenerate_recovery_codes(database: &Database) -> Result<Vec<String>, String> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+        let hashes: Vec<String> = codes.iter().map(|code| hash_recovery_code(code)).collect();
+        database.replace_recovery_codes(&hashes).map_err(|e| e.to_string())?;
+        Ok(codes)
+    }
+
+    /// Check a TOTP code, or failing that a recovery code, which is used up.
+    /// `None` when neither matches.
+    pub fn verify(database: &Database, account: &str, code: &str) -> Result<Option<SecondFactor>, String> {
+        let secret = match database.get_setting(SECRET_SETTING).map_err(|e| e.to_string())? {
+            Some(secret) => secret,
+            None => return Err("TOTP is not enabled".to_string()),
+        };
+
+        if let Some(step) = Self::matching_step(&Self::build(&secret, account)?, code) {
+            let last_step = database
+                .get_int_setting(LAST_STEP_SETTING, 0)
+                .map_err(|e| e.to_string())?;
+            if step as i64 <= last_step {
+                return Ok(None);
+            }
+            database
+                .set_setting(LAST_STEP_SETTING, &step.to_string())
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(SecondFactor::Totp));
+        }
+
+        let consumed = database
+            .consume_recovery_code(&hash_recovery_code(code))
+            .map_err(|e| e.to_string())?;
+        Ok(consumed.then_some(SecondFactor::RecoveryCode))
+    }
+
+    /// The time step `code` is valid for, within the allowed skew
+    fn matching_step(totp: &TOTP, code: &str) -> Option<u64> {
+        let code = code.trim();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let current = now / STEP_SECS;
+        (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+            .find(|step| totp.check(code, step * STEP_SECS))
+    }
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 10];
+    OsRng.fill_bytes(&mut bytes);
+    let chars: String = bytes
+        .iter()
+        .map(|b| RECOVERY_ALPHABET[*b as usize % RECOVERY_ALPHABET.len()] as char)
+        .collect();
+    format!("{}-{}", &chars[..5], &chars[5..])
+}
+
+/// Codes are compared case- and separator-insensitively
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_step_accepts_skew() {
+        let totp = TotpManager::build("JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP", "alice").unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let current = now / STEP_SECS;
+
+        let code = totp.generate(now);
+        assert_eq!(TotpManager::matching_step(&totp, &code), Some(current));
+
+        let previous = totp.generate(now - STEP_SECS);
+        assert_eq!(TotpManager::matching_step(&totp, &previous), Some(current - 1));
+
+        let stale = totp.generate(now - 5 * STEP_SECS);
+        if stale != code && stale != previous && stale != totp.generate(now + STEP_SECS) {
+            assert_eq!(TotpManager::matching_step(&totp, &stale), None);
+        }
+        assert!(totp.get_url().starts_with("otpauth://totp/Ficha:alice?"));
+    }
+
+    #[test]
+    fn test_recovery_code_format() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(&code[5..6], "-");
+        assert_eq!(hash_recovery_code(&code), hash_recovery_code(&code.to_uppercase().replace('-', " ")));
+        assert_ne!(hash_recovery_code(&code), hash_recovery_code(&generate_recovery_code()));
+    }
+}
@@ -1,42 +1,102 @@
 
 import React, { useState, useEffect } from 'react';
-import LockScreen from './components/LockScreen';
+import LockScreen, { AppUnlockRequest } from './components/LockScreen';
 import Dashboard from './components/Dashboard';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { invokePrivileged, setSessionToken, SESSION_EXPIRED_EVENT } from './session';
+import { AuthConfig, ThrottleStatus, UnlockGrant } from './types';
 
 const App: React.FC = () => {
   const [isUnlocked, setIsUnlocked] = useState(false);
   const [authError, setAuthError] = useState(false);
   const [isAuthenticating, setIsAuthenticating] = useState(false);
+  const [throttledUntil, setThrottledUntil] = useState<number | null>(null);
+  const [lockedOut, setLockedOut] = useState(false);
+  const [authConfig, setAuthConfig] = useState<AuthConfig | null>(null);
+  const [setupError, setSetupError] = useState<string | null>(null);
+  const [appUnlockNotice, setAppUnlockNotice] = useState<string | null>(null);
 
-  const handleUnlock = async (password: string) => {
+  const applyThrottle = (status: ThrottleStatus | null) => {
+    setThrottledUntil(status ? Date.now() + status.remaining_secs * 1000 : null);
+    setLockedOut(status?.locked_out ?? false);
+  };
+
+  const handleUnlock = async (password: string, totpCode?: string) => {
     setIsAuthenticating(true);
     setAuthError(false);
 
     try {
-      // Authenticate against system PAM
-      const authenticated = await invoke<boolean>('authenticate', { password });
+      // Check against PAM or the vault passphrase; success mints a session token
+      const token = await invoke<string | null>('authenticate', { password, totpCode: totpCode ?? null });
 
-      if (authenticated) {
-        setIsUnlocked(true);
-        setAuthError(false);
+      if (token) {
+        applyThrottle(null);
+        await completeUnlock(token);
+      } else {
+        setAuthError(true);
+      }
+    } catch (error) {
+      console.error('Authentication error:', error);
+      setAuthError(true);
+    } finally {
+      setIsAuthenticating(false);
+    }
+  };
+
+  // Temporary unlock of one app; the shield stays locked for the rest
+  const handleUnlockApp = async (password: string, totpCode: string | undefined, request: AppUnlockRequest) => {
+    setIsAuthenticating(true);
+    setAuthError(false);
+    setAppUnlockNotice(null);
 
-        // Activate shield (disables monitoring while user session is active)
-        await invoke('activate_shield');
+    try {
+      const grant = await invoke<UnlockGrant | null>('authenticate_app', {
+        password,
+        totpCode: totpCode ?? null,
+        ...request,
+      });
 
-        // Persist state during session (cleared on refresh for security)
-        sessionStorage.setItem('ficha_auth', 'true');
+      if (grant) {
+        applyThrottle(null);
+        setAppUnlockNotice(`${grant.app_name} unlocked for ${request.minutes} min`);
       } else {
         setAuthError(true);
       }
     } catch (error) {
-      console.error('Authentication error:', error);
+      console.error('App unlock error:', error);
       setAuthError(true);
     } finally {
       setIsAuthenticating(false);
     }
   };
 
+  const completeUnlock = async (token: string) => {
+    setSessionToken(token);
+    setIsUnlocked(true);
+    setAuthError(false);
+
+    // Activate shield (disables monitoring while user session is active)
+    await invokePrivileged('activate_shield');
+  };
+
+  // First run with the passphrase method: create it, which also unlocks
+  const handleSetup = async (passphrase: string) => {
+    setIsAuthenticating(true);
+    setSetupError(null);
+
+    try {
+      const token = await invoke<string>('setup_passphrase', { passphrase });
+      setAuthConfig(config => config && { ...config, passphrase_set: true });
+      await completeUnlock(token);
+    } catch (error) {
+      console.error('Passphrase setup error:', error);
+      setSetupError(String(error));
+    } finally {
+      setIsAuthenticating(false);
+    }
+  };
+
   const handleLock = async () => {
     try {
       // Lock shield (re-enables monitoring)
@@ -45,17 +105,36 @@ const App: React.FC = () => {
       console.error('Error locking shield:', error);
     }
 
+    setSessionToken(null);
     setIsUnlocked(false);
-    sessionStorage.removeItem('ficha_auth');
+    // The unlock method may have changed while unlocked
+    invoke<AuthConfig>('get_auth_config').then(setAuthConfig).catch(console.error);
   };
 
   useEffect(() => {
-    const auth = sessionStorage.getItem('ficha_auth');
-    if (auth === 'true') {
-      setIsUnlocked(true);
-      // Re-activate shield on reload
-      invoke('activate_shield').catch(console.error);
-    }
+    // The backend ended the session (lock, idle or expiry)
+    const onExpired = () => {
+      setSessionToken(null);
+      setIsUnlocked(false);
+    };
+
+    window.addEventListener(SESSION_EXPIRED_EVENT, onExpired);
+    const unlisten = listen('session-expired', onExpired);
+
+    // Too many failed unlocks: the backend refuses attempts until the wait ends
+    const unlistenThrottle = listen<ThrottleStatus>('auth-throttled', event => applyThrottle(event.payload));
+    invoke<AuthConfig>('get_auth_config')
+      .then(setAuthConfig)
+      .catch(error => console.error('Failed to load auth config:', error));
+    invoke<ThrottleStatus | null>('get_auth_throttle')
+      .then(applyThrottle)
+      .catch(error => console.error('Failed to load unlock throttle:', error));
+
+    return () => {
+      window.removeEventListener(SESSION_EXPIRED_EVENT, onExpired);
+      unlisten.then(fn => fn());
+      unlistenThrottle.then(fn => fn());
+    };
   }, []);
 
   return (
@@ -65,8 +144,17 @@ const App: React.FC = () => {
       ) : (
         <LockScreen
           onUnlock={handleUnlock}
+          onUnlockApp={handleUnlockApp}
+          appUnlockNotice={appUnlockNotice}
           isError={authError}
           isLoading={isAuthenticating}
+          throttledUntil={throttledUntil}
+          lockedOut={lockedOut}
+          authMethod={authConfig?.method ?? 'system'}
+          totpRequired={authConfig?.totp_enabled ?? false}
+          needsSetup={authConfig?.method === 'passphrase' && !authConfig.passphrase_set}
+          onSetup={handleSetup}
+          setupError={setupError}
         />
       )}
     </div>
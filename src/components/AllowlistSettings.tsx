@@ -0,0 +1,154 @@
+import React, { useEffect, useState } from 'react';
+import { ListChecks, Plus, Trash2, GraduationCap } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { AllowlistConfig, PolicyMode, TerminationStrategy } from '../types';
+import { invokePrivileged } from '../session';
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+/** Policy mode switch and the allowlist used in default-deny mode */
+const AllowlistSettings: React.FC<{ onError: (message: string) => void }> = ({ onError }) => {
+  const [config, setConfig] = useState<AllowlistConfig | null>(null);
+  const [learningMinutes, setLearningMinutes] = useState(60);
+  const [name, setName] = useState('');
+  const [exePath, setExePath] = useState('');
+  const [anyPath, setAnyPath] = useState(false);
+
+  useEffect(() => {
+    invoke<AllowlistConfig>('get_allowlist_config')
+      .then(setConfig)
+      .catch(err => console.error('Failed to load allowlist:', err));
+    const unlisten = listen<AllowlistConfig>('allowlist-updated', event => setConfig(event.payload));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const run = async (command: string, args: Record<string, unknown>, fallback: string) => {
+    try {
+      setConfig(await invokePrivileged<AllowlistConfig>(command, args));
+      return true;
+    } catch (err) {
+      console.error(fallback, err);
+      onError(errorMessage(err, fallback));
+      return false;
+    }
+  };
+
+  const setMode = (mode: PolicyMode, learn: boolean) =>
+    run('set_policy_mode', { mode, learningMinutes: learn ? learningMinutes : null }, 'Failed to change policy mode');
+
+  const addEntry = async () => {
+    const args = { name, exePath: anyPath ? null : exePath, anyPath };
+    if (await run('add_allowed_executable', args, 'Failed to add executable')) {
+      setName('');
+      setExePath('');
+      setAnyPath(false);
+    }
+  };
+
+  if (!config) {
+    return null;
+  }
+
+  const learning = config.learning_until !== null;
+
+  return (
+    <section className="bg-slate-900 border border-slate-800 rounded-3xl p-6 shadow-xl">
+      <h3 className="text-lg font-bold flex items-center gap-2 mb-6">
+        <ListChecks className="w-5 h-5 text-teal-400" /> Policy Mode
+      </h3>
+      <div className="space-y-4">
+        <div className="grid grid-cols-2 gap-2">
+          {(['blocklist', 'allowlist'] as PolicyMode[]).map(mode => (
+            <button
+              key={mode}
+              onClick={() => setMode(mode, false)}
+              className={`p-3 rounded-xl border text-sm font-bold transition-all ${config.mode === mode ? 'bg-teal-600/20 border-teal-500/50 text-teal-300' : 'bg-slate-800/30 border-slate-700/30 text-slate-400 hover:border-slate-600'}`}
+            >
+              {mode === 'blocklist' ? 'Block listed apps' : 'Allow listed apps only'}
+            </button>
+          ))}
+        </div>
+        <p className="text-[10px] text-slate-500">
+          In allowlist mode, anything you run while locked that isn't allowed below or part of the built-in session set ({config.safe_processes.length} system binaries under /usr) is handled like a protected app.
+        </p>
+
+        {config.mode === 'allowlist' && (
+          <>
+            <div className="p-4 bg-slate-800/30 rounded-xl border border-slate-700/30 space-y-3">
+              <div className="flex items-center gap-2 text-sm font-medium">
+                <GraduationCap className="w-4 h-4 text-teal-400" />
+                {learning
+                  ? `Learning until ${new Date(config.learning_until!).toLocaleString()}, not enforcing yet`
+                  : 'Learn from what you run before enforcing'}
+              </div>
+              <div className="flex items-center gap-2">
+                <input
+                  type="number"
+                  min={1}
+                  value={learningMinutes}
+                  onChange={e => setLearningMinutes(Math.max(1, Number(e.target.value) || 1))}
+                  className="w-24 bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm font-mono"
+                />
+                <span className="text-xs text-slate-500">minutes</span>
+                <button onClick={() => setMode('allowlist', true)} className="ml-auto px-3 py-1 bg-teal-600 hover:bg-teal-500 rounded-lg text-xs font-bold transition-colors">
+                  {learning ? 'Restart learning' : 'Start learning'}
+                </button>
+                {learning && (
+                  <button onClick={() => setMode('allowlist', false)} className="px-3 py-1 bg-slate-700 hover:bg-slate-600 rounded-lg text-xs font-bold transition-colors">
+                    Enforce now
+                  </button>
+                )}
+              </div>
+            </div>
+
+            <div className="flex items-center justify-between p-4 bg-slate-800/30 rounded-xl border border-slate-700/30">
+              <span className="text-sm font-medium">Unlisted executables</span>
+              <select
+                value={config.strategy}
+                onChange={e => run('set_allowlist_strategy', { strategy: e.target.value as TerminationStrategy }, 'Failed to set action')}
+                className="bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm"
+              >
+                <option value="kill">Kill</option>
+                <option value="terminate">Terminate gracefully</option>
+                <option value="stop">Stop until unlock</option>
+                <option value="freeze">Freeze until unlock</option>
+              </select>
+            </div>
+
+            <div className="space-y-2 max-h-64 overflow-y-auto">
+              {config.entries.map(entry => (
+                <div key={entry.id} className="flex items-center justify-between p-2 bg-slate-800/30 rounded-lg border border-slate-700/30">
+                  <div className="min-w-0">
+                    <p className="text-sm font-medium truncate">{entry.name}</p>
+                    <p className="text-[10px] text-slate-500 font-mono truncate">{entry.exe_path || 'any path'} · {entry.source}</p>
+                  </div>
+                  <button onClick={() => run('remove_allowed_executable', { id: entry.id }, 'Failed to remove executable')} className="p-1 text-slate-500 hover:text-red-400 transition-colors">
+                    <Trash2 className="w-4 h-4" />
+                  </button>
+                </div>
+              ))}
+            </div>
+
+            <div className="flex items-center gap-2">
+              <input value={name} onChange={e => setName(e.target.value)} placeholder="Process name" className="flex-1 bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm" />
+              <input value={exePath} onChange={e => setExePath(e.target.value)} disabled={anyPath} placeholder="/path/to/binary" className="flex-1 bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm font-mono disabled:opacity-50" />
+              <button onClick={addEntry} disabled={!name.trim() || (!anyPath && !exePath.trim())} className="p-2 bg-teal-600 hover:bg-teal-500 disabled:opacity-50 rounded-lg transition-colors">
+                <Plus className="w-4 h-4" />
+              </button>
+            </div>
+            <label className="flex items-center gap-2 text-[10px] text-slate-500">
+              <input type="checkbox" checked={anyPath} onChange={e => setAnyPath(e.target.checked)} className="accent-teal-500" />
+              Allow any binary with this name, wherever it is. Anything renamed or copied to this name will run too.
+            </label>
+          </>
+        )}
+      </div>
+    </section>
+  );
+};
+
+export default AllowlistSettings;
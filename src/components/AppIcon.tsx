@@ -0,0 +1,50 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+interface AppIconProps {
+  iconName?: string | null;
+  fallback: string;
+  size?: number;
+}
+
+// Resolved icons, shared across the watchlist and the picker
+const iconCache = new Map<string, string | null>();
+
+const AppIcon: React.FC<AppIconProps> = ({ iconName, fallback, size = 48 }) => {
+  const key = iconName ? `${iconName}@${size}` : null;
+  const [src, setSrc] = useState<string | null>(key ? iconCache.get(key) ?? null : null);
+
+  useEffect(() => {
+    if (!iconName || !key) {
+      setSrc(null);
+      return;
+    }
+    if (iconCache.has(key)) {
+      setSrc(iconCache.get(key) ?? null);
+      return;
+    }
+
+    let cancelled = false;
+    invoke<string | null>('get_app_icon', { iconName, size })
+      .then(uri => {
+        iconCache.set(key, uri);
+        if (!cancelled) setSrc(uri);
+      })
+      .catch(error => {
+        console.error('Failed to load icon:', error);
+        iconCache.set(key, null);
+      });
+
+    return () => {
+      cancelled = true;
+    };
+  }, [iconName, size, key]);
+
+  if (!src) {
+    return <span>{fallback}</span>;
+  }
+
+  return <img src={src} alt="" className="w-9 h-9 object-contain" />;
+};
+
+export default AppIcon;
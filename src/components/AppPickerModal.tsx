@@ -1,18 +1,25 @@
 import React, { useEffect, useState } from 'react';
-import { X, Search, Play, Package, Loader } from 'lucide-react';
+import { X, Search, Play, Package, Loader, RefreshCw, Fingerprint } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
+import { MatchRule } from '../types';
+import AppIcon from './AppIcon';
+import { invokePrivileged } from '../session';
 
 interface AppCandidate {
   name: string;
   process_name: string;
   exe_path: string | null;
   category: string;
+  match_rule: MatchRule | null;
+  icon: string | null;
+  wm_class: string | null;
 }
 
 interface AppPickerModalProps {
   isOpen: boolean;
   onClose: () => void;
-  onSelect: (app: AppCandidate) => void;
+  /** `pinHash`: also pin the SHA-256 of the app's executable */
+  onSelect: (app: AppCandidate, pinHash: boolean) => void;
 }
 
 const categoryIcons: { [key: string]: string } = {
@@ -22,6 +29,11 @@ const categoryIcons: { [key: string]: string } = {
   'Media': '🎵',
   'Gaming': '🎮',
   'Graphics': '🎨',
+  'Office': '📄',
+  'Internet': '📡',
+  'Education': '🎓',
+  'System': '⚙️',
+  'Utilities': '🔧',
   'Running': '▶️',
 };
 
@@ -31,6 +43,8 @@ const AppPickerModal: React.FC<AppPickerModalProps> = ({ isOpen, onClose, onSele
   const [searchTerm, setSearchTerm] = useState('');
   const [isLoading, setIsLoading] = useState(false);
   const [selectedCategory, setSelectedCategory] = useState<string>('All');
+  const [isRefreshingHashes, setIsRefreshingHashes] = useState(false);
+  const [pinHash, setPinHash] = useState(false);
 
   useEffect(() => {
     if (isOpen) {
@@ -55,6 +69,17 @@ const AppPickerModal: React.FC<AppPickerModalProps> = ({ isOpen, onClose, onSele
     }
   };
 
+  const refreshPinnedHashes = async () => {
+    setIsRefreshingHashes(true);
+    try {
+      await invokePrivileged('refresh_pinned_hashes');
+    } catch (error) {
+      console.error('Failed to refresh pinned hashes:', error);
+    } finally {
+      setIsRefreshingHashes(false);
+    }
+  };
+
   const filterApps = () => {
     let filtered = apps;
 
@@ -76,7 +101,7 @@ const AppPickerModal: React.FC<AppPickerModalProps> = ({ isOpen, onClose, onSele
   };
 
   const handleSelect = (app: AppCandidate) => {
-    onSelect(app);
+    onSelect(app, pinHash);
     onClose();
     setSearchTerm('');
     setSelectedCategory('All');
@@ -96,12 +121,31 @@ const AppPickerModal: React.FC<AppPickerModalProps> = ({ isOpen, onClose, onSele
               <h2 className="text-2xl font-bold">Select Application</h2>
               <p className="text-sm text-slate-400 mt-1">Choose from installed apps or running processes</p>
             </div>
-            <button
-              onClick={onClose}
-              className="p-2 hover:bg-slate-800 rounded-xl transition-colors"
-            >
-              <X className="w-5 h-5" />
-            </button>
+            <div className="flex items-center gap-2">
+              <label
+                title="Also match renamed or copied copies of the app by the SHA-256 of its executable"
+                className="flex items-center gap-2 px-3 py-2 text-sm text-slate-400 hover:bg-slate-800 rounded-xl transition-colors cursor-pointer"
+              >
+                <input type="checkbox" checked={pinHash} onChange={e => setPinHash(e.target.checked)} className="w-4 h-4 accent-emerald-500" />
+                <Fingerprint className="w-4 h-4" />
+                Pin executable hash
+              </label>
+              <button
+                onClick={refreshPinnedHashes}
+                disabled={isRefreshingHashes}
+                title="Re-hash pinned executables after a package update"
+                className="flex items-center gap-2 px-3 py-2 text-sm text-slate-400 hover:bg-slate-800 rounded-xl transition-colors disabled:opacity-50"
+              >
+                <RefreshCw className={`w-4 h-4 ${isRefreshingHashes ? 'animate-spin' : ''}`} />
+                Refresh pinned hashes
+              </button>
+              <button
+                onClick={onClose}
+                className="p-2 hover:bg-slate-800 rounded-xl transition-colors"
+              >
+                <X className="w-5 h-5" />
+              </button>
+            </div>
           </div>
 
           {/* Search Bar */}
@@ -157,7 +201,7 @@ const AppPickerModal: React.FC<AppPickerModalProps> = ({ isOpen, onClose, onSele
                 >
                   <div className="flex items-center gap-3">
                     <div className="text-3xl">
-                      {categoryIcons[app.category] || '📦'}
+                      <AppIcon iconName={app.icon} fallback={categoryIcons[app.category] || '📦'} />
                     </div>
                     <div className="flex-1 min-w-0">
                       <h4 className="font-bold truncate group-hover:text-emerald-400 transition-colors">
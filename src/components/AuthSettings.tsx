@@ -0,0 +1,226 @@
+import React, { useEffect, useState } from 'react';
+import { Lock, KeyRound, Smartphone } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { AuthConfig, AuthMethod, TotpEnrollment } from '../types';
+import { invokePrivileged } from '../session';
+
+const MIN_PASSPHRASE_LEN = 8;
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+const AuthSettings: React.FC<{ onError: (message: string) => void }> = ({ onError }) => {
+  const [config, setConfig] = useState<AuthConfig | null>(null);
+  const [current, setCurrent] = useState('');
+  const [passphrase, setPassphrase] = useState('');
+  const [confirm, setConfirm] = useState('');
+  const [saved, setSaved] = useState(false);
+  const [enrollment, setEnrollment] = useState<TotpEnrollment | null>(null);
+  const [totpCode, setTotpCode] = useState('');
+  const [recoveryCodes, setRecoveryCodes] = useState<string[] | null>(null);
+  const [codesLeft, setCodesLeft] = useState(0);
+  const [pamService, setPamService] = useState('');
+
+  const loadConfig = () =>
+    Promise.all([invoke<AuthConfig>('get_auth_config'), invoke<number>('get_recovery_codes_left')])
+      .then(([configData, left]) => {
+        setConfig(configData);
+        setCodesLeft(left);
+        setPamService(configData.pam_service);
+      })
+      .catch(err => console.error('Failed to load auth config:', err));
+
+  const runTotpAction = async (action: () => Promise<void>, fallback: string) => {
+    try {
+      await action();
+      setTotpCode('');
+      await loadConfig();
+    } catch (err) {
+      console.error(fallback, err);
+      onError(errorMessage(err, fallback));
+    }
+  };
+
+  const beginEnrollment = () =>
+    runTotpAction(async () => {
+      setRecoveryCodes(null);
+      setEnrollment(await invokePrivileged<TotpEnrollment>('begin_totp_enrollment'));
+    }, 'Failed to start TOTP enrollment');
+
+  const confirmEnrollment = () =>
+    runTotpAction(async () => {
+      setRecoveryCodes(await invokePrivileged<string[]>('confirm_totp_enrollment', { code: totpCode }));
+      setEnrollment(null);
+    }, 'Failed to verify TOTP code');
+
+  const disableTotp = () =>
+    runTotpAction(async () => {
+      await invokePrivileged('disable_totp', { code: totpCode });
+      setRecoveryCodes(null);
+    }, 'Failed to disable TOTP');
+
+  const regenerateCodes = () =>
+    runTotpAction(async () => {
+      setRecoveryCodes(await invokePrivileged<string[]>('regenerate_recovery_codes'));
+    }, 'Failed to regenerate recovery codes');
+
+  useEffect(() => {
+    loadConfig();
+  }, []);
+
+  const switchMethod = async (method: AuthMethod) => {
+    try {
+      await invokePrivileged('set_auth_method', { method });
+      await loadConfig();
+    } catch (err) {
+      console.error('Error setting auth method:', err);
+      onError(errorMessage(err, 'Failed to change unlock method'));
+    }
+  };
+
+  const savePamService = async () => {
+    try {
+      await invokePrivileged('set_pam_service', { service: pamService });
+      await loadConfig();
+    } catch (err) {
+      console.error('Error setting PAM service:', err);
+      onError(errorMessage(err, 'Failed to set PAM service'));
+    }
+  };
+
+  const savePassphrase = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setSaved(false);
+    try {
+      await invokePrivileged('change_passphrase', {
+        current: config?.passphrase_set ? current : null,
+        passphrase,
+      });
+      setCurrent('');
+      setPassphrase('');
+      setConfirm('');
+      setSaved(true);
+      await loadConfig();
+    } catch (err) {
+      console.error('Error changing passphrase:', err);
+      onError(errorMessage(err, 'Failed to change passphrase'));
+    }
+  };
+
+  if (!config) {
+    return null;
+  }
+
+  const canSave = passphrase.length >= MIN_PASSPHRASE_LEN && passphrase === confirm && (!config.passphrase_set || current.length > 0);
+
+  return (
+    <section className="bg-slate-900 border border-slate-800 rounded-3xl p-6 shadow-xl">
+      <h3 className="text-lg font-bold flex items-center gap-2 mb-6">
+        <Lock className="w-5 h-5 text-emerald-400" /> Authentication
+      </h3>
+      <div className="space-y-4">
+        <div className="grid grid-cols-2 gap-2">
+          {([['system', 'System Password'], ['passphrase', 'Vault Passphrase']] as [AuthMethod, string][]).map(([method, label]) => {
+            const available = method === 'system' ? config.system_available : config.passphrase_set;
+            return (
+              <button
+                key={method}
+                disabled={!available || config.method === method}
+                onClick={() => switchMethod(method)}
+                className={`p-3 rounded-xl border text-xs font-bold transition-all disabled:cursor-not-allowed ${config.method === method ? 'bg-emerald-500/10 border-emerald-500/30 text-emerald-400' : 'bg-slate-800/50 border-slate-700 text-slate-400 hover:bg-slate-800 disabled:opacity-50'}`}
+              >
+                {label}
+              </button>
+            );
+          })}
+        </div>
+        <p className="text-xs text-slate-400">
+          {config.method === 'system'
+            ? 'Unlocking uses your system PAM authentication.'
+            : 'Unlocking uses a dedicated vault passphrase, stored as an Argon2id hash.'}
+          {!config.system_available && ' System authentication is not available in this build.'}
+        </p>
+
+        {config.system_available && (
+          <div className="p-4 bg-slate-800/30 rounded-xl border border-slate-700/30 space-y-3">
+            <label className="text-sm font-medium text-slate-400">PAM Service</label>
+            <div className="flex gap-2">
+              <input value={pamService} onChange={e => setPamService(e.target.value)} placeholder="ficha" className="flex-1 bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm font-mono focus:outline-none focus:ring-2 focus:ring-emerald-500/20" />
+              <button onClick={savePamService} disabled={!pamService.trim() || pamService === config.pam_service} className="px-4 bg-slate-800 hover:bg-slate-700 disabled:opacity-50 text-xs font-bold rounded-lg transition-all border border-slate-700">Apply</button>
+            </div>
+            <p className="text-[10px] text-slate-500">Service file under /etc/pam.d used for the system password, e.g. ficha, login or common-auth.</p>
+          </div>
+        )}
+
+        <form onSubmit={savePassphrase} className="p-4 bg-slate-800/30 rounded-xl border border-slate-700/30 space-y-3">
+          <label className="text-sm font-medium text-slate-400 flex items-center gap-2">
+            <KeyRound className="w-4 h-4" /> {config.passphrase_set ? 'Change Vault Passphrase' : 'Set Vault Passphrase'}
+          </label>
+          {config.passphrase_set && (
+            <input type="password" value={current} onChange={e => setCurrent(e.target.value)} placeholder="Current passphrase" className="w-full bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm font-mono focus:outline-none focus:ring-2 focus:ring-emerald-500/20" />
+          )}
+          <input type="password" value={passphrase} onChange={e => setPassphrase(e.target.value)} placeholder={`New passphrase (min ${MIN_PASSPHRASE_LEN} characters)`} className="w-full bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm font-mono focus:outline-none focus:ring-2 focus:ring-emerald-500/20" />
+          <input type="password" value={confirm} onChange={e => setConfirm(e.target.value)} placeholder="Confirm new passphrase" className="w-full bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm font-mono focus:outline-none focus:ring-2 focus:ring-emerald-500/20" />
+          <button type="submit" disabled={!canSave} className="w-full bg-slate-800 hover:bg-slate-700 disabled:opacity-50 disabled:cursor-not-allowed text-xs font-bold py-3 rounded-xl transition-all border border-slate-700">
+            Save Passphrase
+          </button>
+          {saved && <p className="text-xs text-emerald-400">✓ Passphrase updated</p>}
+        </form>
+
+        <div className="p-4 bg-slate-800/30 rounded-xl border border-slate-700/30 space-y-3">
+          <div className="flex items-center justify-between">
+            <label className="text-sm font-medium text-slate-400 flex items-center gap-2">
+              <Smartphone className="w-4 h-4" /> Two-Factor (TOTP)
+            </label>
+            <span className={`text-[10px] font-bold px-2 py-0.5 rounded-full ${config.totp_enabled ? 'bg-emerald-500/10 text-emerald-400 border border-emerald-500/20' : 'bg-slate-700 text-slate-400'}`}>
+              {config.totp_enabled ? `ON · ${codesLeft} recovery codes` : 'OFF'}
+            </span>
+          </div>
+
+          {enrollment && (
+            <div className="space-y-3">
+              <p className="text-xs text-slate-400">Scan with your authenticator app, then enter the code it shows.</p>
+              <div className="bg-white rounded-xl p-3 w-fit mx-auto" dangerouslySetInnerHTML={{ __html: enrollment.qr_svg }} />
+              <p className="text-[10px] text-slate-500 font-mono break-all select-text">{enrollment.secret}</p>
+            </div>
+          )}
+
+          {(enrollment || config.totp_enabled) && (
+            <input
+              type="text"
+              inputMode="numeric"
+              value={totpCode}
+              onChange={e => setTotpCode(e.target.value)}
+              placeholder={enrollment ? '6-digit code' : 'Code required to disable'}
+              className="w-full bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm font-mono tracking-widest focus:outline-none focus:ring-2 focus:ring-emerald-500/20"
+            />
+          )}
+
+          <div className="flex gap-3">
+            {enrollment ? (
+              <button onClick={confirmEnrollment} disabled={!totpCode} className="flex-1 bg-emerald-600 hover:bg-emerald-500 disabled:opacity-50 text-xs font-bold py-3 rounded-xl transition-all">Verify & Enable</button>
+            ) : config.totp_enabled ? (
+              <>
+                <button onClick={regenerateCodes} className="flex-1 bg-slate-800 hover:bg-slate-700 text-xs font-bold py-3 rounded-xl transition-all border border-slate-700">New Recovery Codes</button>
+                <button onClick={disableTotp} disabled={!totpCode} className="flex-1 bg-red-500/10 hover:bg-red-500/20 disabled:opacity-50 text-red-400 text-xs font-bold py-3 rounded-xl transition-all border border-red-500/20">Disable</button>
+              </>
+            ) : (
+              <button onClick={beginEnrollment} className="flex-1 bg-slate-800 hover:bg-slate-700 text-xs font-bold py-3 rounded-xl transition-all border border-slate-700">Set Up Authenticator</button>
+            )}
+          </div>
+
+          {recoveryCodes && (
+            <div className="p-3 bg-amber-500/5 border border-amber-500/20 rounded-xl space-y-2">
+              <p className="text-xs text-amber-400">Save these recovery codes now. Each works once and they won't be shown again.</p>
+              <div className="grid grid-cols-2 gap-1 font-mono text-xs text-slate-300 select-text">
+                {recoveryCodes.map(code => <span key={code}>{code}</span>)}
+              </div>
+            </div>
+          )}
+        </div>
+      </div>
+    </section>
+  );
+};
+
+export default AuthSettings;
@@ -2,13 +2,24 @@
 import React, { useEffect, useState } from 'react';
 import {
   Shield, ShieldAlert, ShieldCheck, Plus, Trash2, Power, History,
-  Activity, Terminal, Settings, Bell, Database, Lock, Search,
-  Filter, Download, AlertTriangle, CheckCircle2, ChevronRight
+  Activity, Terminal, Settings, Bell, Database, Search,
+  Filter, Download, AlertTriangle, CheckCircle2, ChevronRight, Timer, CalendarClock, Hourglass, Eye, Skull, Crosshair
 } from 'lucide-react';
-import { ProtectedApp, SecurityLog, ShieldStatus, SecurityPolicy } from '../types';
+import { Profile, ProtectedApp, SecurityLog, ShieldStatus, SecurityPolicy, KillReport, MatchRule, UnlockGrant, ScheduleTransition, QuotaStatus } from '../types';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import AppPickerModal from './AppPickerModal';
+import AppIcon from './AppIcon';
+import AuthSettings from './AuthSettings';
+import AllowlistSettings from './AllowlistSettings';
+import ElevationExemptions from './ElevationExemptions';
+import ProfileSwitcher from './ProfileSwitcher';
+import UnlockGrants from './UnlockGrants';
+import ScheduleModal from './ScheduleModal';
+import QuotaModal from './QuotaModal';
+import TerminationModal from './TerminationModal';
+import RuleModal from './RuleModal';
+import { invokePrivileged } from '../session';
 
 type Tab = 'dashboard' | 'policies' | 'config' | 'logs';
 
@@ -17,6 +28,9 @@ interface AppCandidate {
   process_name: string;
   exe_path: string | null;
   category: string;
+  match_rule: MatchRule | null;
+  icon: string | null;
+  wm_class: string | null;
 }
 
 const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
@@ -26,10 +40,17 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
   const [logs, setLogs] = useState<SecurityLog[]>([]);
   const [policies, setPolicies] = useState<SecurityPolicy[]>([]);
   const [searchTerm, setSearchTerm] = useState('');
+  const [typeFilter, setTypeFilter] = useState<SecurityLog['type'] | 'all'>('all');
   const [isLoading, setIsLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [isAppPickerOpen, setIsAppPickerOpen] = useState(false);
+  const [scheduleApp, setScheduleApp] = useState<ProtectedApp | null>(null);
+  const [quotaApp, setQuotaApp] = useState<ProtectedApp | null>(null);
+  const [terminationApp, setTerminationApp] = useState<ProtectedApp | null>(null);
+  const [ruleApp, setRuleApp] = useState<ProtectedApp | null>(null);
+  const [quotas, setQuotas] = useState<Record<string, QuotaStatus>>({});
   const [autostartEnabled, setAutostartEnabled] = useState(false);
+  const [auditMode, setAuditMode] = useState(false);
   const [idleTimeout, setIdleTimeout] = useState(10);
 
   // Load initial data
@@ -37,6 +58,13 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
     loadData();
   }, []);
 
+  // Quota budgets drain while apps run, so poll them
+  useEffect(() => {
+    loadQuotas();
+    const interval = setInterval(loadQuotas, 30000);
+    return () => clearInterval(interval);
+  }, []);
+
   // Set up real-time event listeners
   useEffect(() => {
     const unlistenPromises = [
@@ -52,8 +80,31 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
       listen<string>('app-removed', (event) => {
         setApps(prev => prev.filter(app => app.id !== event.payload));
       }),
-      listen<[number, string]>('process-killed', (event) => {
-        console.log(`Process killed: PID ${event.payload[0]}, Name: ${event.payload[1]}`);
+      listen<KillReport>('process-killed', (event) => {
+        const { pid, process_name, pids_terminated } = event.payload;
+        console.log(`Process killed: PID ${pid}, Name: ${process_name}, PIDs terminated: ${pids_terminated}`);
+      }),
+      listen<KillReport>('process-detected', (event) => {
+        const { pid, process_name } = event.payload;
+        console.log(`Process detected (auto-kill off): PID ${pid}, Name: ${process_name}`);
+      }),
+      listen<ScheduleTransition>('schedule-transition', (event) => {
+        const { process_name, blocking } = event.payload;
+        console.log(`Schedule ${blocking ? 'started' : 'ended'} for ${process_name}`);
+      }),
+      listen<QuotaStatus>('quota-warning', (event) => {
+        setQuotas(prev => ({ ...prev, [event.payload.app_id]: event.payload }));
+      }),
+      listen<QuotaStatus>('quota-exhausted', (event) => {
+        setQuotas(prev => ({ ...prev, [event.payload.app_id]: event.payload }));
+      }),
+      listen<Profile>('profile-changed', () => {
+        // Apps, policies and the idle timeout all belong to the profile
+        loadData();
+        loadQuotas();
+      }),
+      listen('policy-reloaded', () => {
+        loadData();
       }),
       listen<boolean>('auto-locked', (event) => {
         if (event.payload) {
@@ -74,13 +125,14 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
     setError(null);
 
     try {
-      const [appsData, logsData, policiesData, statusData, autostartData, idleTimeoutData] = await Promise.all([
+      const [appsData, logsData, policiesData, statusData, autostartData, idleTimeoutData, auditModeData] = await Promise.all([
         invoke<ProtectedApp[]>('get_protected_apps'),
         invoke<SecurityLog[]>('get_security_logs', { limit: 100 }),
         invoke<SecurityPolicy[]>('get_security_policies'),
         invoke<ShieldStatus>('get_shield_status'),
         invoke<boolean>('get_autostart_enabled'),
         invoke<number>('get_idle_timeout'),
+        invoke<boolean>('get_audit_mode'),
       ]);
 
       setApps(appsData);
@@ -89,6 +141,7 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
       setStatus(statusData);
       setAutostartEnabled(autostartData);
       setIdleTimeout(idleTimeoutData);
+      setAuditMode(auditModeData);
     } catch (err) {
       console.error('Error loading data:', err);
       setError(err instanceof Error ? err.message : 'Failed to load data');
@@ -97,9 +150,27 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
     }
   };
 
+  const grantUnlock = async (app: ProtectedApp, minutes = 45) => {
+    try {
+      await invokePrivileged<UnlockGrant>('grant_temporary_unlock', { appId: app.id, minutes, killOnExpiry: true });
+    } catch (err) {
+      console.error('Error granting temporary unlock:', err);
+      setError(err instanceof Error ? err.message : 'Failed to grant temporary unlock');
+    }
+  };
+
+  const revokeUnlock = async (grant: UnlockGrant) => {
+    try {
+      await invokePrivileged('revoke_unlock_grant', { appId: grant.app_id });
+    } catch (err) {
+      console.error('Error revoking temporary unlock:', err);
+      setError(err instanceof Error ? err.message : 'Failed to revoke temporary unlock');
+    }
+  };
+
   const togglePolicy = async (id: string) => {
     try {
-      await invoke('toggle_security_policy', { id });
+      await invokePrivileged('toggle_security_policy', { id });
       setPolicies(prev => prev.map(p => p.id === id ? { ...p, enabled: !p.enabled } : p));
     } catch (err) {
       console.error('Error toggling policy:', err);
@@ -107,7 +178,7 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
     }
   };
 
-  const handleAppSelected = async (app: AppCandidate) => {
+  const handleAppSelected = async (app: AppCandidate, pinHash: boolean) => {
     try {
       const categoryIcons: { [key: string]: string } = {
         'Browser': '🌐',
@@ -116,26 +187,78 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
         'Media': '🎵',
         'Gaming': '🎮',
         'Graphics': '🎨',
+        'Office': '📄',
+        'Internet': '📡',
+        'Education': '🎓',
+        'System': '⚙️',
+        'Utilities': '🔧',
         'Running': '▶️',
       };
 
       const icon = categoryIcons[app.category] || '📦';
 
-      await invoke<ProtectedApp>('add_protected_app', {
+      const added = await invokePrivileged<ProtectedApp>('add_protected_app', {
         name: app.name,
         processName: app.process_name,
         icon: icon,
         category: app.category,
+        matchRule: app.match_rule,
+        iconName: app.icon,
       });
+      if (pinHash) {
+        await pinAppHash(added);
+      }
     } catch (err) {
       console.error('Error adding app:', err);
       setError(err instanceof Error ? err.message : 'Failed to add app');
     }
   };
 
+  const pinAppHash = async (app: ProtectedApp) => {
+    try {
+      const hash = await invokePrivileged<string | null>('set_hash_pinning', { id: app.id, enabled: true });
+      setApps(prev => prev.map(a => a.id === app.id ? { ...a, exe_sha256: hash } : a));
+    } catch (err) {
+      console.error('Error pinning executable hash:', err);
+      setError(typeof err === 'string' ? err : `Added ${app.name}, but its executable hash could not be pinned`);
+    }
+  };
+
+  const loadQuotas = async () => {
+    try {
+      const statuses = await invoke<QuotaStatus[]>('get_quota_status');
+      setQuotas(Object.fromEntries(statuses.map(status => [status.app_id, status])));
+    } catch (err) {
+      console.error('Error loading quotas:', err);
+    }
+  };
+
+  const closeAppSettings = async () => {
+    setScheduleApp(null);
+    setQuotaApp(null);
+    setTerminationApp(null);
+    setRuleApp(null);
+    try {
+      setApps(await invoke<ProtectedApp[]>('get_protected_apps'));
+    } catch (err) {
+      console.error('Error reloading apps:', err);
+    }
+    loadQuotas();
+  };
+
+  const toggleAppAudit = async (app: ProtectedApp) => {
+    try {
+      await invokePrivileged('set_app_audit_only', { id: app.id, enabled: !app.audit_only });
+      setApps(prev => prev.map(a => a.id === app.id ? { ...a, audit_only: !app.audit_only } : a));
+    } catch (err) {
+      console.error('Error toggling audit-only mode:', err);
+      setError(err instanceof Error ? err.message : 'Failed to toggle audit-only mode');
+    }
+  };
+
   const removeApp = async (id: string) => {
     try {
-      await invoke('remove_protected_app', { id });
+      await invokePrivileged('remove_protected_app', { id });
     } catch (err) {
       console.error('Error removing app:', err);
       setError(err instanceof Error ? err.message : 'Failed to remove app');
@@ -143,8 +266,10 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
   };
 
   const filteredLogs = logs.filter(l =>
-    l.event.toLowerCase().includes(searchTerm.toLowerCase()) ||
-    l.type.toLowerCase().includes(searchTerm.toLowerCase())
+    (typeFilter === 'all' || l.type === typeFilter) && (
+      l.event.toLowerCase().includes(searchTerm.toLowerCase()) ||
+      l.type.toLowerCase().includes(searchTerm.toLowerCase())
+    )
   );
 
   if (isLoading) {
@@ -169,14 +294,53 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
             {apps.map(app => (
               <div key={app.id} className="group bg-slate-800/50 border border-slate-700/50 hover:border-emerald-500/30 rounded-2xl p-4 transition-all">
                 <div className="flex items-center gap-4">
-                  <div className="text-3xl">{app.icon}</div>
+                  <div className="text-3xl">
+                    <AppIcon iconName={app.icon_name} fallback={app.icon} />
+                  </div>
                   <div className="flex-1 min-w-0">
                     <h4 className="font-bold truncate">{app.name}</h4>
                     <p className="text-xs text-slate-500 font-mono truncate">Process: {app.processName}</p>
                     {app.lastAttempt && (
                       <p className="text-[10px] text-red-400">Last attempt: {app.lastAttempt}</p>
                     )}
+                    {app.source && (
+                      <p className="text-[10px] text-amber-400 truncate" title={app.source}>Managed by {app.source}</p>
+                    )}
+                    {app.exe_sha256 && (
+                      <p className="text-[10px] text-emerald-400 font-mono truncate" title={app.exe_sha256}>Hash pinned: {app.exe_sha256.slice(0, 16)}</p>
+                    )}
+                    {app.audit_only && (
+                      <p className="text-[10px] text-cyan-400">Audit only, never terminated</p>
+                    )}
+                    {app.schedule && (
+                      <p className="text-[10px] text-sky-400">Blocked on a schedule</p>
+                    )}
+                    {quotas[app.id] && (
+                      <p className={`text-[10px] ${quotas[app.id].remaining_secs > 0 ? 'text-violet-400' : 'text-red-400'}`}>
+                        {quotas[app.id].remaining_secs > 0
+                          ? `${Math.ceil(quotas[app.id].remaining_secs / 60)} of ${quotas[app.id].quota_secs / 60} min left today`
+                          : 'Daily quota used up'}
+                      </p>
+                    )}
                   </div>
+                  <button onClick={() => setRuleApp(app)} title="Change how the app is recognised" className="p-2 text-slate-600 hover:text-emerald-400 opacity-0 group-hover:opacity-100 transition-all">
+                    <Crosshair className="w-4 h-4" />
+                  </button>
+                  <button onClick={() => setTerminationApp(app)} title="Choose how the app is stopped" className="p-2 text-slate-600 hover:text-red-400 opacity-0 group-hover:opacity-100 transition-all">
+                    <Skull className="w-4 h-4" />
+                  </button>
+                  <button onClick={() => setQuotaApp(app)} title="Limit minutes per day" className="p-2 text-slate-600 hover:text-violet-400 opacity-0 group-hover:opacity-100 transition-all">
+                    <Hourglass className="w-4 h-4" />
+                  </button>
+                  <button onClick={() => toggleAppAudit(app)} title={app.audit_only ? 'Enforce this app' : 'Audit only: log matches without terminating'} className={`p-2 ${app.audit_only ? 'text-cyan-400' : 'text-slate-600 opacity-0 group-hover:opacity-100'} hover:text-cyan-300 transition-all`}>
+                    <Eye className="w-4 h-4" />
+                  </button>
+                  <button onClick={() => setScheduleApp(app)} title="Block on a schedule" className="p-2 text-slate-600 hover:text-sky-400 opacity-0 group-hover:opacity-100 transition-all">
+                    <CalendarClock className="w-4 h-4" />
+                  </button>
+                  <button onClick={() => grantUnlock(app)} title="Allow for 45 minutes while locked" className="p-2 text-slate-600 hover:text-amber-400 opacity-0 group-hover:opacity-100 transition-all">
+                    <Timer className="w-4 h-4" />
+                  </button>
                   <button onClick={() => removeApp(app.id)} className="p-2 text-slate-600 hover:text-red-400 opacity-0 group-hover:opacity-100 transition-all">
                     <Trash2 className="w-4 h-4" />
                   </button>
@@ -198,6 +362,14 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
           </div>
         </section>
 
+        <section className="bg-slate-900 border border-slate-800 rounded-3xl p-6 shadow-xl">
+          <h3 className="text-lg font-bold flex items-center gap-2 mb-6">
+            <Timer className="w-5 h-5 text-amber-400" /> Temporary Unlocks
+          </h3>
+          <UnlockGrants onRevoke={revokeUnlock} />
+          <p className="text-xs text-slate-500 mt-3">Apps allowed for a limited time while the shield is locked. Grant one from the lock screen or the watchlist.</p>
+        </section>
+
         <section className="bg-slate-900 border border-slate-800 rounded-3xl p-6 shadow-xl">
           <div className="flex items-center justify-between mb-6">
             <h3 className="text-lg font-bold flex items-center gap-2">
@@ -277,6 +449,9 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
               </button>
             </div>
           ))}
+          {policies.some(policy => policy.id === 'policy_3' && policy.enabled) && (
+            <ElevationExemptions onError={setError} />
+          )}
         </div>
       </div>
     </div>
@@ -307,7 +482,7 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
                 checked={autostartEnabled}
                 onChange={async (e) => {
                   try {
-                    await invoke('toggle_autostart', { enabled: e.target.checked });
+                    await invokePrivileged('toggle_autostart', { enabled: e.target.checked });
                     setAutostartEnabled(e.target.checked);
                   } catch (err) {
                     console.error('Error toggling autostart:', err);
@@ -317,6 +492,29 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
                 className="w-4 h-4 accent-emerald-500"
               />
             </div>
+            <div className="flex items-center justify-between p-4 bg-slate-800/30 rounded-xl border border-slate-700/30">
+              <div className="flex items-center gap-3">
+                <Eye className="w-5 h-5 text-slate-400" />
+                <div>
+                  <span className="text-sm font-medium">Audit Mode (dry run)</span>
+                  <p className="text-[10px] text-slate-500">Log what would be terminated without terminating anything</p>
+                </div>
+              </div>
+              <input
+                type="checkbox"
+                checked={auditMode}
+                onChange={async (e) => {
+                  try {
+                    await invokePrivileged('set_audit_mode', { enabled: e.target.checked });
+                    setAuditMode(e.target.checked);
+                  } catch (err) {
+                    console.error('Error toggling audit mode:', err);
+                    setError(err instanceof Error ? err.message : 'Failed to toggle audit mode');
+                  }
+                }}
+                className="w-4 h-4 accent-cyan-500"
+              />
+            </div>
             <div className="p-4 bg-slate-800/30 rounded-xl border border-slate-700/30 space-y-3">
               <label className="text-sm font-medium text-slate-400">Logging Intensity</label>
               <input type="range" min="0" max="100" defaultValue="75" className="w-full h-2 bg-slate-700 rounded-lg appearance-none cursor-pointer accent-emerald-500" />
@@ -340,8 +538,8 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
                   const newTimeout = parseInt(e.target.value);
                   setIdleTimeout(newTimeout);
                   try {
-                    await invoke('set_idle_timeout', { minutes: newTimeout });
-                    await invoke('reset_idle_timer');
+                    await invokePrivileged('set_idle_timeout', { minutes: newTimeout });
+                    await invokePrivileged('reset_idle_timer');
                   } catch (err) {
                     console.error('Error setting idle timeout:', err);
                     setError(err instanceof Error ? err.message : 'Failed to set idle timeout');
@@ -376,17 +574,9 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
       </div>
 
       <div className="space-y-8">
-        <section className="bg-slate-900 border border-slate-800 rounded-3xl p-6 shadow-xl">
-          <h3 className="text-lg font-bold flex items-center gap-2 mb-6">
-            <Lock className="w-5 h-5 text-emerald-400" /> Authentication
-          </h3>
-          <div className="space-y-4">
-            <p className="text-xs text-slate-400">Ficha uses your system PAM authentication for secure access control.</p>
-            <div className="p-4 bg-emerald-500/5 border border-emerald-500/20 rounded-xl">
-              <p className="text-xs text-emerald-400">✓ System authentication active</p>
-            </div>
-          </div>
-        </section>
+        <AuthSettings onError={setError} />
+
+        <AllowlistSettings onError={setError} />
 
         <section className="bg-slate-900 border border-slate-800 rounded-3xl p-6 shadow-xl border-dashed border-red-500/30">
           <h3 className="text-lg font-bold flex items-center gap-2 mb-4 text-red-400">
@@ -425,9 +615,21 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
               className="bg-slate-800 border border-slate-700 rounded-xl py-2 pl-10 pr-4 text-sm focus:outline-none focus:ring-1 focus:ring-emerald-500 w-full sm:w-64"
             />
           </div>
-          <button className="p-2 bg-slate-800 border border-slate-700 rounded-xl hover:bg-slate-700 transition-colors">
-            <Filter className="w-5 h-5 text-slate-400" />
-          </button>
+          <div className="relative">
+            <Filter className="absolute left-3 top-1/2 -translate-y-1/2 w-4 h-4 text-slate-500 pointer-events-none" />
+            <select
+              value={typeFilter}
+              onChange={e => setTypeFilter(e.target.value as SecurityLog['type'] | 'all')}
+              className="bg-slate-800 border border-slate-700 rounded-xl py-2 pl-9 pr-3 text-sm focus:outline-none focus:ring-1 focus:ring-emerald-500"
+            >
+              <option value="all">All types</option>
+              <option value="audit">Would have terminated</option>
+              <option value="error">Error</option>
+              <option value="warning">Warning</option>
+              <option value="success">Success</option>
+              <option value="info">Info</option>
+            </select>
+          </div>
         </div>
       </div>
       <div className="flex-1 overflow-y-auto p-2 custom-scrollbar">
@@ -451,6 +653,7 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
                     log.type === 'error' ? 'text-red-400 bg-red-400/10' :
                     log.type === 'success' ? 'text-emerald-400 bg-emerald-400/10' :
                     log.type === 'warning' ? 'text-amber-400 bg-amber-400/10' :
+                    log.type === 'audit' ? 'text-cyan-400 bg-cyan-400/10' :
                     'text-slate-400 bg-slate-700/50'
                   }`}>
                     {log.type}
@@ -495,6 +698,8 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
           </div>
         </div>
 
+        <ProfileSwitcher onError={setError} />
+
         <nav className="flex-1 space-y-2">
           <button onClick={() => setActiveTab('dashboard')} className={`w-full flex items-center gap-3 px-4 py-3 rounded-xl font-medium transition-all ${activeTab === 'dashboard' ? 'bg-emerald-600/10 text-emerald-400 shadow-inner border border-emerald-500/20' : 'text-slate-400 hover:bg-slate-800 hover:text-white'}`}>
             <Activity className="w-5 h-5" /> Dashboard
@@ -530,7 +735,7 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
                 {status === ShieldStatus.ACTIVE ? 'SHIELD ACTIVE' : status === ShieldStatus.THREAT_DETECTED ? 'MITIGATION ACTIVE' : 'SHIELD LOCKED'}
               </p>
               <p className="opacity-70 text-[10px]">
-                {status === ShieldStatus.ACTIVE ? 'Protected session active' : status === ShieldStatus.THREAT_DETECTED ? 'Threat neutralized' : 'Real-time monitoring online'}
+                {status === ShieldStatus.ACTIVE ? 'Protected session active' : status === ShieldStatus.THREAT_DETECTED ? 'Threat neutralized' : status === ShieldStatus.FROZEN ? 'Protected apps frozen until unlock' : 'Real-time monitoring online'}
               </p>
             </div>
           </div>
@@ -547,6 +752,11 @@ const Dashboard: React.FC<{ onLock: () => void }> = ({ onLock }) => {
         onClose={() => setIsAppPickerOpen(false)}
         onSelect={handleAppSelected}
       />
+
+      <ScheduleModal app={scheduleApp} onClose={closeAppSettings} />
+      <QuotaModal app={quotaApp} onClose={closeAppSettings} />
+      <TerminationModal app={terminationApp} onClose={closeAppSettings} />
+      <RuleModal app={ruleApp} onClose={closeAppSettings} />
     </div>
   );
 };
@@ -0,0 +1,66 @@
+import React, { useEffect, useState } from 'react';
+import { KeyRound, Plus, Trash2 } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { ElevationExemption } from '../types';
+import { invokePrivileged } from '../session';
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+/** Commands that may still be run with sudo, su, pkexec, doas or run0 while locked */
+const ElevationExemptions: React.FC<{ onError: (message: string) => void }> = ({ onError }) => {
+  const [exemptions, setExemptions] = useState<ElevationExemption[]>([]);
+  const [command, setCommand] = useState('');
+
+  useEffect(() => {
+    invoke<ElevationExemption[]>('get_elevation_exemptions')
+      .then(setExemptions)
+      .catch(err => console.error('Failed to load elevation exemptions:', err));
+  }, []);
+
+  const run = async (name: string, args: Record<string, unknown>, fallback: string) => {
+    try {
+      setExemptions(await invokePrivileged<ElevationExemption[]>(name, args));
+      return true;
+    } catch (err) {
+      console.error(fallback, err);
+      onError(errorMessage(err, fallback));
+      return false;
+    }
+  };
+
+  const addExemption = async () => {
+    if (await run('add_elevation_exemption', { command }, 'Failed to add exemption')) {
+      setCommand('');
+    }
+  };
+
+  return (
+    <div className="p-5 bg-slate-800/40 rounded-2xl border border-slate-700/40 space-y-3">
+      <h4 className="font-bold flex items-center gap-2">
+        <KeyRound className="w-4 h-4 text-emerald-400" /> Root Access Exemptions
+      </h4>
+      <p className="text-xs text-slate-500">
+        Elevated commands matching one of these exactly are allowed while locked, e.g. <span className="font-mono">systemctl suspend</span>. End with <span className="font-mono">*</span> to allow further arguments too. A program given by path must be in /usr/bin, /usr/sbin, /bin or /sbin.
+      </p>
+      <div className="space-y-2">
+        {exemptions.map(exemption => (
+          <div key={exemption.id} className="flex items-center justify-between p-2 bg-slate-800/30 rounded-lg border border-slate-700/30">
+            <span className="text-sm font-mono truncate">{exemption.command}</span>
+            <button onClick={() => run('remove_elevation_exemption', { id: exemption.id }, 'Failed to remove exemption')} className="p-1 text-slate-500 hover:text-red-400 transition-colors">
+              <Trash2 className="w-4 h-4" />
+            </button>
+          </div>
+        ))}
+      </div>
+      <div className="flex items-center gap-2">
+        <input value={command} onChange={e => setCommand(e.target.value)} placeholder="Command, e.g. apt update" className="flex-1 bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm font-mono" />
+        <button onClick={addExemption} disabled={!command.trim()} className="p-2 bg-emerald-600 hover:bg-emerald-500 disabled:opacity-50 rounded-lg transition-colors">
+          <Plus className="w-4 h-4" />
+        </button>
+      </div>
+    </div>
+  );
+};
+
+export default ElevationExemptions;
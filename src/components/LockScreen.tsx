@@ -1,19 +1,109 @@
 
-import React, { useState } from 'react';
-import { Shield, Lock, ChevronRight, Fingerprint } from 'lucide-react';
+import React, { useEffect, useState } from 'react';
+import { Shield, Lock, ChevronRight, Fingerprint, KeyRound } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { AuthMethod, ProtectedApp } from '../types';
+import PamConversation from './PamConversation';
+import UnlockGrants from './UnlockGrants';
+
+const MIN_PASSPHRASE_LEN = 8;
+const GRANT_DURATIONS = [15, 30, 45, 60, 120, 240];
+
+/** Unlock one protected app instead of the whole shield */
+export interface AppUnlockRequest {
+  appId: string;
+  minutes: number;
+  killOnExpiry: boolean;
+}
 
 interface LockScreenProps {
-  onUnlock: (password: string) => void;
+  onUnlock: (password: string, totpCode?: string) => void;
+  onUnlockApp?: (password: string, totpCode: string | undefined, request: AppUnlockRequest) => void;
+  /** Confirmation shown after a single-app unlock */
+  appUnlockNotice?: string | null;
   isError: boolean;
   isLoading?: boolean;
+  /** Epoch ms before which the backend rejects unlock attempts */
+  throttledUntil?: number | null;
+  lockedOut?: boolean;
+  authMethod?: AuthMethod;
+  /** Ask for a TOTP or recovery code alongside the secret */
+  totpRequired?: boolean;
+  /** No vault passphrase exists yet: ask for a new one instead */
+  needsSetup?: boolean;
+  onSetup?: (passphrase: string) => void;
+  setupError?: string | null;
 }
 
-const LockScreen: React.FC<LockScreenProps> = ({ onUnlock, isError, isLoading = false }) => {
+const LockScreen: React.FC<LockScreenProps> = ({
+  onUnlock,
+  onUnlockApp,
+  appUnlockNotice = null,
+  isError,
+  isLoading = false,
+  throttledUntil = null,
+  lockedOut = false,
+  authMethod = 'system',
+  totpRequired = false,
+  needsSetup = false,
+  onSetup,
+  setupError = null,
+}) => {
   const [password, setPassword] = useState('');
+  const [confirm, setConfirm] = useState('');
+  const [totpCode, setTotpCode] = useState('');
+  const [waitSecs, setWaitSecs] = useState(0);
+  const [appMode, setAppMode] = useState(false);
+  const [apps, setApps] = useState<ProtectedApp[]>([]);
+  const [grantAppId, setGrantAppId] = useState('');
+  const [grantMinutes, setGrantMinutes] = useState(45);
+  const [killOnExpiry, setKillOnExpiry] = useState(true);
+
+  useEffect(() => {
+    if (!appMode) return;
+    invoke<ProtectedApp[]>('get_protected_apps')
+      .then(data => {
+        setApps(data);
+        setGrantAppId(current => current || data[0]?.id || '');
+      })
+      .catch(error => console.error('Failed to load protected apps:', error));
+  }, [appMode]);
+
+  useEffect(() => {
+    if (!throttledUntil) {
+      setWaitSecs(0);
+      return;
+    }
+
+    const tick = () => setWaitSecs(Math.max(0, Math.ceil((throttledUntil - Date.now()) / 1000)));
+    tick();
+    const interval = setInterval(tick, 1000);
+    return () => clearInterval(interval);
+  }, [throttledUntil]);
+
+  const isThrottled = waitSecs > 0;
+  const formatWait = (secs: number) =>
+    secs >= 60 ? `${Math.floor(secs / 60)}m ${String(secs % 60).padStart(2, '0')}s` : `${secs}s`;
 
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
-    if (!isLoading) {
+    if (isLoading || isThrottled) {
+      return;
+    }
+    if (needsSetup) {
+      if (password.length >= MIN_PASSPHRASE_LEN && password === confirm) {
+        onSetup?.(password);
+      }
+    } else if (appMode) {
+      if (grantAppId && onUnlockApp) {
+        onUnlockApp(password, totpRequired ? totpCode : undefined, { appId: grantAppId, minutes: grantMinutes, killOnExpiry });
+        setPassword('');
+        setTotpCode('');
+      }
+    } else if (totpRequired) {
+      onUnlock(password, totpCode);
+      setTotpCode('');
+    } else {
       onUnlock(password);
     }
   };
@@ -34,28 +124,97 @@ const LockScreen: React.FC<LockScreenProps> = ({ onUnlock, isError, isLoading =
 
         <form onSubmit={handleSubmit} className="space-y-6">
           <div className="space-y-2">
-            <label className="text-xs font-semibold text-slate-500 uppercase tracking-wider ml-1">System Password</label>
+            <label className="text-xs font-semibold text-slate-500 uppercase tracking-wider ml-1">
+              {needsSetup ? 'Create Vault Passphrase' : authMethod === 'passphrase' ? 'Vault Passphrase' : 'System Password'}
+            </label>
             <div className="relative group">
               <Lock className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-500 group-focus-within:text-emerald-400 transition-colors" />
               <input
                 type="password"
                 value={password}
                 onChange={(e) => setPassword(e.target.value)}
-                placeholder="Enter OS Admin Password"
+                placeholder={needsSetup ? `At least ${MIN_PASSPHRASE_LEN} characters` : authMethod === 'passphrase' ? 'Enter Vault Passphrase' : 'Enter OS Admin Password'}
                 className={`w-full bg-slate-800/50 border ${isError ? 'border-red-500/50' : 'border-slate-700'} rounded-xl py-4 pl-12 pr-4 focus:outline-none focus:ring-2 focus:ring-emerald-500/20 transition-all font-mono`}
                 autoFocus
               />
             </div>
-            {isError && <p className="text-red-400 text-xs mt-1 ml-1">Invalid administrator credentials</p>}
+            {totpRequired && !needsSetup && (
+              <div className="relative group">
+                <KeyRound className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-500 group-focus-within:text-emerald-400 transition-colors" />
+                <input
+                  type="text"
+                  inputMode="numeric"
+                  autoComplete="one-time-code"
+                  value={totpCode}
+                  onChange={(e) => setTotpCode(e.target.value)}
+                  placeholder="Authenticator or recovery code"
+                  className={`w-full bg-slate-800/50 border ${isError ? 'border-red-500/50' : 'border-slate-700'} rounded-xl py-4 pl-12 pr-4 focus:outline-none focus:ring-2 focus:ring-emerald-500/20 transition-all font-mono tracking-widest`}
+                />
+              </div>
+            )}
+            {needsSetup && (
+              <input
+                type="password"
+                value={confirm}
+                onChange={(e) => setConfirm(e.target.value)}
+                placeholder="Confirm passphrase"
+                className="w-full bg-slate-800/50 border border-slate-700 rounded-xl py-4 px-4 focus:outline-none focus:ring-2 focus:ring-emerald-500/20 transition-all font-mono"
+              />
+            )}
+            {needsSetup && password.length > 0 && password.length < MIN_PASSPHRASE_LEN && (
+              <p className="text-slate-400 text-xs mt-1 ml-1">Use at least {MIN_PASSPHRASE_LEN} characters</p>
+            )}
+            {needsSetup && confirm.length > 0 && password !== confirm && (
+              <p className="text-red-400 text-xs mt-1 ml-1">Passphrases do not match</p>
+            )}
+            {setupError && <p className="text-red-400 text-xs mt-1 ml-1">{setupError}</p>}
+            {isThrottled ? (
+              <p className="text-amber-400 text-xs mt-1 ml-1">
+                {lockedOut ? 'Unlocking is locked out' : 'Too many failed attempts'} — try again in {formatWait(waitSecs)}
+              </p>
+            ) : (
+              isError && <p className="text-red-400 text-xs mt-1 ml-1">{totpRequired ? 'Invalid credentials or verification code' : authMethod === 'passphrase' ? 'Invalid vault passphrase' : 'Invalid administrator credentials'}</p>
+            )}
           </div>
 
+          {!needsSetup && onUnlockApp && (
+            <div className="space-y-3">
+              <label className="flex items-center gap-2 text-xs text-slate-400 ml-1 cursor-pointer">
+                <input type="checkbox" checked={appMode} onChange={(e) => setAppMode(e.target.checked)} className="w-4 h-4 accent-emerald-500" />
+                Unlock a single app only
+              </label>
+              {appMode && (
+                <div className="p-4 bg-slate-800/30 rounded-xl border border-slate-700/30 space-y-3">
+                  <select value={grantAppId} onChange={(e) => setGrantAppId(e.target.value)} className="w-full bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm focus:outline-none">
+                    {apps.map(app => <option key={app.id} value={app.id}>{app.name}</option>)}
+                  </select>
+                  <div className="flex gap-2">
+                    <select value={grantMinutes} onChange={(e) => setGrantMinutes(parseInt(e.target.value))} className="flex-1 bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm focus:outline-none">
+                      {GRANT_DURATIONS.map(minutes => (
+                        <option key={minutes} value={minutes}>{minutes >= 60 ? `${minutes / 60} h` : `${minutes} min`}</option>
+                      ))}
+                    </select>
+                    <label className="flex items-center gap-2 text-xs text-slate-400 cursor-pointer">
+                      <input type="checkbox" checked={killOnExpiry} onChange={(e) => setKillOnExpiry(e.target.checked)} className="w-4 h-4 accent-emerald-500" />
+                      Close on expiry
+                    </label>
+                  </div>
+                </div>
+              )}
+              {appUnlockNotice && <p className="text-emerald-400 text-xs ml-1">{appUnlockNotice}</p>}
+              <UnlockGrants />
+            </div>
+          )}
+
+          {authMethod === 'system' && !needsSetup && <PamConversation />}
+
           <button
             type="submit"
-            disabled={isLoading}
+            disabled={isLoading || isThrottled}
             className="w-full bg-emerald-600 hover:bg-emerald-500 disabled:bg-slate-700 disabled:cursor-not-allowed text-white font-bold py-4 rounded-xl shadow-lg shadow-emerald-900/20 transition-all flex items-center justify-center gap-2 group active:scale-[0.98]"
           >
-            {isLoading ? 'Authenticating...' : 'Unlock Shield'}
-            {!isLoading && <ChevronRight className="w-5 h-5 group-hover:translate-x-1 transition-transform" />}
+            {isLoading ? 'Authenticating...' : isThrottled ? `Retry in ${formatWait(waitSecs)}` : needsSetup ? 'Create & Unlock' : appMode ? 'Unlock App' : 'Unlock Shield'}
+            {!isLoading && !isThrottled && <ChevronRight className="w-5 h-5 group-hover:translate-x-1 transition-transform" />}
           </button>
         </form>
 
@@ -0,0 +1,75 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { PamMessage, PamPrompt } from '../types';
+
+/** Extra PAM prompts (OTP, expired password) and messages during unlock */
+const PamConversation: React.FC = () => {
+  const [prompt, setPrompt] = useState<PamPrompt | null>(null);
+  const [answer, setAnswer] = useState('');
+  const [messages, setMessages] = useState<PamMessage[]>([]);
+
+  useEffect(() => {
+    const unlistenPrompt = listen<PamPrompt>('pam-prompt', event => {
+      setAnswer('');
+      setPrompt(event.payload);
+    });
+    const unlistenMessage = listen<PamMessage>('pam-message', event => {
+      setMessages(prev => [...prev.slice(-2), event.payload]);
+    });
+
+    return () => {
+      unlistenPrompt.then(fn => fn());
+      unlistenMessage.then(fn => fn());
+    };
+  }, []);
+
+  const respond = async (response: string | null) => {
+    if (!prompt) return;
+    try {
+      await invoke('respond_pam_prompt', { id: prompt.id, response });
+    } catch (error) {
+      console.error('Failed to answer PAM prompt:', error);
+    }
+    setPrompt(null);
+    setAnswer('');
+  };
+
+  if (!prompt && messages.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="space-y-3">
+      {messages.map((msg, i) => (
+        <p key={i} className={`text-xs ml-1 ${msg.error ? 'text-red-400' : 'text-slate-400'}`}>{msg.message}</p>
+      ))}
+      {prompt && (
+        // Rendered inside the lock screen's form, so no nested <form>
+        <div className="p-4 bg-slate-800/50 border border-emerald-500/30 rounded-xl space-y-3"
+        >
+          <label className="text-xs font-semibold text-slate-300">{prompt.message}</label>
+          <input
+            type={prompt.echo ? 'text' : 'password'}
+            value={answer}
+            onChange={(e) => setAnswer(e.target.value)}
+            onKeyDown={(e) => {
+              if (e.key === 'Enter') {
+                e.preventDefault();
+                respond(answer);
+              }
+            }}
+            className="w-full bg-slate-800/50 border border-slate-700 rounded-lg py-2 px-3 text-sm font-mono focus:outline-none focus:ring-2 focus:ring-emerald-500/20"
+            autoFocus
+          />
+          <div className="flex gap-2">
+            <button type="button" onClick={() => respond(answer)} className="flex-1 bg-emerald-600 hover:bg-emerald-500 text-xs font-bold py-2 rounded-lg transition-all">Continue</button>
+            <button type="button" onClick={() => respond(null)} className="flex-1 bg-slate-800 hover:bg-slate-700 text-xs font-bold py-2 rounded-lg transition-all border border-slate-700">Cancel</button>
+          </div>
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default PamConversation;
@@ -0,0 +1,104 @@
+import React, { useEffect, useState } from 'react';
+import { Layers, Plus, Trash2 } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Profile } from '../types';
+import { invokePrivileged } from '../session';
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+/** Pick the active profile (e.g. Work, Home, Travel), add or remove profiles */
+const ProfileSwitcher: React.FC<{ onError: (message: string) => void }> = ({ onError }) => {
+  const [profiles, setProfiles] = useState<Profile[]>([]);
+  const [name, setName] = useState('');
+  const [isAdding, setIsAdding] = useState(false);
+
+  const loadProfiles = () =>
+    invoke<Profile[]>('get_profiles')
+      .then(setProfiles)
+      .catch(err => console.error('Failed to load profiles:', err));
+
+  useEffect(() => {
+    loadProfiles();
+    const unlisten = listen<Profile>('profile-changed', () => loadProfiles());
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const active = profiles.find(profile => profile.active);
+
+  const switchTo = async (id: string) => {
+    try {
+      await invokePrivileged<Profile>('switch_profile', { id });
+    } catch (err) {
+      console.error('Failed to switch profile:', err);
+      onError(errorMessage(err, 'Failed to switch profile'));
+    }
+  };
+
+  const addProfile = async () => {
+    try {
+      await invokePrivileged<Profile>('create_profile', { name, copyCurrent: true });
+      setName('');
+      setIsAdding(false);
+      await loadProfiles();
+    } catch (err) {
+      console.error('Failed to create profile:', err);
+      onError(errorMessage(err, 'Failed to create profile'));
+    }
+  };
+
+  const deleteActive = async () => {
+    if (!active) {
+      return;
+    }
+    const fallback = profiles.find(profile => !profile.active);
+    if (!fallback || !confirm(`Delete profile ${active.name} and its protected apps?`)) {
+      return;
+    }
+    try {
+      await invokePrivileged<Profile>('switch_profile', { id: fallback.id });
+      setProfiles(await invokePrivileged<Profile[]>('delete_profile', { id: active.id }));
+    } catch (err) {
+      console.error('Failed to delete profile:', err);
+      onError(errorMessage(err, 'Failed to delete profile'));
+    }
+  };
+
+  return (
+    <div className="mb-8 space-y-2">
+      <label className="text-[10px] font-bold text-slate-500 uppercase tracking-widest flex items-center gap-1">
+        <Layers className="w-3 h-3" /> Profile
+      </label>
+      <div className="flex items-center gap-2">
+        <select
+          value={active?.id ?? ''}
+          onChange={e => switchTo(e.target.value)}
+          className="flex-1 min-w-0 bg-slate-800 border border-slate-700 rounded-lg px-2 py-1.5 text-sm"
+        >
+          {profiles.map(profile => (
+            <option key={profile.id} value={profile.id}>{profile.name}</option>
+          ))}
+        </select>
+        <button onClick={() => setIsAdding(!isAdding)} title="New profile from this one" className="p-1.5 text-slate-400 hover:text-emerald-400 transition-colors">
+          <Plus className="w-4 h-4" />
+        </button>
+        <button onClick={deleteActive} disabled={profiles.length < 2} title="Delete this profile" className="p-1.5 text-slate-400 hover:text-red-400 disabled:opacity-40 transition-colors">
+          <Trash2 className="w-4 h-4" />
+        </button>
+      </div>
+      {isAdding && (
+        <div className="flex items-center gap-2">
+          <input value={name} onChange={e => setName(e.target.value)} placeholder="Work, Home, Travel..." className="flex-1 min-w-0 bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm" />
+          <button onClick={addProfile} disabled={!name.trim()} className="px-3 py-1 bg-emerald-600 hover:bg-emerald-500 disabled:opacity-50 rounded-lg text-xs font-bold transition-colors">
+            Add
+          </button>
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default ProfileSwitcher;
@@ -0,0 +1,92 @@
+import React, { useEffect, useState } from 'react';
+import { X, Hourglass } from 'lucide-react';
+import { ProtectedApp } from '../types';
+import { invokePrivileged } from '../session';
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+interface QuotaModalProps {
+  app: ProtectedApp | null;
+  onClose: () => void;
+}
+
+/** Set how many minutes per day an app may run while it is allowed */
+const QuotaModal: React.FC<QuotaModalProps> = ({ app, onClose }) => {
+  const [minutes, setMinutes] = useState(120);
+  const [error, setError] = useState<string | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    setError(null);
+    setMinutes(app?.daily_quota_mins ?? 120);
+  }, [app]);
+
+  if (!app) {
+    return null;
+  }
+
+  const save = async (next: number | null) => {
+    setSaving(true);
+    setError(null);
+    try {
+      await invokePrivileged('set_app_quota', { id: app.id, minutes: next });
+      onClose();
+    } catch (err) {
+      console.error('Error saving quota:', err);
+      setError(errorMessage(err, 'Failed to save quota'));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+      <div className="bg-slate-900 border border-slate-800 rounded-3xl shadow-2xl w-full max-w-md flex flex-col">
+        <div className="p-6 border-b border-slate-800 flex items-center justify-between">
+          <div>
+            <h2 className="text-2xl font-bold flex items-center gap-2">
+              <Hourglass className="w-6 h-6 text-violet-400" /> Daily Quota
+            </h2>
+            <p className="text-sm text-slate-400 mt-1">{app.name} is closed once it has run this long today. Warnings are sent 15 and 5 minutes before.</p>
+          </div>
+          <button onClick={onClose} className="p-2 text-slate-500 hover:text-white transition-colors">
+            <X className="w-5 h-5" />
+          </button>
+        </div>
+
+        <div className="p-6 space-y-3">
+          <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Minutes per day</label>
+          <input
+            type="number"
+            min={1}
+            max={1440}
+            value={minutes}
+            onChange={e => setMinutes(Math.max(1, Math.min(1440, Number(e.target.value) || 1)))}
+            className="w-full bg-slate-800 border border-slate-700 rounded-xl px-3 py-2 text-sm font-mono focus:outline-none focus:border-emerald-500"
+          />
+          {error && <p className="text-sm text-red-400">{error}</p>}
+        </div>
+
+        <div className="p-6 border-t border-slate-800 flex items-center justify-between">
+          <button
+            onClick={() => save(null)}
+            disabled={saving || app.daily_quota_mins == null}
+            className="text-sm text-slate-400 hover:text-red-400 disabled:opacity-40 transition-colors"
+          >
+            Remove quota
+          </button>
+          <button
+            onClick={() => save(minutes)}
+            disabled={saving}
+            className="px-4 py-2 bg-violet-600 hover:bg-violet-500 disabled:opacity-50 rounded-xl text-sm font-bold transition-colors"
+          >
+            Save quota
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+export default QuotaModal;
@@ -0,0 +1,135 @@
+import React, { useEffect, useState } from 'react';
+import { X, Crosshair } from 'lucide-react';
+import { MatchKind, ProtectedApp } from '../types';
+import { invokePrivileged } from '../session';
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+const KINDS: { value: MatchKind; label: string; description: string }[] = [
+  { value: 'glob', label: 'Glob', description: 'Against the exe path when the pattern has a /, otherwise against the process and exe file name.' },
+  { value: 'comm', label: 'Process name', description: 'Exact name the kernel reports for the process.' },
+  { value: 'exe_path', label: 'Executable path', description: 'Exact path of the running binary.' },
+  { value: 'exe_basename', label: 'Executable name', description: 'Exact file name of the running binary.' },
+  { value: 'cmdline_regex', label: 'Command line regex', description: 'Regex against the whole command line.' },
+  { value: 'script', label: 'Script', description: 'Glob against the script, jar or .exe an interpreter or Wine was started with.' },
+  { value: 'flatpak', label: 'Flatpak', description: 'Glob against the Flatpak application ID.' },
+  { value: 'snap', label: 'Snap', description: 'Glob against the snap name.' },
+  { value: 'app_image', label: 'AppImage', description: 'Glob against the .AppImage the process runs from.' },
+];
+
+interface RuleModalProps {
+  app: ProtectedApp | null;
+  onClose: () => void;
+}
+
+/** Edit how an app is recognised among running processes */
+const RuleModal: React.FC<RuleModalProps> = ({ app, onClose }) => {
+  const [kind, setKind] = useState<MatchKind>('glob');
+  const [pattern, setPattern] = useState('');
+  const [caseSensitive, setCaseSensitive] = useState(false);
+  const [exclusions, setExclusions] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    setError(null);
+    setKind(app?.match_rule?.kind ?? 'glob');
+    setPattern(app?.match_rule?.pattern ?? '');
+    setCaseSensitive(app?.match_rule?.case_sensitive ?? false);
+    setExclusions((app?.match_rule?.exclusions ?? []).join('\n'));
+  }, [app]);
+
+  if (!app) {
+    return null;
+  }
+
+  const save = async () => {
+    setSaving(true);
+    setError(null);
+    try {
+      const matchRule = {
+        kind,
+        pattern: pattern.trim(),
+        case_sensitive: caseSensitive,
+        exclusions: exclusions.split('\n').map(line => line.trim()).filter(Boolean),
+      };
+      await invokePrivileged('update_match_rule', { id: app.id, matchRule });
+      onClose();
+    } catch (err) {
+      console.error('Error saving match rule:', err);
+      setError(errorMessage(err, 'Failed to save match rule'));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const selected = KINDS.find(option => option.value === kind);
+
+  return (
+    <div className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+      <div className="bg-slate-900 border border-slate-800 rounded-3xl shadow-2xl w-full max-w-md flex flex-col">
+        <div className="p-6 border-b border-slate-800 flex items-center justify-between">
+          <div>
+            <h2 className="text-2xl font-bold flex items-center gap-2">
+              <Crosshair className="w-6 h-6 text-emerald-400" /> Match Rule
+            </h2>
+            <p className="text-sm text-slate-400 mt-1">How {app.name} is recognised among running processes.</p>
+          </div>
+          <button onClick={onClose} className="p-2 text-slate-500 hover:text-white transition-colors">
+            <X className="w-5 h-5" />
+          </button>
+        </div>
+
+        <div className="p-6 space-y-3">
+          <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Match on</label>
+          <select
+            value={kind}
+            onChange={e => setKind(e.target.value as MatchKind)}
+            className="w-full bg-slate-800 border border-slate-700 rounded-xl px-3 py-2 text-sm focus:outline-none focus:border-emerald-500"
+          >
+            {KINDS.map(option => (
+              <option key={option.value} value={option.value}>{option.label}</option>
+            ))}
+          </select>
+          {selected && <p className="text-[10px] text-slate-500">{selected.description}</p>}
+
+          <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Pattern</label>
+          <input
+            value={pattern}
+            onChange={e => setPattern(e.target.value)}
+            className="w-full bg-slate-800 border border-slate-700 rounded-xl px-3 py-2 text-sm font-mono focus:outline-none focus:border-emerald-500"
+          />
+
+          <label className="flex items-center gap-2 text-sm">
+            <input type="checkbox" checked={caseSensitive} onChange={e => setCaseSensitive(e.target.checked)} className="accent-emerald-500" />
+            Case sensitive
+          </label>
+
+          <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Exclusions</label>
+          <textarea
+            value={exclusions}
+            onChange={e => setExclusions(e.target.value)}
+            rows={3}
+            placeholder="One glob per line, e.g. *-crash-handler"
+            className="w-full bg-slate-800 border border-slate-700 rounded-xl px-3 py-2 text-sm font-mono focus:outline-none focus:border-emerald-500"
+          />
+          <p className="text-[10px] text-slate-500">A process matching any exclusion is never treated as {app.name}.</p>
+          {error && <p className="text-sm text-red-400">{error}</p>}
+        </div>
+
+        <div className="p-6 border-t border-slate-800 flex justify-end">
+          <button
+            onClick={save}
+            disabled={saving || !pattern.trim()}
+            className="px-4 py-2 bg-emerald-600 hover:bg-emerald-500 disabled:opacity-50 rounded-xl text-sm font-bold transition-colors"
+          >
+            Save rule
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+export default RuleModal;
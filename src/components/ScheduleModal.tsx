@@ -0,0 +1,180 @@
+import React, { useEffect, useState } from 'react';
+import { X, Plus, Trash2, CalendarClock } from 'lucide-react';
+import { ProtectedApp, Schedule, ScheduleWindow, Weekday } from '../types';
+import { invokePrivileged } from '../session';
+
+const WEEKDAYS: Weekday[] = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+const defaultWindow = (): ScheduleWindow => ({
+  days: ['Mon', 'Tue', 'Wed', 'Thu', 'Fri'],
+  start: '09:00',
+  end: '17:00',
+});
+
+interface ScheduleModalProps {
+  app: ProtectedApp | null;
+  onClose: () => void;
+}
+
+/** Edit the weekly windows during which an app is blocked regardless of the shield */
+const ScheduleModal: React.FC<ScheduleModalProps> = ({ app, onClose }) => {
+  const [schedule, setSchedule] = useState<Schedule | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    setError(null);
+    setSchedule(app?.schedule ?? null);
+  }, [app]);
+
+  if (!app) {
+    return null;
+  }
+
+  const draft: Schedule = schedule ?? {
+    timezone: Intl.DateTimeFormat().resolvedOptions().timeZone,
+    windows: [defaultWindow()],
+    exceptions: [],
+  };
+
+  const update = (changes: Partial<Schedule>) => setSchedule({ ...draft, ...changes });
+
+  const updateWindow = (index: number, changes: Partial<ScheduleWindow>) =>
+    update({ windows: draft.windows.map((w, i) => (i === index ? { ...w, ...changes } : w)) });
+
+  const toggleDay = (index: number, day: Weekday) => {
+    const days = draft.windows[index].days;
+    updateWindow(index, {
+      days: days.includes(day) ? days.filter(d => d !== day) : WEEKDAYS.filter(d => d === day || days.includes(d)),
+    });
+  };
+
+  const save = async (next: Schedule | null) => {
+    setSaving(true);
+    setError(null);
+    try {
+      await invokePrivileged('set_app_schedule', { id: app.id, schedule: next });
+      onClose();
+    } catch (err) {
+      console.error('Error saving schedule:', err);
+      setError(errorMessage(err, 'Failed to save schedule'));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+      <div className="bg-slate-900 border border-slate-800 rounded-3xl shadow-2xl w-full max-w-xl max-h-[80vh] flex flex-col">
+        <div className="p-6 border-b border-slate-800 flex items-center justify-between">
+          <div>
+            <h2 className="text-2xl font-bold flex items-center gap-2">
+              <CalendarClock className="w-6 h-6 text-sky-400" /> Schedule
+            </h2>
+            <p className="text-sm text-slate-400 mt-1">{app.name} is blocked inside these windows, locked or not, and allowed outside them.</p>
+          </div>
+          <button onClick={onClose} className="p-2 text-slate-500 hover:text-white transition-colors">
+            <X className="w-5 h-5" />
+          </button>
+        </div>
+
+        <div className="p-6 space-y-6 overflow-y-auto">
+          <div>
+            <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Timezone</label>
+            <input
+              value={draft.timezone ?? ''}
+              onChange={e => update({ timezone: e.target.value || null })}
+              placeholder="System timezone"
+              className="mt-2 w-full bg-slate-800 border border-slate-700 rounded-xl px-3 py-2 text-sm font-mono focus:outline-none focus:border-emerald-500"
+            />
+          </div>
+
+          <div className="space-y-3">
+            <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Blocked windows</label>
+            {draft.windows.map((entry, index) => (
+              <div key={index} className="p-3 bg-slate-800/50 rounded-xl border border-slate-700/50 space-y-3">
+                <div className="flex flex-wrap gap-1">
+                  {WEEKDAYS.map(day => (
+                    <button
+                      key={day}
+                      onClick={() => toggleDay(index, day)}
+                      className={`px-2 py-1 rounded-lg text-xs font-bold transition-colors ${entry.days.includes(day) ? 'bg-sky-600 text-white' : 'bg-slate-700 text-slate-400'}`}
+                    >
+                      {day}
+                    </button>
+                  ))}
+                </div>
+                <div className="flex items-center gap-2">
+                  <input type="time" value={entry.start} onChange={e => updateWindow(index, { start: e.target.value })} className="bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm" />
+                  <span className="text-slate-500">to</span>
+                  <input type="time" value={entry.end} onChange={e => updateWindow(index, { end: e.target.value })} className="bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm" />
+                  <button onClick={() => update({ windows: draft.windows.filter((_, i) => i !== index) })} className="ml-auto p-1 text-slate-500 hover:text-red-400 transition-colors">
+                    <Trash2 className="w-4 h-4" />
+                  </button>
+                </div>
+              </div>
+            ))}
+            <button onClick={() => update({ windows: [...draft.windows, defaultWindow()] })} className="text-xs text-sky-400 hover:text-sky-300 flex items-center gap-1">
+              <Plus className="w-3 h-3" /> Add window
+            </button>
+          </div>
+
+          <div className="space-y-3">
+            <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Exceptions</label>
+            {draft.exceptions.map((exception, index) => (
+              <div key={index} className="flex items-center gap-2">
+                <input
+                  type="date"
+                  value={exception.date}
+                  onChange={e => update({ exceptions: draft.exceptions.map((x, i) => (i === index ? { ...x, date: e.target.value } : x)) })}
+                  className="bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm"
+                />
+                <select
+                  value={exception.blocked ? 'blocked' : 'allowed'}
+                  onChange={e => update({ exceptions: draft.exceptions.map((x, i) => (i === index ? { ...x, blocked: e.target.value === 'blocked' } : x)) })}
+                  className="bg-slate-800 border border-slate-700 rounded-lg px-2 py-1 text-sm"
+                >
+                  <option value="allowed">Allowed all day</option>
+                  <option value="blocked">Blocked all day</option>
+                </select>
+                <button onClick={() => update({ exceptions: draft.exceptions.filter((_, i) => i !== index) })} className="ml-auto p-1 text-slate-500 hover:text-red-400 transition-colors">
+                  <Trash2 className="w-4 h-4" />
+                </button>
+              </div>
+            ))}
+            <button
+              onClick={() => update({ exceptions: [...draft.exceptions, { date: new Date().toISOString().slice(0, 10), blocked: false }] })}
+              className="text-xs text-sky-400 hover:text-sky-300 flex items-center gap-1"
+            >
+              <Plus className="w-3 h-3" /> Add exception
+            </button>
+          </div>
+
+          {error && <p className="text-sm text-red-400">{error}</p>}
+        </div>
+
+        <div className="p-6 border-t border-slate-800 flex items-center justify-between">
+          <button
+            onClick={() => save(null)}
+            disabled={saving || !app.schedule}
+            className="text-sm text-slate-400 hover:text-red-400 disabled:opacity-40 transition-colors"
+          >
+            Remove schedule
+          </button>
+          <button
+            onClick={() => save(draft)}
+            disabled={saving}
+            className="px-4 py-2 bg-sky-600 hover:bg-sky-500 disabled:opacity-50 rounded-xl text-sm font-bold transition-colors"
+          >
+            Save schedule
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+export default ScheduleModal;
@@ -0,0 +1,108 @@
+import React, { useEffect, useState } from 'react';
+import { X, Skull } from 'lucide-react';
+import { ProtectedApp, TerminationStrategy } from '../types';
+import { invokePrivileged } from '../session';
+
+const errorMessage = (err: unknown, fallback: string) =>
+  typeof err === 'string' ? err : (err as { message?: string })?.message ?? fallback;
+
+const STRATEGIES: { value: TerminationStrategy; label: string; description: string }[] = [
+  { value: 'kill', label: 'Kill', description: 'SIGKILL the app and everything it started right away.' },
+  { value: 'terminate', label: 'Terminate gracefully', description: 'SIGTERM first so the app can save its state, SIGKILL whatever is left after the grace period.' },
+  { value: 'stop', label: 'Stop until unlock', description: 'SIGSTOP the app and continue it after the next unlock.' },
+  { value: 'freeze', label: 'Freeze until unlock', description: 'Freeze the app with the cgroup freezer and thaw it after the next unlock, falling back to stopping it.' },
+];
+
+interface TerminationModalProps {
+  app: ProtectedApp | null;
+  onClose: () => void;
+}
+
+/** Choose how an app is stopped while it is enforced */
+const TerminationModal: React.FC<TerminationModalProps> = ({ app, onClose }) => {
+  const [strategy, setStrategy] = useState<TerminationStrategy>('kill');
+  const [gracePeriod, setGracePeriod] = useState(5);
+  const [error, setError] = useState<string | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    setError(null);
+    setStrategy(app?.termination_strategy ?? 'kill');
+    setGracePeriod(app?.grace_period_secs ?? 5);
+  }, [app]);
+
+  if (!app) {
+    return null;
+  }
+
+  const save = async () => {
+    setSaving(true);
+    setError(null);
+    try {
+      await invokePrivileged('set_termination_strategy', { id: app.id, strategy, gracePeriodSecs: gracePeriod });
+      onClose();
+    } catch (err) {
+      console.error('Error saving termination strategy:', err);
+      setError(errorMessage(err, 'Failed to save termination strategy'));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+      <div className="bg-slate-900 border border-slate-800 rounded-3xl shadow-2xl w-full max-w-md flex flex-col">
+        <div className="p-6 border-b border-slate-800 flex items-center justify-between">
+          <div>
+            <h2 className="text-2xl font-bold flex items-center gap-2">
+              <Skull className="w-6 h-6 text-red-400" /> Enforcement Action
+            </h2>
+            <p className="text-sm text-slate-400 mt-1">What happens to {app.name} when it runs while it is blocked.</p>
+          </div>
+          <button onClick={onClose} className="p-2 text-slate-500 hover:text-white transition-colors">
+            <X className="w-5 h-5" />
+          </button>
+        </div>
+
+        <div className="p-6 space-y-3">
+          {STRATEGIES.map(option => (
+            <button
+              key={option.value}
+              onClick={() => setStrategy(option.value)}
+              className={`w-full text-left p-3 rounded-xl border transition-all ${strategy === option.value ? 'bg-red-500/10 border-red-500/40' : 'bg-slate-800/30 border-slate-700/30 hover:border-slate-600'}`}
+            >
+              <p className="text-sm font-bold">{option.label}</p>
+              <p className="text-[10px] text-slate-500">{option.description}</p>
+            </button>
+          ))}
+          {strategy === 'terminate' && (
+            <>
+              <label className="text-xs font-bold text-slate-500 uppercase tracking-widest">Grace period (seconds)</label>
+              <input
+                type="number"
+                min={1}
+                max={120}
+                value={gracePeriod}
+                onChange={e => setGracePeriod(Math.max(1, Math.min(120, Number(e.target.value) || 1)))}
+                className="w-full bg-slate-800 border border-slate-700 rounded-xl px-3 py-2 text-sm font-mono focus:outline-none focus:border-emerald-500"
+              />
+            </>
+          )}
+          {error && <p className="text-sm text-red-400">{error}</p>}
+        </div>
+
+        <div className="p-6 border-t border-slate-800 flex justify-end">
+          <button
+            onClick={save}
+            disabled={saving}
+            className="px-4 py-2 bg-red-600 hover:bg-red-500 disabled:opacity-50 rounded-xl text-sm font-bold transition-colors"
+          >
+            Save action
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+export default TerminationModal;
@@ -0,0 +1,62 @@
+import React, { useEffect, useState } from 'react';
+import { Timer, X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { UnlockGrant } from '../types';
+
+const formatRemaining = (ms: number) => {
+  const secs = Math.max(0, Math.ceil(ms / 1000));
+  const h = Math.floor(secs / 3600);
+  const m = Math.floor((secs % 3600) / 60);
+  const s = secs % 60;
+  return h > 0 ? `${h}h ${String(m).padStart(2, '0')}m` : `${m}:${String(s).padStart(2, '0')}`;
+};
+
+/** Active temporary unlocks with live countdowns */
+const UnlockGrants: React.FC<{ onRevoke?: (grant: UnlockGrant) => void }> = ({ onRevoke }) => {
+  const [grants, setGrants] = useState<UnlockGrant[]>([]);
+  const [now, setNow] = useState(Date.now());
+
+  useEffect(() => {
+    invoke<UnlockGrant[]>('get_unlock_grants')
+      .then(setGrants)
+      .catch(err => console.error('Failed to load unlock grants:', err));
+    const unlisten = listen<UnlockGrant[]>('unlock-grants', event => setGrants(event.payload));
+    const interval = setInterval(() => setNow(Date.now()), 1000);
+
+    return () => {
+      unlisten.then(fn => fn());
+      clearInterval(interval);
+    };
+  }, []);
+
+  if (grants.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="space-y-2">
+      {grants.map(grant => (
+        <div key={grant.app_id} className="flex items-center justify-between p-3 bg-amber-500/5 rounded-xl border border-amber-500/20">
+          <div className="flex items-center gap-3 min-w-0">
+            <Timer className="w-4 h-4 text-amber-400 shrink-0" />
+            <div className="min-w-0">
+              <p className="text-sm font-medium truncate">{grant.app_name}</p>
+              <p className="text-[10px] text-slate-500">{grant.kill_on_expiry ? 'Closes on expiry' : 'Stays open, relaunch blocked'}</p>
+            </div>
+          </div>
+          <div className="flex items-center gap-2">
+            <span className="text-xs font-mono font-bold text-amber-400">{formatRemaining(new Date(grant.expires_at).getTime() - now)}</span>
+            {onRevoke && (
+              <button onClick={() => onRevoke(grant)} className="p-1 text-slate-500 hover:text-red-400 transition-colors" title="Revoke">
+                <X className="w-4 h-4" />
+              </button>
+            )}
+          </div>
+        </div>
+      ))}
+    </div>
+  );
+};
+
+export default UnlockGrants;
@@ -0,0 +1,31 @@
+import { invoke, InvokeArgs } from '@tauri-apps/api/core';
+
+export interface CommandError {
+  kind: 'unauthorized' | 'failed';
+  message: string;
+}
+
+export const SESSION_EXPIRED_EVENT = 'ficha-session-expired';
+
+// Kept in memory only: a reload means authenticating again
+let sessionToken: string | null = null;
+
+export const setSessionToken = (token: string | null) => {
+  sessionToken = token;
+};
+
+export const isUnauthorized = (error: unknown): boolean =>
+  typeof error === 'object' && error !== null && (error as CommandError).kind === 'unauthorized';
+
+/** Invoke a state-changing command with the session token attached */
+export const invokePrivileged = async <T>(command: string, args: InvokeArgs = {}): Promise<T> => {
+  try {
+    return await invoke<T>(command, { ...(args as Record<string, unknown>), token: sessionToken });
+  } catch (error) {
+    if (isUnauthorized(error)) {
+      sessionToken = null;
+      window.dispatchEvent(new Event(SESSION_EXPIRED_EVENT));
+    }
+    throw error;
+  }
+};
@@ -2,7 +2,8 @@
 export enum ShieldStatus {
   LOCKED = 'LOCKED',
   ACTIVE = 'ACTIVE',
-  THREAT_DETECTED = 'THREAT_DETECTED'
+  THREAT_DETECTED = 'THREAT_DETECTED',
+  FROZEN = 'FROZEN'
 }
 
 export interface ProtectedApp {
@@ -13,16 +14,165 @@ export interface ProtectedApp {
   category: string;
   lastAttempt?: string;
   createdAt?: string;
+  icon_name?: string | null;
+  schedule?: Schedule | null;
+  daily_quota_mins?: number | null;
+  audit_only?: boolean;
+  match_rule?: MatchRule;
+  /** Pinned SHA-256 of the app's executable */
+  exe_sha256?: string | null;
+  termination_strategy?: TerminationStrategy;
+  /** Seconds between SIGTERM and SIGKILL with the terminate strategy */
+  grace_period_secs?: number;
+  /** Policy file that declares the app; it can't be changed in the app */
+  source?: string | null;
+}
+
+/** Today's remaining budget of an app with a daily quota */
+export interface QuotaStatus {
+  app_id: string;
+  app_name: string;
+  process_name: string;
+  quota_secs: number;
+  used_secs: number;
+  remaining_secs: number;
+}
+
+export type Weekday = 'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun';
+
+/** Weekly blocked window; an end at or before the start runs past midnight */
+export interface ScheduleWindow {
+  days: Weekday[];
+  start: string;
+  end: string;
+}
+
+export interface ScheduleException {
+  date: string;
+  blocked: boolean;
+}
+
+/** Enforces an app by time of day instead of by the shield */
+export interface Schedule {
+  timezone: string | null;
+  windows: ScheduleWindow[];
+  exceptions: ScheduleException[];
+}
+
+export interface ScheduleTransition {
+  process_name: string;
+  blocking: boolean;
+}
+
+/** Temporary unlock of one protected app while the shield is locked */
+export interface UnlockGrant {
+  app_id: string;
+  app_name: string;
+  process_name: string;
+  expires_at: string;
+  kill_on_expiry: boolean;
+  created_at: string;
 }
 
 export interface SecurityLog {
   id: string;
   timestamp: string;
   event: string;
-  type: 'info' | 'warning' | 'error' | 'success';
+  type: 'info' | 'warning' | 'error' | 'success' | 'audit';
   app?: string;
 }
 
+export type TerminationStrategy = 'kill' | 'terminate' | 'stop' | 'freeze';
+
+export type MatchKind =
+  | 'comm'
+  | 'exe_path'
+  | 'exe_basename'
+  | 'glob'
+  | 'cmdline_regex'
+  | 'script'
+  | 'flatpak'
+  | 'snap'
+  | 'app_image';
+
+export interface MatchRule {
+  kind: MatchKind;
+  pattern: string;
+  case_sensitive: boolean;
+  exclusions: string[];
+}
+
+export type KillReason =
+  | { kind: 'protected' }
+  /** Enforced by allowlist mode rather than as a protected app */
+  | { kind: 'unlisted' }
+  /** Root Access Prevention: sudo, su, pkexec, doas or run0 while locked */
+  | { kind: 'elevation'; cmdline: string[]; parent_pid: number; parent_name: string | null };
+
+export interface Profile {
+  id: string;
+  name: string;
+  idle_timeout_mins: number;
+  active: boolean;
+  created_at: string;
+}
+
+export interface ElevationExemption {
+  id: number;
+  command: string;
+  created_at: string;
+}
+
+export interface KillReport {
+  pid: number;
+  process_name: string;
+  pids_terminated: number;
+  strategy: TerminationStrategy;
+  escalated: boolean;
+  /** Why the process was enforced */
+  reason: KillReason;
+  /** Audit mode: the process that would have been terminated, nothing was signalled */
+  audit: { pid: number; name: string; exe_path: string | null; ppid: number; cmdline: string[] } | null;
+  /** Auto-kill is off: the process was only alerted on, nothing was signalled */
+  alert_only: boolean;
+}
+
+export type AuthMethod = 'system' | 'passphrase';
+
+export interface AuthConfig {
+  method: AuthMethod;
+  passphrase_set: boolean;
+  system_available: boolean;
+  totp_enabled: boolean;
+  pam_service: string;
+}
+
+/** Payload of the `pam-prompt` event */
+export interface PamPrompt {
+  id: string;
+  message: string;
+  echo: boolean;
+}
+
+/** Payload of the `pam-message` event */
+export interface PamMessage {
+  message: string;
+  error: boolean;
+}
+
+export interface TotpEnrollment {
+  secret: string;
+  otpauth_uri: string;
+  qr_svg: string;
+}
+
+/** Payload of the `auth-throttled` event */
+export interface ThrottleStatus {
+  remaining_secs: number;
+  failed_attempts: number;
+  locked_out: boolean;
+}
+
 export interface AIInsight {
   summary: string;
   recommendations: string[];
@@ -35,3 +185,22 @@ export interface SecurityPolicy {
   enabled: boolean;
   severity: 'low' | 'medium' | 'high';
 }
+
+export type PolicyMode = 'blocklist' | 'allowlist';
+
+/** Executable allowed while locked in allowlist mode */
+export interface AllowedExecutable {
+  id: number;
+  name: string;
+  exe_path: string;
+  source: 'manual' | 'learned';
+  created_at: string;
+}
+
+export interface AllowlistConfig {
+  mode: PolicyMode;
+  learning_until: string | null;
+  strategy: TerminationStrategy;
+  entries: AllowedExecutable[];
+  safe_processes: string[];
+}